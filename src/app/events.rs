@@ -0,0 +1,175 @@
+use crate::app::{
+    Custom3d, clear_move_highlights, move_model, spawn_capture_chaos, swap_model_template,
+};
+use crate::game_logic::chess::{
+    ChessSceneState, Color, GameEvent, GameEventSubscriber, Piece, PieceType, PromotedModelUpdate,
+    Square, game_outcome_message,
+};
+use crate::render::model::Model;
+use crate::render::renderer::RendererRenderResources;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+impl Custom3d {
+    /// Hands the scene's queued events to each subscriber in turn: the
+    /// piece models, the status message, then the registered subscribers
+    /// such as the event log. Every move goes through here, so a move
+    /// looks the same whether it was clicked, typed, or came from an
+    /// engine, a book or a puzzle line.
+    pub(super) fn dispatch_game_events(&mut self, chess_state: &mut ChessSceneState) {
+        let events = chess_state.take_events();
+        if events.is_empty() {
+            return;
+        }
+
+        let renderer_handle = Arc::clone(self.get_renderer());
+        if let Ok(mut renderer) = renderer_handle.write() {
+            let mut animator = ModelAnimator {
+                chess_state: &mut *chess_state,
+                renderer: &mut renderer,
+                piece_templates: &self.piece_templates,
+                captured_chaos: &mut self.captured_chaos,
+                capture_chaos_seed: &mut self.capture_chaos_seed,
+                moved: Vec::new(),
+            };
+            publish(&mut animator, &events);
+        }
+        publish(
+            &mut StatusMessages {
+                status: &mut chess_state.last_error,
+            },
+            &events,
+        );
+        self.publish_to_subscribers(&events);
+    }
+
+    /// Hands replayed events only to the registered subscribers; the models
+    /// already stand where the replay left them.
+    pub(super) fn publish_to_subscribers(&mut self, events: &[GameEvent]) {
+        for subscriber in &mut self.event_subscribers {
+            publish(subscriber.as_mut(), events);
+        }
+    }
+}
+
+fn publish(subscriber: &mut dyn GameEventSubscriber, events: &[GameEvent]) {
+    for event in events {
+        subscriber.on_game_event(event);
+    }
+}
+
+/// Moves the piece models to match the events of a move. The scene has
+/// already bound each model to its new square, so every event finds its
+/// models by the squares it names.
+struct ModelAnimator<'a> {
+    chess_state: &'a mut ChessSceneState,
+    renderer: &'a mut RendererRenderResources,
+    piece_templates: &'a HashMap<(PieceType, Color), Model>,
+    captured_chaos: &'a mut Vec<usize>,
+    capture_chaos_seed: &'a mut u32,
+    /// Models already sent on their way, so castling does not restart the
+    /// king's jump.
+    moved: Vec<usize>,
+}
+
+impl ModelAnimator<'_> {
+    /// Jumps the model standing on `square` there, once.
+    fn move_model_on(&mut self, square: Square) {
+        let Some(&model_index) = self.chess_state.model_by_square.get(&square) else {
+            return;
+        };
+        self.move_model_to(model_index, square);
+    }
+
+    fn move_model_to(&mut self, model_index: usize, square: Square) {
+        if self.moved.contains(&model_index) {
+            return;
+        }
+        self.moved.push(model_index);
+        let destination = self.chess_state.square_to_world(square);
+        move_model(self.renderer, model_index, destination);
+    }
+
+    fn swap_template(&mut self, model_index: usize, piece: Piece) {
+        swap_model_template(
+            self.piece_templates,
+            PromotedModelUpdate { model_index, piece },
+            self.renderer,
+        );
+    }
+}
+
+impl GameEventSubscriber for ModelAnimator<'_> {
+    fn on_game_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::MoveMade { mv, piece, .. } => {
+                clear_move_highlights(self.chess_state, self.renderer);
+                self.renderer.update_selected_model(None);
+                // An Atomic capturer still jumps to the square it blows up.
+                match self.chess_state.captured_model(mv.to, *piece) {
+                    Some(capturer) => self.move_model_to(capturer.model_index, mv.to),
+                    None => self.move_model_on(mv.to),
+                }
+            }
+            GameEvent::Captured { square, piece } => {
+                let Some(captured) = self.chess_state.captured_model(*square, *piece) else {
+                    return;
+                };
+                match captured.pocketed {
+                    Some((pocket_piece, position)) => {
+                        self.swap_template(captured.model_index, pocket_piece);
+                        move_model(self.renderer, captured.model_index, position);
+                    }
+                    None => spawn_capture_chaos(
+                        self.captured_chaos,
+                        self.capture_chaos_seed,
+                        captured.model_index,
+                        self.renderer,
+                    ),
+                }
+            }
+            GameEvent::Castled { color, kingside } => {
+                let rank = match color {
+                    Color::White => 0,
+                    Color::Black => 7,
+                };
+                let (king_file, rook_file) = if *kingside { (6, 5) } else { (2, 3) };
+                for file in [king_file, rook_file] {
+                    if let Some(square) = Square::new(file, rank) {
+                        self.move_model_on(square);
+                    }
+                }
+            }
+            GameEvent::Promoted { square, piece } => {
+                if let Some(&model_index) = self.chess_state.model_by_square.get(square) {
+                    self.swap_template(model_index, *piece);
+                }
+            }
+            GameEvent::EnPassant { .. }
+            | GameEvent::Check { .. }
+            | GameEvent::DrawClaimable(_)
+            | GameEvent::GameOver(_) => {}
+        }
+    }
+}
+
+/// Shows how the game ended, or that a draw can be claimed, in the status
+/// line.
+struct StatusMessages<'a> {
+    status: &'a mut Option<String>,
+}
+
+impl GameEventSubscriber for StatusMessages<'_> {
+    fn on_game_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::GameOver(outcome) => *self.status = Some(game_outcome_message(*outcome)),
+            GameEvent::DrawClaimable(outcome) => {
+                *self.status = Some(format!(
+                    "Draw can be claimed ({})",
+                    game_outcome_message(*outcome)
+                ));
+            }
+            _ => {}
+        }
+    }
+}
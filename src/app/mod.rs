@@ -1,6 +1,11 @@
 use crate::game_logic::chess::{
-    ChessSceneState, Color, GameState, ModelMoveUpdate, PieceType, game_outcome_message,
-    parse_piece_template_name, square_to_world,
+    BoardSetup, Chess960Generator, ChessSceneState, Color, EngineSearch, EventLog,
+    GameEventSubscriber, GameOutcome, GameState, ModelMoveUpdate, Move, MoveHighlight, OpeningBook,
+    Piece, PieceType, PromotedModelUpdate, PromotionChoiceModel, PuzzleAttempt, PuzzleSession,
+    RestoredModelUpdate, START_POSITION_FEN, SearchLimits, SearchResult, Square, ThreatMark,
+    TimeControl, UciEngine, UciEngineConfig, Variant, book_error_message, fen_error_message,
+    load_puzzles, move_error_message, notation_error_message, parse_pgn, parse_piece_template_name,
+    pgn_error_message, puzzle_error_message, square_to_world, uci_error_message,
 };
 use crate::render::animation::chaos_gravity::ChaosGravityAnimation;
 use crate::render::animation::move_jump::MoveJumpAnimation;
@@ -11,16 +16,27 @@ use crate::render::model::mesh::cube::cube_mesh_builder;
 use crate::render::model::{Model, NamedModel};
 use crate::render::renderer::{RendererCallback, RendererRenderResources};
 use eframe::{egui, egui_wgpu};
-use glam::{Vec2, Vec3};
+use glam::{Quat, Vec2, Vec3};
 use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_4;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicU8;
 use std::sync::{Arc, RwLock};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+mod events;
 mod input;
 pub mod panels;
 
+/// One step through the move history.
+#[derive(Clone, Copy)]
+enum HistoryStep {
+    /// Moves the models back directly, as an undo has no events.
+    Undo,
+    /// Replays the move's events like any other move.
+    Redo,
+}
+
 pub struct Custom3d {
     camera: Camera,
     renderer: Arc<RwLock<RendererRenderResources>>,
@@ -29,8 +45,35 @@ pub struct Custom3d {
     show_help: bool,
     prev_frame: Instant,
     chess_state: Option<ChessSceneState>,
+    fen_input: String,
+    fen_status: Option<String>,
+    move_input: String,
+    piece_templates: HashMap<(PieceType, Color), Model>,
     captured_chaos: Vec<usize>,
     capture_chaos_seed: u32,
+    /// Models taken off the board in setup mode, reused for the next
+    /// pieces placed rather than growing the renderer's list.
+    parked_setup_models: Vec<usize>,
+    computer_color: Option<Color>,
+    computer_think_ms: u64,
+    engine_search: Option<EngineSearch>,
+    engine_search_ply: usize,
+    /// A history step still to take, one ply per finished animation, to
+    /// get back to the human's turn against the computer.
+    pending_history_step: Option<HistoryStep>,
+    last_engine_result: Option<SearchResult>,
+    uci_engine_path: String,
+    uci_engine_options: String,
+    uci_engine: Option<UciEngine>,
+    opening_book_path: String,
+    opening_book: Option<OpeningBook>,
+    chess960_generator: Chess960Generator,
+    chess_variant: Variant,
+    time_control: Option<TimeControl>,
+    show_threat_overlay: bool,
+    event_subscribers: Vec<Box<dyn GameEventSubscriber>>,
+    puzzle_path: String,
+    puzzle_session: Option<PuzzleSession>,
 }
 
 impl Custom3d {
@@ -53,8 +96,31 @@ impl Custom3d {
             show_help: false,
             prev_frame: Instant::now(),
             chess_state: None,
+            fen_input: START_POSITION_FEN.to_owned(),
+            fen_status: None,
+            move_input: String::new(),
+            piece_templates: HashMap::new(),
             captured_chaos: Vec::new(),
+            parked_setup_models: Vec::new(),
             capture_chaos_seed: 1,
+            computer_color: None,
+            computer_think_ms: 1000,
+            engine_search: None,
+            engine_search_ply: 0,
+            pending_history_step: None,
+            last_engine_result: None,
+            uci_engine_path: std::env::var("RENDERING_UCI_ENGINE").unwrap_or_default(),
+            uci_engine_options: String::new(),
+            uci_engine: None,
+            opening_book_path: std::env::var("RENDERING_OPENING_BOOK").unwrap_or_default(),
+            opening_book: None,
+            chess960_generator: Chess960Generator::new(chess960_seed()),
+            chess_variant: Variant::Standard,
+            time_control: None,
+            show_threat_overlay: false,
+            event_subscribers: event_subscribers(),
+            puzzle_path: std::env::var("RENDERING_PUZZLES").unwrap_or_default(),
+            puzzle_session: None,
         })
     }
 
@@ -90,6 +156,261 @@ impl Custom3d {
         self.chess_state.as_ref()
     }
 
+    pub fn get_fen_input_mut(&mut self) -> &mut String {
+        &mut self.fen_input
+    }
+
+    pub fn get_fen_status(&self) -> Option<&str> {
+        self.fen_status.as_deref()
+    }
+
+    pub fn set_fen_status(&mut self, fen_status: Option<String>) {
+        self.fen_status = fen_status;
+    }
+
+    pub fn get_move_input_mut(&mut self) -> &mut String {
+        &mut self.move_input
+    }
+
+    pub fn get_computer_color(&self) -> Option<Color> {
+        self.computer_color
+    }
+
+    pub fn set_computer_color(&mut self, computer_color: Option<Color>) {
+        if self.computer_color != computer_color {
+            self.engine_search = None;
+        }
+        self.computer_color = computer_color;
+    }
+
+    pub fn get_computer_think_ms_mut(&mut self) -> &mut u64 {
+        &mut self.computer_think_ms
+    }
+
+    pub fn is_computer_thinking(&self) -> bool {
+        self.engine_search.is_some()
+    }
+
+    pub fn get_last_engine_result(&self) -> Option<&SearchResult> {
+        self.last_engine_result.as_ref()
+    }
+
+    pub fn get_uci_engine_path_mut(&mut self) -> &mut String {
+        &mut self.uci_engine_path
+    }
+
+    pub fn get_uci_engine_options_mut(&mut self) -> &mut String {
+        &mut self.uci_engine_options
+    }
+
+    pub fn get_uci_engine(&self) -> Option<&UciEngine> {
+        self.uci_engine.as_ref()
+    }
+
+    /// Starts the configured external engine; the computer opponent uses it
+    /// instead of the built-in search until it is disconnected.
+    pub fn connect_uci_engine(&mut self) {
+        let path = self.uci_engine_path.trim();
+        if path.is_empty() {
+            return;
+        }
+
+        self.engine_search = None;
+        self.uci_engine = Some(UciEngine::start(UciEngineConfig {
+            path: PathBuf::from(path),
+            args: Vec::new(),
+            options: UciEngineConfig::parse_options(&self.uci_engine_options),
+        }));
+    }
+
+    pub fn disconnect_uci_engine(&mut self) {
+        self.engine_search = None;
+        self.uci_engine = None;
+    }
+
+    /// Rules for games loaded from the center panel.
+    pub fn get_chess_variant(&self) -> Variant {
+        self.chess_variant
+    }
+
+    pub fn set_chess_variant(&mut self, chess_variant: Variant) {
+        self.chess_variant = chess_variant;
+    }
+
+    pub fn get_time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    /// Restarts the clocks of the current game with `time_control`, which
+    /// also applies to games loaded later; `None` plays untimed.
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>) {
+        if self.time_control == time_control {
+            return;
+        }
+        self.time_control = time_control;
+        if let Some(chess_state) = &mut self.chess_state {
+            chess_state.set_time_control(time_control, Instant::now());
+        }
+    }
+
+    pub fn get_show_threat_overlay(&self) -> bool {
+        self.show_threat_overlay
+    }
+
+    pub fn set_show_threat_overlay(&mut self, show_threat_overlay: bool) {
+        self.show_threat_overlay = show_threat_overlay;
+    }
+
+    pub fn get_opening_book_path_mut(&mut self) -> &mut String {
+        &mut self.opening_book_path
+    }
+
+    pub fn get_opening_book(&self) -> Option<&OpeningBook> {
+        self.opening_book.as_ref()
+    }
+
+    /// Reads the Polyglot book at the configured path; a read error is shown
+    /// as the chess status.
+    pub fn load_opening_book(&mut self) {
+        let path = self.opening_book_path.trim();
+        if path.is_empty() {
+            return;
+        }
+
+        match OpeningBook::load(Path::new(path)) {
+            Ok(book) => self.opening_book = Some(book),
+            Err(err) => {
+                if let Some(chess_state) = &mut self.chess_state {
+                    chess_state.last_error = Some(book_error_message(&err));
+                }
+            }
+        }
+    }
+
+    pub fn unload_opening_book(&mut self) {
+        self.opening_book = None;
+    }
+
+    pub fn get_puzzle_path_mut(&mut self) -> &mut String {
+        &mut self.puzzle_path
+    }
+
+    pub fn get_puzzle_session(&self) -> Option<&PuzzleSession> {
+        self.puzzle_session.as_ref()
+    }
+
+    /// Reads the EPD or CSV puzzle file at the configured path and sets up
+    /// its first puzzle. The computer opponent is switched off; the
+    /// puzzle line plays the other side.
+    pub fn load_puzzles(&mut self) {
+        let path = self.puzzle_path.trim();
+        if path.is_empty() {
+            return;
+        }
+
+        match load_puzzles(Path::new(path)) {
+            Ok(puzzles) => {
+                self.computer_color = None;
+                self.puzzle_session = Some(PuzzleSession::new(puzzles));
+                self.show_current_puzzle();
+            }
+            Err(err) => self.set_fen_status(Some(puzzle_error_message(&err))),
+        }
+    }
+
+    /// Gives up on the current puzzle, if unsolved, and moves to the next.
+    pub fn next_puzzle(&mut self) {
+        if let Some(puzzle_session) = &mut self.puzzle_session {
+            puzzle_session.advance();
+            self.show_current_puzzle();
+        }
+    }
+
+    pub fn stop_puzzles(&mut self) {
+        self.puzzle_session = None;
+    }
+
+    fn show_current_puzzle(&mut self) {
+        let Some(puzzle_session) = &self.puzzle_session else {
+            return;
+        };
+        let Some(puzzle) = puzzle_session.current() else {
+            if let Some(chess_state) = &mut self.chess_state {
+                chess_state.last_error = Some(format!(
+                    "All {} puzzles done: {} solved, {} failed",
+                    puzzle_session.len(),
+                    puzzle_session.solved_count(),
+                    puzzle_session.failed_count()
+                ));
+            }
+            return;
+        };
+
+        let game_state = puzzle.position.clone();
+        let loaded = Self::resolve_chess_scene_path()
+            .and_then(|path| self.load_chess_scene(&path, game_state));
+        if let Err(err) = loaded {
+            self.set_fen_status(Some(err));
+        }
+    }
+
+    /// Whether the puzzle line is about to play the opponent's move, which
+    /// keeps the player's clicks off the board until it has.
+    fn is_puzzle_reply_pending(&self) -> bool {
+        self.puzzle_session
+            .as_ref()
+            .is_some_and(|puzzle_session| puzzle_session.next_reply().is_some())
+    }
+
+    /// Plays the opponent's next move of the puzzle line once the previous
+    /// move has finished animating.
+    fn update_puzzle_replies(&mut self) {
+        let Some(reply) = self
+            .puzzle_session
+            .as_ref()
+            .and_then(PuzzleSession::next_reply)
+        else {
+            return;
+        };
+        let Some(chess_state) = &self.chess_state else {
+            return;
+        };
+        if chess_state.setup.is_some()
+            || chess_state.game_outcome.is_some()
+            || self.is_move_animation_in_progress()
+        {
+            return;
+        }
+
+        if let Some(puzzle_session) = &mut self.puzzle_session {
+            puzzle_session.reply_played();
+        }
+        self.play_computer_move(reply);
+    }
+
+    /// Checks a move against the puzzle line when it is the solver's turn.
+    /// A wrong move is turned down with a message and `false`.
+    fn check_puzzle_move(&mut self, chess_state: &mut ChessSceneState, chess_move: Move) -> bool {
+        let Some(puzzle_session) = &mut self.puzzle_session else {
+            return true;
+        };
+        if !puzzle_session.is_solver_to_move() {
+            return true;
+        }
+
+        match puzzle_session.try_move(&chess_state.game_state, chess_move) {
+            PuzzleAttempt::Wrong => {
+                let notation = chess_state
+                    .game_state
+                    .move_to_san(chess_move)
+                    .unwrap_or_else(|_| chess_move.to_string());
+                chess_state.last_error = Some(format!("{notation} is not the solution, try again"));
+                false
+            }
+            PuzzleAttempt::Illegal | PuzzleAttempt::Correct | PuzzleAttempt::Solved => true,
+        }
+    }
+
     pub fn set_selected_model(&mut self, selected_model: Option<usize>) {
         self.selected_model = selected_model;
     }
@@ -190,7 +511,11 @@ impl Custom3d {
         ))
     }
 
-    pub fn load_chess_scene(&mut self, file_path: &Path) -> Result<(), String> {
+    pub fn load_chess_scene(
+        &mut self,
+        file_path: &Path,
+        game_state: GameState,
+    ) -> Result<(), String> {
         let (device, queue) = {
             let renderer = self
                 .get_renderer()
@@ -241,7 +566,6 @@ impl Custom3d {
                 .world_bounds()
                 .ok_or("Board object has no vertices")?;
 
-            let game_state = GameState::new_start_position();
             let mut model_by_square = HashMap::new();
             let mut square_by_model = HashMap::new();
 
@@ -263,14 +587,34 @@ impl Custom3d {
                 square_by_model.insert(model_index, square);
             }
 
-            ChessSceneState::new(
+            let mut chess_state = ChessSceneState::new(
                 game_state,
                 board_model_index,
                 board_min,
                 board_max,
                 model_by_square,
                 square_by_model,
-            )
+            );
+            for color in [Color::White, Color::Black] {
+                let pocket = chess_state.game_state.pocket(color).collect::<Vec<_>>();
+                for (piece_type, count) in pocket {
+                    let piece = Piece { piece_type, color };
+                    let Some(template) = piece_templates.get(&(piece_type, color)) else {
+                        continue;
+                    };
+                    for _ in 0..count {
+                        let model_index = renderer.get_models().len();
+                        let mut transform = Transform::default();
+                        transform.set_position(chess_state.push_pocket_model(model_index, piece));
+                        renderer
+                            .get_models_mut()
+                            .push(template.instance_with_transform(&device, transform));
+                    }
+                }
+            }
+            chess_state.update_game_outcome();
+            chess_state.set_time_control(self.time_control, Instant::now());
+            chess_state
         };
 
         self.camera.frame_board_top_down_orthographic(
@@ -284,15 +628,68 @@ impl Custom3d {
             renderer.update_selected_model(None);
         }
         self.chess_state = Some(chess_state);
+        self.piece_templates = piece_templates;
         self.captured_chaos.clear();
+        self.parked_setup_models.clear();
         self.capture_chaos_seed = 1;
+        self.engine_search = None;
+        self.last_engine_result = None;
 
         Ok(())
     }
 
     pub fn import_chess_scene(&mut self) -> Result<(), String> {
+        self.puzzle_session = None;
+        let path = Self::resolve_chess_scene_path()?;
+        let game_state = GameState::new_start_position().with_variant(self.chess_variant);
+        self.load_chess_scene(&path, game_state)
+    }
+
+    /// Starts a game from the next Chess960 position of the seeded
+    /// generator.
+    pub fn import_chess960_scene(&mut self) -> Result<(), String> {
+        self.puzzle_session = None;
+        let number = self.chess960_generator.next_position();
+        let path = Self::resolve_chess_scene_path()?;
+        let game_state = GameState::new_chess960(number).with_variant(self.chess_variant);
+        self.load_chess_scene(&path, game_state)
+    }
+
+    pub fn import_chess_scene_from_fen(&mut self, fen: &str) -> Result<(), String> {
+        self.puzzle_session = None;
+        let game_state = GameState::from_fen(fen)
+            .map_err(|err| fen_error_message(&err))?
+            .with_variant(self.chess_variant);
+        let path = Self::resolve_chess_scene_path()?;
+        self.load_chess_scene(&path, game_state)
+    }
+
+    pub fn import_chess_scene_from_pgn(&mut self, pgn: &str) -> Result<(), String> {
+        self.puzzle_session = None;
+        let games = parse_pgn(pgn).map_err(|err| pgn_error_message(&err))?;
+        let game = games.into_iter().next().ok_or("PGN contains no game")?;
+        let mut replayed = Vec::new();
+        let (final_state, _) = game
+            .replay_with_events(|event| replayed.push(event.clone()))
+            .map_err(|err| pgn_error_message(&err))?;
+
         let path = Self::resolve_chess_scene_path()?;
-        self.load_chess_scene(&path)
+        self.load_chess_scene(&path, final_state)?;
+        if let Some(chess_state) = self.chess_state.as_mut() {
+            chess_state
+                .restore_pgn_history(&game)
+                .map_err(|err| pgn_error_message(&err))?;
+        }
+        self.publish_to_subscribers(&replayed);
+        Ok(())
+    }
+
+    pub fn export_chess_pgn(&self) -> Result<String, String> {
+        let chess_state = self.get_chess_state().ok_or("No chess game loaded")?;
+        chess_state
+            .to_pgn_game()
+            .to_pgn()
+            .map_err(|err| pgn_error_message(&err))
     }
 
     pub fn custom_painting(&mut self, ui: &mut egui::Ui) {
@@ -383,6 +780,12 @@ impl Custom3d {
             return;
         };
 
+        if chess_state.setup.is_some() {
+            self.handle_setup_click(&mut chess_state, closest_model, hit_point);
+            self.chess_state = Some(chess_state);
+            return;
+        }
+
         if chess_state.game_outcome.is_some() {
             self.set_selected_model(None);
             if let Ok(mut renderer) = self.get_renderer().write() {
@@ -394,15 +797,32 @@ impl Custom3d {
             return;
         }
 
-        if self.is_move_animation_in_progress() {
+        if self.is_move_animation_in_progress()
+            || self.is_computer_to_move(&chess_state)
+            || self.is_puzzle_reply_pending()
+        {
             self.chess_state = Some(chess_state);
             return;
         }
 
         chess_state.clear_last_error();
 
+        if chess_state.pending_promotion.is_some() {
+            let promotion_move = closest_model
+                .and_then(|model_index| chess_state.try_pick_promotion_model(model_index));
+            if let Some(chess_move) = promotion_move {
+                self.commit_chess_move(&mut chess_state, chess_move);
+            } else {
+                self.cancel_promotion(&mut chess_state);
+            }
+            self.chess_state = Some(chess_state);
+            return;
+        }
+
         if let Some(model_index) = closest_model {
-            if chess_state.try_select_piece_model(model_index).is_some() {
+            if chess_state.try_select_piece_model(model_index).is_some()
+                || chess_state.try_select_pocket_model(model_index).is_some()
+            {
                 self.set_selected_model(Some(model_index));
                 if let Ok(mut renderer) = self.get_renderer().write() {
                     update_move_highlights(&mut chess_state, &mut renderer);
@@ -413,41 +833,26 @@ impl Custom3d {
             }
 
             if let Some(chess_move) = chess_state.try_build_click_move(model_index, hit_point) {
-                match chess_state.game_state.apply_move(chess_move) {
-                    Ok(()) => {
-                        let update =
-                            chess_state.apply_mapping_after_move(chess_move.from, chess_move.to);
-                        let renderer_handle = Arc::clone(self.get_renderer());
-                        if let Ok(mut renderer) = renderer_handle.write() {
-                            self.apply_move_to_models(update, &mut renderer);
-                            clear_move_highlights(&mut chess_state, &mut renderer);
-                            renderer.update_selected_model(None);
-                        }
-
-                        let side_to_move = chess_state.game_state.side_to_move();
-                        if chess_state.game_state.is_checkmate(side_to_move) {
-                            let winner = side_to_move.opposite();
-                            let outcome =
-                                crate::game_logic::chess::GameOutcome::Checkmate { winner };
-                            chess_state.game_outcome = Some(outcome);
-                            chess_state.last_error = Some(game_outcome_message(outcome));
-                        } else if chess_state.game_state.is_stalemate(side_to_move) {
-                            let outcome = crate::game_logic::chess::GameOutcome::Stalemate;
-                            chess_state.game_outcome = Some(outcome);
-                            chess_state.last_error = Some(game_outcome_message(outcome));
-                        }
-
-                        chess_state.clear_selection();
-                        self.set_selected_model(None);
+                let is_legal_promotion = chess_state
+                    .game_state
+                    .is_promotion_move(chess_move.from, chess_move.to)
+                    && chess_state
+                        .game_state
+                        .legal_moves_from(chess_move.from)
+                        .contains(&chess_move.to);
+
+                if is_legal_promotion {
+                    chess_state.pending_promotion = Some(chess_move);
+                    if let Ok(mut renderer) = self.get_renderer().write() {
+                        clear_move_highlights(&mut chess_state, &mut renderer);
+                        show_promotion_picker(
+                            &mut chess_state,
+                            &mut renderer,
+                            &self.piece_templates,
+                        );
                     }
-                    Err(err) => {
-                        chess_state.last_error =
-                            Some(crate::game_logic::chess::move_error_message(err));
-                    }
-                }
-
-                if let Ok(mut renderer) = self.get_renderer().write() {
-                    update_move_highlights(&mut chess_state, &mut renderer);
+                } else {
+                    self.commit_chess_move(&mut chess_state, chess_move);
                 }
 
                 self.chess_state = Some(chess_state);
@@ -464,77 +869,478 @@ impl Custom3d {
         self.chess_state = Some(chess_state);
     }
 
-    fn is_model_in_capture_chaos(&self, model_index: usize) -> bool {
-        self.captured_chaos.contains(&model_index)
+    pub fn choose_promotion(&mut self, piece_type: PieceType) {
+        let Some(mut chess_state) = self.chess_state.take() else {
+            return;
+        };
+
+        if let Some(pending) = chess_state.pending_promotion {
+            chess_state.clear_last_error();
+            self.commit_chess_move(
+                &mut chess_state,
+                Move::with_promotion(pending.from, pending.to, piece_type),
+            );
+        }
+
+        self.chess_state = Some(chess_state);
     }
 
-    fn is_move_animation_in_progress(&self) -> bool {
-        let Ok(renderer) = self.get_renderer().read() else {
-            return false;
+    pub fn claim_draw(&mut self) {
+        self.end_chess_game(|chess_state, _| chess_state.claim_draw());
+    }
+
+    pub fn resign(&mut self) {
+        self.end_chess_game(|chess_state, color| chess_state.resign(color));
+    }
+
+    /// Offers a draw for the player at the board. The computer never takes
+    /// a draw and turns the offer down straight away.
+    pub fn offer_draw(&mut self) {
+        let Some(mut chess_state) = self.chess_state.take() else {
+            return;
         };
 
-        renderer
-            .get_models()
-            .iter()
-            .any(|model| model.has_active_blocking_animation())
+        let color = self.human_color(&chess_state);
+        if chess_state.offer_draw(color) && self.computer_color == Some(color.opposite()) {
+            chess_state.decline_draw(color.opposite());
+            chess_state.last_error = Some("Computer declines the draw".to_owned());
+        }
+        self.chess_state = Some(chess_state);
     }
 
-    fn apply_move_to_models(
+    /// Answers the standing draw offer for the side it was made to.
+    pub fn answer_draw_offer(&mut self, accept: bool) {
+        if accept {
+            self.end_chess_game(|chess_state, _| {
+                let offered_to = chess_state.draw_offer?.opposite();
+                chess_state.accept_draw(offered_to)
+            });
+        } else if let Some(chess_state) = &mut self.chess_state
+            && let Some(offered_by) = chess_state.draw_offer
+        {
+            chess_state.decline_draw(offered_by.opposite());
+        }
+    }
+
+    /// The side the player at the board controls: the side to move in a
+    /// two-player game, otherwise the side the computer is not playing.
+    fn human_color(&self, chess_state: &ChessSceneState) -> Color {
+        self.computer_color
+            .map_or(chess_state.game_state.side_to_move(), Color::opposite)
+    }
+
+    /// Ends the game with whatever `end` decides for the player at the
+    /// board, dropping any selection or pending promotion.
+    fn end_chess_game(
         &mut self,
-        update: Option<ModelMoveUpdate>,
-        renderer: &mut RendererRenderResources,
+        end: impl FnOnce(&mut ChessSceneState, Color) -> Option<GameOutcome>,
     ) {
-        let Some(update) = update else {
+        let Some(mut chess_state) = self.chess_state.take() else {
             return;
         };
 
-        if let Some(captured_model_index) = update.captured_model_index {
-            self.spawn_capture_chaos(captured_model_index, renderer);
+        let color = self.human_color(&chess_state);
+        if end(&mut chess_state, color).is_some() {
+            self.cancel_promotion(&mut chess_state);
         }
+        self.chess_state = Some(chess_state);
+    }
 
-        for moved in update.moved_models {
-            if let Some(model) = renderer.get_models_mut().get_mut(moved.model_index) {
-                let base_transform = model.get_transform();
-                let start_position = base_transform.get_position();
-                model
-                    .get_transform_mut()
-                    .set_position(moved.destination_world_position);
-
-                if start_position.distance(moved.destination_world_position) > 0.001 {
-                    model.set_animation(Some(Box::new(MoveJumpAnimation::new(
-                        base_transform,
-                        moved.destination_world_position,
-                    ))));
-                } else {
-                    model.set_animation(None);
+    pub fn play_notation_move(&mut self, text: &str) {
+        let Some(mut chess_state) = self.chess_state.take() else {
+            return;
+        };
+
+        chess_state.clear_last_error();
+        if chess_state.game_outcome.is_some()
+            || chess_state.setup.is_some()
+            || self.is_move_animation_in_progress()
+            || self.is_computer_to_move(&chess_state)
+            || self.is_puzzle_reply_pending()
+        {
+            self.chess_state = Some(chess_state);
+            return;
+        }
+
+        match chess_state.game_state.parse_move(text) {
+            Ok(chess_move) => self.commit_chess_move(&mut chess_state, chess_move),
+            Err(err) => chess_state.last_error = Some(notation_error_message(&err)),
+        }
+        self.chess_state = Some(chess_state);
+    }
+
+    /// Switches the board into setup mode.
+    pub fn begin_chess_setup(&mut self) {
+        let Some(mut chess_state) = self.chess_state.take() else {
+            return;
+        };
+
+        if self.is_move_animation_in_progress() {
+            self.chess_state = Some(chess_state);
+            return;
+        }
+        self.cancel_promotion(&mut chess_state);
+        chess_state.clear_last_error();
+        chess_state.begin_setup(Instant::now());
+        self.engine_search = None;
+        self.puzzle_session = None;
+        self.chess_state = Some(chess_state);
+    }
+
+    /// Replaces the palette choice and the side to move, castling and en
+    /// passant settings of the position in setup mode.
+    pub fn set_chess_setup(&mut self, setup: BoardSetup, palette: Option<Piece>) {
+        if let Some(chess_state) = &mut self.chess_state
+            && chess_state.setup.is_some()
+        {
+            chess_state.setup = Some(setup);
+            chess_state.setup_palette = palette;
+        }
+    }
+
+    pub fn clear_chess_setup(&mut self) {
+        let Some(chess_state) = &mut self.chess_state else {
+            return;
+        };
+
+        let removed_models = chess_state.clear_setup_board();
+        if let Ok(mut renderer) = self.renderer.write() {
+            for model_index in removed_models {
+                park_model(&mut renderer, model_index);
+                self.parked_setup_models.push(model_index);
+            }
+        }
+    }
+
+    /// Validates the edited position and, if it is playable, reloads the
+    /// scene with it as a new game.
+    pub fn finish_chess_setup(&mut self) {
+        let Some(chess_state) = &mut self.chess_state else {
+            return;
+        };
+
+        chess_state.clear_last_error();
+        let Some(game_state) = chess_state.finish_setup() else {
+            return;
+        };
+        let loaded = Self::resolve_chess_scene_path()
+            .and_then(|path| self.load_chess_scene(&path, game_state));
+        if let Err(err) = loaded {
+            self.set_fen_status(Some(err));
+        }
+    }
+
+    /// Places or removes the palette piece on the clicked square, parking
+    /// removed models and reusing parked ones for placed pieces.
+    fn handle_setup_click(
+        &mut self,
+        chess_state: &mut ChessSceneState,
+        closest_model: Option<usize>,
+        hit_point: Option<Vec3>,
+    ) {
+        let Some(edit) = closest_model
+            .and_then(|model_index| chess_state.setup_click_square(model_index, hit_point))
+            .and_then(|square| chess_state.edit_setup_square(square))
+        else {
+            return;
+        };
+
+        let Ok(mut renderer) = self.renderer.write() else {
+            return;
+        };
+        if let Some(model_index) = edit.removed_model {
+            park_model(&mut renderer, model_index);
+            self.parked_setup_models.push(model_index);
+        }
+        if let Some(piece) = edit.placed
+            && let Some(template) = self.piece_templates.get(&(piece.piece_type, piece.color))
+        {
+            let device = renderer.get_wgpu_render_state().device.clone();
+            let mut transform = Transform::default();
+            transform.set_position(chess_state.square_to_world(edit.square));
+            let instance = template.instance_with_transform(&device, transform);
+            let model_index = match self.parked_setup_models.pop() {
+                Some(model_index) => {
+                    renderer.get_models_mut()[model_index] = instance;
+                    model_index
                 }
+                None => {
+                    renderer.get_models_mut().push(instance);
+                    renderer.get_models().len() - 1
+                }
+            };
+            chess_state.bind_model(edit.square, model_index);
+        }
+    }
+
+    pub fn undo_chess_move(&mut self) {
+        self.step_chess_history(HistoryStep::Undo);
+    }
+
+    pub fn redo_chess_move(&mut self) {
+        self.step_chess_history(HistoryStep::Redo);
+    }
+
+    /// Steps through history once. Against the computer, a step that
+    /// leaves the computer to move queues another for when the animation
+    /// has finished, so the steps play one after another instead of
+    /// piling their animations onto the same models.
+    fn step_chess_history(&mut self, step: HistoryStep) {
+        self.pending_history_step = None;
+        let Some(mut chess_state) = self.chess_state.take() else {
+            return;
+        };
+
+        if self.is_move_animation_in_progress()
+            || chess_state.setup.is_some()
+            || self.puzzle_session.is_some()
+        {
+            self.chess_state = Some(chess_state);
+            return;
+        }
+
+        if chess_state.pending_promotion.is_some() {
+            self.cancel_promotion(&mut chess_state);
+        }
+        chess_state.clear_last_error();
+
+        let stepped = match step {
+            HistoryStep::Undo => self.undo_models(&mut chess_state),
+            HistoryStep::Redo => {
+                let redone = chess_state.redo_move();
+                self.dispatch_game_events(&mut chess_state);
+                redone
+            }
+        };
+        if stepped {
+            self.set_selected_model(None);
+
+            if self.is_computer_to_move(&chess_state) {
+                self.pending_history_step = Some(step);
             }
         }
+        chess_state.sync_clock(Instant::now());
+
+        self.chess_state = Some(chess_state);
+    }
+
+    /// Takes back the last move and moves its models back. Returns whether
+    /// there was one to take back.
+    fn undo_models(&mut self, chess_state: &mut ChessSceneState) -> bool {
+        let Some(mut update) = chess_state.undo_move() else {
+            return false;
+        };
+        let restored_models = std::mem::take(&mut update.restored_models);
+        let renderer_handle = Arc::clone(self.get_renderer());
+        if let Ok(mut renderer) = renderer_handle.write() {
+            self.restore_captured_models(chess_state, restored_models, &mut renderer);
+            self.apply_move_to_models(update, &mut renderer);
+            clear_move_highlights(chess_state, &mut renderer);
+            renderer.update_selected_model(None);
+        }
+        true
     }
 
-    fn spawn_capture_chaos(
+    /// Drops pieces brought back by an undo onto their squares, creating a
+    /// fresh model when the piece was captured before the scene was loaded.
+    fn restore_captured_models(
         &mut self,
-        captured_model_index: usize,
+        chess_state: &mut ChessSceneState,
+        restored_models: Vec<RestoredModelUpdate>,
         renderer: &mut RendererRenderResources,
     ) {
-        self.captured_chaos
-            .retain(|idx| *idx != captured_model_index);
+        let drop_height = (chess_state.board_max - chess_state.board_min).length() * 0.25;
+
+        for restored in restored_models {
+            let destination = chess_state.square_to_world(restored.square);
+            let mut start_transform = Transform::default();
+            start_transform.set_position(destination + Vec3::new(0.0, drop_height, 0.0));
+
+            let model_index = match restored.model_index {
+                Some(model_index) => {
+                    self.captured_chaos.retain(|idx| *idx != model_index);
+                    model_index
+                }
+                None => {
+                    let Some(template) = self
+                        .piece_templates
+                        .get(&(restored.piece.piece_type, restored.piece.color))
+                    else {
+                        continue;
+                    };
+                    let device = renderer.get_wgpu_render_state().device.clone();
+                    let model_index = renderer.get_models().len();
+                    renderer
+                        .get_models_mut()
+                        .push(template.instance_with_transform(&device, start_transform));
+                    chess_state.bind_model(restored.square, model_index);
+                    model_index
+                }
+            };
+
+            if let Some(model) = renderer.get_models_mut().get_mut(model_index) {
+                *model.get_transform_mut() = start_transform;
+                model.get_transform_mut().set_position(destination);
+                model.set_animation(Some(Box::new(MoveJumpAnimation::new(
+                    start_transform,
+                    destination,
+                ))));
+            }
+        }
+    }
 
-        let Some(model) = renderer.get_models_mut().get_mut(captured_model_index) else {
+    fn is_computer_to_move(&self, chess_state: &ChessSceneState) -> bool {
+        chess_state.setup.is_none()
+            && self.puzzle_session.is_none()
+            && self.computer_color == Some(chess_state.game_state.side_to_move())
+    }
+
+    /// Starts a search when the computer is to move and plays its result
+    /// once the background thread reports back. A search is dropped when
+    /// the position it was started from no longer matches the game.
+    fn update_computer_opponent(&mut self) {
+        let Some(chess_state) = self.get_chess_state() else {
+            self.engine_search = None;
             return;
         };
 
-        let seed = self
-            .capture_chaos_seed
-            .wrapping_mul(1_664_525)
-            .wrapping_add((captured_model_index as u32).wrapping_mul(1_013_904_223));
-        self.capture_chaos_seed = self.capture_chaos_seed.wrapping_add(1);
-        let base_transform = model.get_transform();
-        model.set_animation(Some(Box::new(ChaosGravityAnimation::new(
-            base_transform,
-            seed,
-        ))));
-        self.captured_chaos.push(captured_model_index);
+        let ply = chess_state.game_state.move_history().len();
+        let should_search = self.is_computer_to_move(chess_state)
+            && self.pending_history_step.is_none()
+            && chess_state.game_outcome.is_none()
+            && chess_state.pending_promotion.is_none();
+
+        if let Some(engine_search) = &self.engine_search {
+            if !should_search || self.engine_search_ply != ply {
+                self.engine_search = None;
+                return;
+            }
+
+            let Some(result) = engine_search.try_result() else {
+                return;
+            };
+            self.engine_search = None;
+            match result {
+                Ok(result) => {
+                    let best_move = result.best_move;
+                    self.last_engine_result = Some(result);
+                    if let Some(chess_move) = best_move {
+                        self.play_computer_move(chess_move);
+                    }
+                }
+                Err(err) => {
+                    self.uci_engine = None;
+                    self.computer_color = None;
+                    if let Some(chess_state) = self.chess_state.as_mut() {
+                        chess_state.last_error = Some(uci_error_message(&err));
+                    }
+                }
+            }
+            return;
+        }
+
+        if should_search && !self.is_move_animation_in_progress() {
+            let state = chess_state.game_state.clone();
+            let limits = SearchLimits::move_time(Duration::from_millis(self.computer_think_ms));
+            self.engine_search = Some(match &self.uci_engine {
+                Some(uci_engine) => EngineSearch::start_external(uci_engine, state, limits),
+                None => EngineSearch::start(state, limits),
+            });
+            self.engine_search_ply = ply;
+        }
+    }
+
+    fn play_computer_move(&mut self, chess_move: Move) {
+        let Some(mut chess_state) = self.chess_state.take() else {
+            return;
+        };
+
+        chess_state.clear_last_error();
+        self.commit_chess_move(&mut chess_state, chess_move);
+        self.chess_state = Some(chess_state);
+    }
+
+    fn cancel_promotion(&mut self, chess_state: &mut ChessSceneState) {
+        chess_state.pending_promotion = None;
+        chess_state.clear_selection();
+        self.set_selected_model(None);
+        if let Ok(mut renderer) = self.get_renderer().write() {
+            hide_promotion_picker(chess_state, &mut renderer);
+            clear_move_highlights(chess_state, &mut renderer);
+            renderer.update_selected_model(None);
+        }
+    }
+
+    fn commit_chess_move(&mut self, chess_state: &mut ChessSceneState, chess_move: Move) {
+        chess_state.pending_promotion = None;
+        if let Ok(mut renderer) = self.get_renderer().write() {
+            hide_promotion_picker(chess_state, &mut renderer);
+        }
+
+        if !self.check_puzzle_move(chess_state, chess_move) {
+            self.cancel_promotion(chess_state);
+            return;
+        }
+
+        match chess_state.play_move(chess_move, Instant::now()) {
+            Ok(()) => {
+                self.dispatch_game_events(chess_state);
+                if self
+                    .puzzle_session
+                    .as_ref()
+                    .is_some_and(PuzzleSession::is_solved)
+                {
+                    chess_state.last_error = Some("Puzzle solved".to_owned());
+                }
+                chess_state.clear_selection();
+                self.set_selected_model(None);
+            }
+            Err(err) => {
+                chess_state.last_error = Some(move_error_message(err));
+            }
+        }
+
+        if let Ok(mut renderer) = self.get_renderer().write() {
+            update_move_highlights(chess_state, &mut renderer);
+        }
+    }
+
+    fn is_model_in_capture_chaos(&self, model_index: usize) -> bool {
+        self.captured_chaos.contains(&model_index)
+    }
+
+    fn is_move_animation_in_progress(&self) -> bool {
+        let Ok(renderer) = self.get_renderer().read() else {
+            return false;
+        };
+
+        renderer
+            .get_models()
+            .iter()
+            .any(|model| model.has_active_blocking_animation())
+    }
+
+    /// Moves the models back for an undone move.
+    fn apply_move_to_models(
+        &self,
+        update: ModelMoveUpdate,
+        renderer: &mut RendererRenderResources,
+    ) {
+        for swapped in update
+            .promoted_model
+            .into_iter()
+            .chain(update.recolored_models)
+        {
+            swap_model_template(&self.piece_templates, swapped, renderer);
+        }
+
+        for moved in update.moved_models {
+            move_model(
+                renderer,
+                moved.model_index,
+                moved.destination_world_position,
+            );
+        }
     }
 
     fn update_capture_chaos(&mut self, renderer: &mut RendererRenderResources) {
@@ -578,6 +1384,38 @@ impl eframe::App for Custom3d {
         ctx.input(|i| {
             self.handle_input(i, &delta_time);
         });
+        if let Some(mut chess_state) = self.chess_state.take() {
+            chess_state.update_clock(curr_frame);
+            self.dispatch_game_events(&mut chess_state);
+            {
+                let mut renderer = self.renderer.write().unwrap();
+                if self.show_threat_overlay && chess_state.setup.is_none() {
+                    update_threat_overlay(&mut chess_state, &mut renderer);
+                } else if chess_state.threat_overlay_key.is_some() {
+                    clear_threat_overlay(&mut chess_state, &mut renderer);
+                }
+            }
+            self.chess_state = Some(chess_state);
+        }
+        if let Some(step) = self.pending_history_step
+            && !self.is_move_animation_in_progress()
+        {
+            self.step_chess_history(step);
+        }
+        self.update_computer_opponent();
+        self.update_puzzle_replies();
+        if !ctx.wants_keyboard_input() {
+            let (undo, redo) = ctx.input(|i| {
+                let z = i.modifiers.command && i.key_pressed(egui::Key::Z);
+                let y = i.modifiers.command && i.key_pressed(egui::Key::Y);
+                (z && !i.modifiers.shift, y || (z && i.modifiers.shift))
+            });
+            if undo {
+                self.undo_chess_move();
+            } else if redo {
+                self.redo_chess_move();
+            }
+        }
         self.top_panel(&delta_time, ctx);
         if self.get_show_help() {
             self.help(ctx);
@@ -588,62 +1426,260 @@ impl eframe::App for Custom3d {
     }
 }
 
+/// `RENDERING_EVENT_LOG` prints every game event to stderr.
+fn event_subscribers() -> Vec<Box<dyn GameEventSubscriber>> {
+    let mut subscribers: Vec<Box<dyn GameEventSubscriber>> = Vec::new();
+    if std::env::var_os("RENDERING_EVENT_LOG").is_some() {
+        subscribers.push(Box::new(EventLog::new(std::io::stderr())));
+    }
+    subscribers
+}
+
+/// `RENDERING_CHESS960_SEED` makes the sequence of Chess960 games
+/// reproducible; otherwise it follows the clock.
+fn chess960_seed() -> u64 {
+    std::env::var("RENDERING_CHESS960_SEED")
+        .ok()
+        .and_then(|seed| seed.trim().parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        })
+}
+
 fn update_move_highlights(
     chess_state: &mut ChessSceneState,
     renderer: &mut RendererRenderResources,
 ) {
-    let Some(from) = chess_state.selected_square else {
-        clear_move_highlights(chess_state, renderer);
+    clear_move_highlights(chess_state, renderer);
+    if chess_state.selected_square.is_none() && chess_state.selected_drop.is_none() {
         return;
-    };
+    }
+
+    let mut highlights = HashMap::<Square, MoveHighlight>::new();
+    for legal_move in chess_state.game_state.legal_moves() {
+        if !chess_state.is_selected_move(legal_move.mv) {
+            continue;
+        }
+        let highlight = MoveHighlight::for_move(&legal_move);
+        highlights
+            .entry(legal_move.mv.to)
+            .and_modify(|current| *current = (*current).max(highlight))
+            .or_insert(highlight);
+    }
 
-    let legal_targets = chess_state.game_state.legal_moves_from(from);
     let board_min = chess_state.board_min;
     let board_max = chess_state.board_max;
     let square_width = (board_max.x - board_min.x) / 8.0;
     let square_depth = (board_max.z - board_min.z) / 8.0;
 
-    let highlight_scale = Vec3::new(square_width * 0.65, 0.04, square_depth * 0.65);
+    let mut used_per_style = HashMap::<MoveHighlight, usize>::new();
+    for (square, highlight) in highlights {
+        let world = chess_state.square_to_world(square);
+        let position = Vec3::new(world.x, board_max.y + 0.02, world.z);
+        let (color, footprint, height) = highlight_style(highlight);
+        let scale = Vec3::new(square_width * footprint, height, square_depth * footprint);
+        let rotation = match highlight {
+            MoveHighlight::Special => Quat::from_rotation_y(FRAC_PI_4),
+            _ => Quat::IDENTITY,
+        };
+
+        let used = used_per_style.entry(highlight).or_default();
+        let pool = chess_state.highlight_models.entry(highlight).or_default();
+        show_marker_model(renderer, pool, *used, (position, scale, rotation), color);
+        *used += 1;
+    }
+}
+
+/// Moves the `used`-th model of a marker pool to `position` and `scale`,
+/// first adding a cube of `color` turned by `rotation` when the pool has
+/// run out.
+fn show_marker_model(
+    renderer: &mut RendererRenderResources,
+    pool: &mut Vec<usize>,
+    used: usize,
+    (position, scale, rotation): (Vec3, Vec3, Quat),
+    color: (f32, f32, f32),
+) {
+    if let Some(model_index) = pool.get(used) {
+        if let Some(model) = renderer.get_models_mut().get_mut(*model_index) {
+            model.get_transform_mut().set_position(position);
+            *model.get_transform_mut().get_scale_mut() = scale;
+        }
+        return;
+    }
 
     let (device, queue) = (
         renderer.get_wgpu_render_state().device.clone(),
         renderer.get_wgpu_render_state().queue.clone(),
     );
+    let mut transform = Transform::default().rotation(rotation);
+    transform.set_position(position);
+    *transform.get_scale_mut() = scale;
+
+    let marker_model = cube_mesh_builder()
+        .build(&device)
+        .to_model(&device, &queue, color, transform);
+    pool.push(renderer.get_models().len());
+    renderer.get_models_mut().push(marker_model);
+}
+
+/// Redraws the threat overlay when the position has changed since it was
+/// last drawn.
+fn update_threat_overlay(
+    chess_state: &mut ChessSceneState,
+    renderer: &mut RendererRenderResources,
+) {
+    let key = chess_state.game_state.zobrist_key();
+    if chess_state.threat_overlay_key == Some(key) {
+        return;
+    }
+    clear_threat_overlay(chess_state, renderer);
+    chess_state.threat_overlay_key = Some(key);
+
+    let board_min = chess_state.board_min;
+    let board_max = chess_state.board_max;
+    let square_width = (board_max.x - board_min.x) / 8.0;
+    let square_depth = (board_max.z - board_min.z) / 8.0;
 
-    for (idx, square) in legal_targets.iter().copied().enumerate() {
+    let mut used_per_mark = HashMap::<ThreatMark, usize>::new();
+    for (square, mark) in chess_state.threat_marks() {
         let world = chess_state.square_to_world(square);
+        let (color, footprint, height) = threat_mark_style(mark);
+        let position = Vec3::new(world.x, board_max.y + height / 2.0, world.z);
+        let scale = Vec3::new(square_width * footprint, height, square_depth * footprint);
 
-        if idx >= chess_state.highlight_model_indices.len() {
-            let mut transform = Transform::default();
-            transform.set_position(Vec3::new(world.x, board_max.y + 0.02, world.z));
-            *transform.get_scale_mut() = highlight_scale;
-
-            let highlight_model = cube_mesh_builder().build(&device).to_model(
-                &device,
-                &queue,
-                (0.15, 0.9, 0.25),
-                transform,
-            );
+        let used = used_per_mark.entry(mark).or_default();
+        let pool = chess_state.threat_overlay_models.entry(mark).or_default();
+        show_marker_model(
+            renderer,
+            pool,
+            *used,
+            (position, scale, Quat::IDENTITY),
+            color,
+        );
+        *used += 1;
+    }
+}
 
-            let model_index = renderer.get_models().len();
-            renderer.get_models_mut().push(highlight_model);
-            chess_state.highlight_model_indices.push(model_index);
-        } else if let Some(model) = renderer
-            .get_models_mut()
-            .get_mut(chess_state.highlight_model_indices[idx])
-        {
-            model
-                .get_transform_mut()
-                .set_position(Vec3::new(world.x, board_max.y + 0.02, world.z));
-            *model.get_transform_mut().get_scale_mut() = highlight_scale;
-        }
+/// Color, share of the square covered and height. Control tints cover the
+/// whole square just above the board; hanging and pinned pieces stand on a
+/// slightly raised plate, and pin lines are marked by small tiles.
+fn threat_mark_style(mark: ThreatMark) -> ((f32, f32, f32), f32, f32) {
+    match mark {
+        ThreatMark::WhiteControl => ((0.3, 0.55, 1.0), 0.98, 0.004),
+        ThreatMark::BlackControl => ((1.0, 0.35, 0.3), 0.98, 0.004),
+        ThreatMark::Contested => ((0.65, 0.4, 0.85), 0.98, 0.004),
+        ThreatMark::Hanging => ((1.0, 0.85, 0.1), 0.75, 0.012),
+        ThreatMark::Pinned => ((0.95, 0.2, 0.85), 0.75, 0.012),
+        ThreatMark::PinLine => ((0.95, 0.2, 0.85), 0.25, 0.012),
     }
+}
 
-    for hidden_idx in legal_targets.len()..chess_state.highlight_model_indices.len() {
-        if let Some(model) = renderer
-            .get_models_mut()
-            .get_mut(chess_state.highlight_model_indices[hidden_idx])
-        {
+fn clear_threat_overlay(chess_state: &mut ChessSceneState, renderer: &mut RendererRenderResources) {
+    for model_index in chess_state
+        .threat_overlay_models
+        .values()
+        .flatten()
+        .copied()
+    {
+        park_model(renderer, model_index);
+    }
+    chess_state.threat_overlay_key = None;
+}
+
+/// Color, share of the square covered and height. Quiet moves and checks
+/// get a small tile, captures a wide plate showing around the target piece,
+/// and special moves a raised diamond.
+fn highlight_style(highlight: MoveHighlight) -> ((f32, f32, f32), f32, f32) {
+    match highlight {
+        MoveHighlight::Quiet => ((0.15, 0.9, 0.25), 0.4, 0.04),
+        MoveHighlight::Check => ((1.0, 0.6, 0.1), 0.4, 0.04),
+        MoveHighlight::Capture => ((0.9, 0.15, 0.15), 0.9, 0.02),
+        MoveHighlight::Special => ((0.2, 0.45, 1.0), 0.5, 0.08),
+    }
+}
+
+/// Jumps a model from where it stands to `destination`.
+fn move_model(renderer: &mut RendererRenderResources, model_index: usize, destination: Vec3) {
+    let Some(model) = renderer.get_models_mut().get_mut(model_index) else {
+        return;
+    };
+    let base_transform = model.get_transform();
+    let start_position = base_transform.get_position();
+    model.get_transform_mut().set_position(destination);
+
+    if start_position.distance(destination) > 0.001 {
+        model.set_animation(Some(Box::new(MoveJumpAnimation::new(
+            base_transform,
+            destination,
+        ))));
+    } else {
+        model.set_animation(None);
+    }
+}
+
+/// Replaces a model's mesh with the template for its new piece, keeping
+/// its transform.
+fn swap_model_template(
+    piece_templates: &HashMap<(PieceType, Color), Model>,
+    swapped: PromotedModelUpdate,
+    renderer: &mut RendererRenderResources,
+) {
+    let Some(template) = piece_templates.get(&(swapped.piece.piece_type, swapped.piece.color))
+    else {
+        return;
+    };
+    let device = renderer.get_wgpu_render_state().device.clone();
+    if let Some(model) = renderer.get_models_mut().get_mut(swapped.model_index) {
+        let transform = *model.get_transform_mut();
+        *model = template.instance_with_transform(&device, transform);
+    }
+}
+
+/// Sends a captured model tumbling off the board; `update_capture_chaos`
+/// parks it once the animation ends.
+fn spawn_capture_chaos(
+    captured_chaos: &mut Vec<usize>,
+    capture_chaos_seed: &mut u32,
+    captured_model_index: usize,
+    renderer: &mut RendererRenderResources,
+) {
+    captured_chaos.retain(|idx| *idx != captured_model_index);
+
+    let Some(model) = renderer.get_models_mut().get_mut(captured_model_index) else {
+        return;
+    };
+
+    let seed = capture_chaos_seed
+        .wrapping_mul(1_664_525)
+        .wrapping_add((captured_model_index as u32).wrapping_mul(1_013_904_223));
+    *capture_chaos_seed = capture_chaos_seed.wrapping_add(1);
+    let base_transform = model.get_transform();
+    model.set_animation(Some(Box::new(ChaosGravityAnimation::new(
+        base_transform,
+        seed,
+    ))));
+    captured_chaos.push(captured_model_index);
+}
+
+/// Moves a model that no longer stands for a piece out of sight.
+fn park_model(renderer: &mut RendererRenderResources, model_index: usize) {
+    if let Some(model) = renderer.get_models_mut().get_mut(model_index) {
+        model.set_animation(None);
+        model
+            .get_transform_mut()
+            .set_position(Vec3::new(0.0, -1000.0, 0.0));
+    }
+}
+
+fn clear_move_highlights(
+    chess_state: &mut ChessSceneState,
+    renderer: &mut RendererRenderResources,
+) {
+    for model_index in chess_state.highlight_models.values().flatten().copied() {
+        if let Some(model) = renderer.get_models_mut().get_mut(model_index) {
             model
                 .get_transform_mut()
                 .set_position(Vec3::new(0.0, -1000.0, 0.0));
@@ -651,12 +1687,57 @@ fn update_move_highlights(
     }
 }
 
-fn clear_move_highlights(
+fn show_promotion_picker(
     chess_state: &mut ChessSceneState,
     renderer: &mut RendererRenderResources,
+    piece_templates: &HashMap<(PieceType, Color), Model>,
 ) {
-    for model_index in chess_state.highlight_model_indices.iter().copied() {
-        if let Some(model) = renderer.get_models_mut().get_mut(model_index) {
+    let Some(pending) = chess_state.pending_promotion else {
+        return;
+    };
+    let Some(color) = chess_state
+        .game_state
+        .piece_at(pending.from)
+        .map(|piece| piece.color)
+    else {
+        return;
+    };
+
+    let device = renderer.get_wgpu_render_state().device.clone();
+
+    for (slot, piece_type) in PieceType::PROMOTION_CHOICES.into_iter().enumerate() {
+        let piece = Piece { piece_type, color };
+        let position = chess_state.promotion_choice_world(pending.to, slot);
+
+        if let Some(model_index) = chess_state.promotion_choice_model(piece) {
+            if let Some(model) = renderer.get_models_mut().get_mut(model_index) {
+                model.get_transform_mut().set_position(position);
+            }
+            continue;
+        }
+
+        let Some(template) = piece_templates.get(&(piece_type, color)) else {
+            continue;
+        };
+
+        let mut transform = Transform::default();
+        transform.set_position(position);
+        let model_index = renderer.get_models().len();
+        renderer
+            .get_models_mut()
+            .push(template.instance_with_transform(&device, transform));
+        chess_state
+            .promotion_choice_models
+            .push(PromotionChoiceModel { model_index, piece });
+    }
+}
+
+fn hide_promotion_picker(
+    chess_state: &mut ChessSceneState,
+    renderer: &mut RendererRenderResources,
+) {
+    for choice in chess_state.promotion_choice_models.iter() {
+        if let Some(model) = renderer.get_models_mut().get_mut(choice.model_index) {
             model
                 .get_transform_mut()
                 .set_position(Vec3::new(0.0, -1000.0, 0.0));
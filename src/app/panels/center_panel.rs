@@ -1,4 +1,5 @@
 use crate::app::Custom3d;
+use crate::game_logic::chess::Variant;
 use crate::render::buffers::camera::CameraProjection;
 use crate::render::buffers::transform::Transform;
 use crate::render::model::Model;
@@ -56,6 +57,7 @@ impl Custom3d {
             });
             let button = ui.button("Add model");
             let chess_button = ui.button("Load chess board");
+            let chess960_button = ui.button("New Chess960 game");
             ui.add(Label::new(format!(
                 "Loading {} models",
                 self.get_loading()
@@ -99,6 +101,87 @@ impl Custom3d {
             {
                 eprintln!("{err}");
             }
+            if chess960_button.clicked()
+                && let Err(err) = self.import_chess960_scene()
+            {
+                eprintln!("{err}");
+            }
+            ui.horizontal(|ui| {
+                ui.label("Variant:");
+                let mut chess_variant = self.get_chess_variant();
+                for variant in Variant::ALL {
+                    ui.radio_value(&mut chess_variant, variant, variant.name());
+                }
+                self.set_chess_variant(chess_variant);
+            });
+            ui.horizontal(|ui| {
+                ui.label("FEN:");
+                ui.text_edit_singleline(self.get_fen_input_mut());
+                if ui.button("Load FEN").clicked() {
+                    let fen = self.get_fen_input_mut().clone();
+                    match self.import_chess_scene_from_fen(&fen) {
+                        Ok(()) => self.set_fen_status(None),
+                        Err(err) => {
+                            eprintln!("{err}");
+                            self.set_fen_status(Some(err));
+                        }
+                    }
+                }
+                let fen = self
+                    .get_chess_state()
+                    .map(|state| state.game_state.to_fen());
+                if ui
+                    .add_enabled(fen.is_some(), egui::Button::new("Copy FEN"))
+                    .clicked()
+                    && let Some(fen) = fen
+                {
+                    ui.ctx().copy_text(fen.clone());
+                    *self.get_fen_input_mut() = fen;
+                    self.set_fen_status(Some("FEN copied to clipboard".to_owned()));
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Load PGN").clicked()
+                    && let Some(file) = rfd::FileDialog::new()
+                        .add_filter("pgn", &["pgn"])
+                        .pick_file()
+                {
+                    let result = std::fs::read_to_string(&file)
+                        .map_err(|err| format!("Failed to read {}: {err}", file.display()))
+                        .and_then(|pgn| self.import_chess_scene_from_pgn(&pgn));
+                    match result {
+                        Ok(()) => self.set_fen_status(None),
+                        Err(err) => {
+                            eprintln!("{err}");
+                            self.set_fen_status(Some(err));
+                        }
+                    }
+                }
+                let can_save = self.get_chess_state().is_some();
+                if ui
+                    .add_enabled(can_save, egui::Button::new("Save PGN"))
+                    .clicked()
+                    && let Some(file) = rfd::FileDialog::new()
+                        .add_filter("pgn", &["pgn"])
+                        .set_file_name("game.pgn")
+                        .save_file()
+                {
+                    let result = self.export_chess_pgn().and_then(|pgn| {
+                        std::fs::write(&file, pgn)
+                            .map_err(|err| format!("Failed to write {}: {err}", file.display()))
+                    });
+                    match result {
+                        Ok(()) => self.set_fen_status(Some(format!("Saved {}", file.display()))),
+                        Err(err) => {
+                            eprintln!("{err}");
+                            self.set_fen_status(Some(err));
+                        }
+                    }
+                }
+            });
+            if let Some(status) = self.get_fen_status() {
+                ui.label(status);
+            }
             egui::Frame::canvas(ui.style())
                 .fill(egui::Color32::from_gray(50))
                 .show(ui, |ui| {
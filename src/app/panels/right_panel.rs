@@ -1,6 +1,9 @@
 use crate::app::Custom3d;
-use crate::game_logic::chess::{Color, GameOutcome};
+use crate::game_logic::chess::{
+    BoardSetup, Color, GameOutcome, Piece, PieceType, SearchResult, Square, TimeControl, Variant,
+};
 use eframe::egui;
+use std::time::{Duration, Instant};
 
 impl Custom3d {
     pub fn right_panel(&mut self, ctx: &egui::Context) {
@@ -93,6 +96,34 @@ impl Custom3d {
                         renderer.set_outline(None);
                     }
 
+                    let mut promotion_choice = None;
+                    let mut claim_draw = false;
+                    let mut setup_action = None;
+                    let mut setup_edit = None;
+                    let mut resign = false;
+                    let mut offer_draw = false;
+                    let mut draw_answer = None;
+                    let mut undo = false;
+                    let mut redo = false;
+                    let mut submit_move = false;
+                    let mut move_input = self.get_move_input_mut().clone();
+                    let mut time_control = self.get_time_control();
+                    let mut show_threat_overlay = self.get_show_threat_overlay();
+                    let mut computer_color = self.get_computer_color();
+                    let mut computer_think_ms = *self.get_computer_think_ms_mut();
+                    let computer_thinking = self.is_computer_thinking();
+                    let engine_summary = self.get_last_engine_result().map(engine_result_text);
+                    let mut uci_engine_path = self.get_uci_engine_path_mut().clone();
+                    let mut uci_engine_options = self.get_uci_engine_options_mut().clone();
+                    let uci_engine_label = self
+                        .get_uci_engine()
+                        .map(|engine| format!("Engine: {}", engine.config().path.display()));
+                    let mut uci_engine_action = None;
+                    let mut opening_book_path = self.get_opening_book_path_mut().clone();
+                    let mut opening_book_action = None;
+                    let mut book_move = None;
+                    let mut puzzle_path = self.get_puzzle_path_mut().clone();
+                    let mut puzzle_action = None;
                     if let Some(chess_state) = self.get_chess_state() {
                         ui.separator();
                         if let Some(outcome) = chess_state.game_outcome {
@@ -104,6 +135,61 @@ impl Custom3d {
                                     winner: Color::Black,
                                 } => ui.label("Game over: checkmate (Black wins)"),
                                 GameOutcome::Stalemate => ui.label("Game over: stalemate (draw)"),
+                                GameOutcome::FiftyMoveRule => {
+                                    ui.label("Game over: fifty-move rule (draw)")
+                                }
+                                GameOutcome::ThreefoldRepetition => {
+                                    ui.label("Game over: threefold repetition (draw)")
+                                }
+                                GameOutcome::FivefoldRepetition => {
+                                    ui.label("Game over: fivefold repetition (draw)")
+                                }
+                                GameOutcome::SeventyFiveMoveRule => {
+                                    ui.label("Game over: 75-move rule (draw)")
+                                }
+                                GameOutcome::InsufficientMaterial => {
+                                    ui.label("Game over: insufficient material (draw)")
+                                }
+                                GameOutcome::KingOfTheHill {
+                                    winner: Color::White,
+                                } => ui.label("Game over: king on the hill (White wins)"),
+                                GameOutcome::KingOfTheHill {
+                                    winner: Color::Black,
+                                } => ui.label("Game over: king on the hill (Black wins)"),
+                                GameOutcome::ThreeCheck {
+                                    winner: Color::White,
+                                } => ui.label("Game over: third check (White wins)"),
+                                GameOutcome::ThreeCheck {
+                                    winner: Color::Black,
+                                } => ui.label("Game over: third check (Black wins)"),
+                                GameOutcome::KingExploded {
+                                    winner: Color::White,
+                                } => ui.label("Game over: king exploded (White wins)"),
+                                GameOutcome::KingExploded {
+                                    winner: Color::Black,
+                                } => ui.label("Game over: king exploded (Black wins)"),
+                                GameOutcome::Timeout {
+                                    winner: Color::White,
+                                } => ui.label("Game over: Black flagged (White wins)"),
+                                GameOutcome::Timeout {
+                                    winner: Color::Black,
+                                } => ui.label("Game over: White flagged (Black wins)"),
+                                GameOutcome::TimeoutVsInsufficientMaterial => {
+                                    ui.label("Game over: flag fell, no mating material (draw)")
+                                }
+                                GameOutcome::Resignation {
+                                    winner: Color::White,
+                                } => ui.label("Game over: Black resigned (White wins)"),
+                                GameOutcome::Resignation {
+                                    winner: Color::Black,
+                                } => ui.label("Game over: White resigned (Black wins)"),
+                                GameOutcome::Forfeit {
+                                    winner: Color::White,
+                                } => ui.label("Game over: Black forfeited (White wins)"),
+                                GameOutcome::Forfeit {
+                                    winner: Color::Black,
+                                } => ui.label("Game over: White forfeited (Black wins)"),
+                                GameOutcome::DrawByAgreement => ui.label("Game over: draw agreed"),
                             };
                         } else {
                             let side_to_move = match chess_state.game_state.side_to_move() {
@@ -112,19 +198,478 @@ impl Custom3d {
                             };
                             ui.label(format!("Chess turn: {side_to_move}"));
                         }
+                        ui.label(format!(
+                            "Move {} (halfmove clock {})",
+                            chess_state.game_state.fullmove_number(),
+                            chess_state.game_state.halfmove_clock()
+                        ));
+                        if let Some(number) = chess_state.initial_state.chess960_position() {
+                            ui.label(format!("Chess960 position {number}"));
+                        }
+                        let game_state = &chess_state.game_state;
+                        match game_state.variant() {
+                            Variant::Standard => {}
+                            Variant::KingOfTheHill => {
+                                ui.label("King of the Hill: reach d4, e4, d5 or e5");
+                            }
+                            Variant::Atomic => {
+                                ui.label("Atomic: captures explode; kings cannot capture");
+                            }
+                            Variant::Crazyhouse => {
+                                ui.label(format!(
+                                    "Crazyhouse pockets: White {}, Black {}",
+                                    game_state.pocket_text(Color::White),
+                                    game_state.pocket_text(Color::Black)
+                                ));
+                            }
+                            Variant::ThreeCheck => {
+                                ui.label(format!(
+                                    "Three-check: White needs {}, Black needs {} more checks",
+                                    game_state.checks_remaining(Color::White),
+                                    game_state.checks_remaining(Color::Black)
+                                ));
+                            }
+                        }
                         if let Some(square) = chess_state.selected_square {
-                            let file = (b'a' + square.file()) as char;
-                            let rank = square.rank() + 1;
-                            ui.label(format!("Selected: {file}{rank}"));
+                            ui.label(format!("Selected: {square}"));
+                            let game_state = &chess_state.game_state;
+                            let opponent = game_state.side_to_move().opposite();
+                            ui.label(format!(
+                                "Attacked by: {}; defended by: {}",
+                                squares_text(&game_state.attackers(square, opponent)),
+                                squares_text(&game_state.defenders(square))
+                            ));
+                        } else if let Some(piece_type) = chess_state.selected_drop {
+                            ui.label(format!("Selected: {piece_type:?} drop"));
                         } else {
                             ui.label("Selected: none");
                         }
 
+                        ui.checkbox(&mut show_threat_overlay, "Threat overlay")
+                            .on_hover_text(
+                                "Tint squares by the side controlling them; \
+                                 mark hanging (yellow) and pinned (magenta) pieces",
+                            );
+
+                        match &chess_state.setup {
+                            Some(setup) => {
+                                let mut setup = setup.clone();
+                                let mut palette = chess_state.setup_palette;
+                                setup_panel(ui, &mut setup, &mut palette);
+                                ui.horizontal(|ui| {
+                                    if ui.button("Clear board").clicked() {
+                                        setup_action = Some(SetupAction::Clear);
+                                    }
+                                    if ui.button("Start game").clicked() {
+                                        setup_action = Some(SetupAction::Finish);
+                                    }
+                                });
+                                setup_edit = Some((setup, palette));
+                            }
+                            None => {
+                                if ui.button("Set up position").clicked() {
+                                    setup_action = Some(SetupAction::Begin);
+                                }
+                            }
+                        }
+
+                        if let Some(clock) = &chess_state.clock {
+                            let now = Instant::now();
+                            ui.label(format!("Clock: {}", clock.time_control()));
+                            for (color, name) in [(Color::White, "White"), (Color::Black, "Black")]
+                            {
+                                let running = if clock.running() == Some(color) {
+                                    " *"
+                                } else {
+                                    ""
+                                };
+                                ui.label(format!(
+                                    "{name} clock: {}{running}",
+                                    clock_text(clock.remaining(color, now))
+                                ));
+                            }
+                        }
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label("Time control:");
+                            ui.radio_value(&mut time_control, None, "Untimed");
+                            for preset in TimeControl::PRESETS {
+                                ui.radio_value(&mut time_control, Some(preset), preset.to_string());
+                            }
+                        });
+
+                        if chess_state.game_outcome.is_none()
+                            && chess_state.game_state.claimable_draw().is_some()
+                            && ui.button("Claim draw").clicked()
+                        {
+                            claim_draw = true;
+                        }
+                        if chess_state.game_outcome.is_none() && chess_state.setup.is_none() {
+                            ui.horizontal(|ui| {
+                                if ui.button("Resign").clicked() {
+                                    resign = true;
+                                }
+                                if ui
+                                    .add_enabled(
+                                        chess_state.draw_offer.is_none(),
+                                        egui::Button::new("Offer draw"),
+                                    )
+                                    .clicked()
+                                {
+                                    offer_draw = true;
+                                }
+                            });
+                        }
+                        if let Some(offered_by) = chess_state.draw_offer {
+                            let name = match offered_by {
+                                Color::White => "White",
+                                Color::Black => "Black",
+                            };
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{name} offers a draw"));
+                                if computer_color != Some(offered_by.opposite()) {
+                                    if ui.button("Accept").clicked() {
+                                        draw_answer = Some(true);
+                                    }
+                                    if ui.button("Decline").clicked() {
+                                        draw_answer = Some(false);
+                                    }
+                                }
+                            });
+                        }
+
+                        ui.radio_value(&mut computer_color, None, "Two players");
+                        ui.radio_value(
+                            &mut computer_color,
+                            Some(Color::Black),
+                            "Play as White vs computer",
+                        );
+                        ui.radio_value(
+                            &mut computer_color,
+                            Some(Color::White),
+                            "Play as Black vs computer",
+                        );
+                        if computer_color.is_some() {
+                            ui.add(
+                                egui::Slider::new(&mut computer_think_ms, 100..=10_000)
+                                    .text("Think time (ms)"),
+                            );
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("UCI engine:");
+                            ui.text_edit_singleline(&mut uci_engine_path);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Options:");
+                            ui.text_edit_singleline(&mut uci_engine_options)
+                                .on_hover_text("Name=value pairs, e.g. Threads=2, Hash=64");
+                        });
+                        ui.horizontal(|ui| match &uci_engine_label {
+                            Some(label) => {
+                                ui.label(label);
+                                if ui.button("Disconnect").clicked() {
+                                    uci_engine_action = Some(false);
+                                }
+                            }
+                            None => {
+                                ui.label("Engine: built-in");
+                                if ui.button("Connect UCI engine").clicked() {
+                                    uci_engine_action = Some(true);
+                                }
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Opening book:");
+                            ui.text_edit_singleline(&mut opening_book_path)
+                                .on_hover_text("Polyglot .bin file");
+                        });
+                        let book_moves = self
+                            .get_opening_book()
+                            .map(|book| (book.entry_count(), book.moves(&chess_state.game_state)));
+                        ui.horizontal(|ui| match &book_moves {
+                            Some((entries, _)) => {
+                                ui.label(format!("Book: {entries} entries"));
+                                if ui.button("Unload").clicked() {
+                                    opening_book_action = Some(false);
+                                }
+                            }
+                            None => {
+                                ui.label("Book: none");
+                                if ui.button("Load book").clicked() {
+                                    opening_book_action = Some(true);
+                                }
+                            }
+                        });
+                        if let Some((_, moves)) = &book_moves
+                            && !moves.is_empty()
+                        {
+                            let total_weight =
+                                moves.iter().map(|book| book.weight as u32).sum::<u32>();
+                            ui.horizontal_wrapped(|ui| {
+                                ui.label("Book moves:");
+                                for book in moves {
+                                    let san = chess_state
+                                        .game_state
+                                        .move_to_san(book.mv)
+                                        .unwrap_or_else(|_| book.mv.to_string());
+                                    let share = book.weight as u32 * 100 / total_weight;
+                                    if ui.button(format!("{san} ({share}%)")).clicked() {
+                                        book_move = Some(book.mv);
+                                    }
+                                }
+                            });
+                        }
+
+                        if computer_thinking {
+                            ui.label("Computer is thinking...");
+                        }
+                        if let Some(summary) = &engine_summary {
+                            ui.label(summary);
+                        }
+
+                        ui.horizontal(|ui| {
+                            let game_state = &chess_state.game_state;
+                            if ui
+                                .add_enabled(game_state.can_undo(), egui::Button::new("Undo"))
+                                .clicked()
+                            {
+                                undo = true;
+                            }
+                            if ui
+                                .add_enabled(game_state.can_redo(), egui::Button::new("Redo"))
+                                .clicked()
+                            {
+                                redo = true;
+                            }
+                        });
+
+                        if chess_state.pending_promotion.is_some() {
+                            ui.label("Promote to:");
+                            ui.horizontal(|ui| {
+                                for piece_type in PieceType::PROMOTION_CHOICES {
+                                    if ui.button(format!("{piece_type:?}")).clicked() {
+                                        promotion_choice = Some(piece_type);
+                                    }
+                                }
+                            });
+                        }
+
+                        ui.horizontal(|ui| {
+                            let response = ui.text_edit_singleline(&mut move_input);
+                            let entered = response.lost_focus()
+                                && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                            if ui.button("Play").clicked() || entered {
+                                submit_move = true;
+                            }
+                        });
+
                         if let Some(err) = &chess_state.last_error {
                             ui.label(format!("Move: {err}"));
                         }
+
+                        let move_list = chess_state.move_list_text();
+                        if !move_list.is_empty() {
+                            ui.label(move_list);
+                        }
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Puzzles:");
+                        ui.text_edit_singleline(&mut puzzle_path)
+                            .on_hover_text("EPD with bm opcodes, or Lichess CSV");
+                    });
+                    match self.get_puzzle_session() {
+                        Some(puzzle_session) => {
+                            if let Some(puzzle) = puzzle_session.current() {
+                                let rating = puzzle
+                                    .rating
+                                    .map(|rating| format!(", rating {rating}"))
+                                    .unwrap_or_default();
+                                ui.label(format!(
+                                    "Puzzle {}/{} ({}{rating}): {:?} to play",
+                                    puzzle_session.number(),
+                                    puzzle_session.len(),
+                                    puzzle.id,
+                                    puzzle.solver()
+                                ));
+                            }
+                            ui.label(format!(
+                                "Solved: {}  Failed: {}",
+                                puzzle_session.solved_count(),
+                                puzzle_session.failed_count()
+                            ));
+                            ui.horizontal(|ui| {
+                                if puzzle_session.current().is_some()
+                                    && ui.button("Next puzzle").clicked()
+                                {
+                                    puzzle_action = Some(PuzzleAction::Next);
+                                }
+                                if ui.button("Stop").clicked() {
+                                    puzzle_action = Some(PuzzleAction::Stop);
+                                }
+                            });
+                        }
+                        None => {
+                            if ui.button("Load puzzles").clicked() {
+                                puzzle_action = Some(PuzzleAction::Load);
+                            }
+                        }
+                    }
+
+                    if submit_move {
+                        self.play_notation_move(&move_input);
+                        move_input.clear();
+                    }
+                    *self.get_move_input_mut() = move_input;
+                    self.set_time_control(time_control);
+                    self.set_show_threat_overlay(show_threat_overlay);
+                    self.set_computer_color(computer_color);
+                    *self.get_computer_think_ms_mut() = computer_think_ms;
+                    *self.get_uci_engine_path_mut() = uci_engine_path;
+                    *self.get_uci_engine_options_mut() = uci_engine_options;
+                    match uci_engine_action {
+                        Some(true) => self.connect_uci_engine(),
+                        Some(false) => self.disconnect_uci_engine(),
+                        None => {}
+                    }
+                    *self.get_opening_book_path_mut() = opening_book_path;
+                    match opening_book_action {
+                        Some(true) => self.load_opening_book(),
+                        Some(false) => self.unload_opening_book(),
+                        None => {}
+                    }
+                    if let Some(mv) = book_move {
+                        self.play_notation_move(&mv.to_string());
+                    }
+                    *self.get_puzzle_path_mut() = puzzle_path;
+                    match puzzle_action {
+                        Some(PuzzleAction::Load) => self.load_puzzles(),
+                        Some(PuzzleAction::Next) => self.next_puzzle(),
+                        Some(PuzzleAction::Stop) => self.stop_puzzles(),
+                        None => {}
+                    }
+
+                    if let Some((setup, palette)) = setup_edit {
+                        self.set_chess_setup(setup, palette);
+                    }
+                    match setup_action {
+                        Some(SetupAction::Begin) => self.begin_chess_setup(),
+                        Some(SetupAction::Clear) => self.clear_chess_setup(),
+                        Some(SetupAction::Finish) => self.finish_chess_setup(),
+                        None => {}
+                    }
+
+                    if claim_draw {
+                        self.claim_draw();
+                    }
+                    if resign {
+                        self.resign();
+                    }
+                    if offer_draw {
+                        self.offer_draw();
+                    }
+                    if let Some(accept) = draw_answer {
+                        self.answer_draw_offer(accept);
+                    }
+
+                    if undo {
+                        self.undo_chess_move();
+                    } else if redo {
+                        self.redo_chess_move();
+                    }
+
+                    if let Some(piece_type) = promotion_choice {
+                        self.choose_promotion(piece_type);
                     }
                 });
             });
     }
 }
+
+/// Square names separated by spaces, or `none`.
+fn squares_text(squares: &[Square]) -> String {
+    if squares.is_empty() {
+        return "none".to_owned();
+    }
+    squares
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+enum SetupAction {
+    Begin,
+    Clear,
+    Finish,
+}
+
+enum PuzzleAction {
+    Load,
+    Next,
+    Stop,
+}
+
+/// Palette, side to move, castling and en passant controls for setup mode.
+/// Clicking a square places the palette piece, or clears the square for
+/// "Remove".
+fn setup_panel(ui: &mut egui::Ui, setup: &mut BoardSetup, palette: &mut Option<Piece>) {
+    ui.label("Setup: click a square to place the piece");
+    for color in [Color::White, Color::Black] {
+        ui.horizontal_wrapped(|ui| {
+            for piece_type in PieceType::ALL {
+                let piece = Piece { piece_type, color };
+                ui.radio_value(palette, Some(piece), format!("{color:?} {piece_type:?}"));
+            }
+        });
+    }
+    ui.radio_value(palette, None, "Remove");
+
+    ui.horizontal(|ui| {
+        ui.label("To move:");
+        ui.radio_value(&mut setup.side_to_move, Color::White, "White");
+        ui.radio_value(&mut setup.side_to_move, Color::Black, "Black");
+    });
+    ui.horizontal(|ui| {
+        ui.label("Castling:");
+        ui.checkbox(setup.castling_mut(Color::White, true), "K");
+        ui.checkbox(setup.castling_mut(Color::White, false), "Q");
+        ui.checkbox(setup.castling_mut(Color::Black, true), "k");
+        ui.checkbox(setup.castling_mut(Color::Black, false), "q");
+    });
+    ui.horizontal_wrapped(|ui| {
+        ui.label("En passant:");
+        ui.radio_value(&mut setup.en_passant_file, None, "-");
+        for file in 0..8u8 {
+            let name = ((b'a' + file) as char).to_string();
+            ui.radio_value(&mut setup.en_passant_file, Some(file), name);
+        }
+    });
+}
+
+/// `m:ss`, with tenths of a second once under ten seconds.
+fn clock_text(remaining: Duration) -> String {
+    let seconds = remaining.as_secs();
+    if seconds < 10 {
+        format!("0:{:04.1}", remaining.as_secs_f32())
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn engine_result_text(result: &SearchResult) -> String {
+    let score = match result.mate_in() {
+        Some(moves) => format!("mate in {moves}"),
+        None => format!("{:+.2}", result.score as f32 / 100.0),
+    };
+    let line = result
+        .principal_variation
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "Engine: {score} at depth {} ({} nodes)\nLine: {line}",
+        result.depth, result.nodes
+    )
+}
@@ -0,0 +1,264 @@
+use crate::game_logic::chess::{
+    GameState, MatchConfig, MatchScore, OpeningBook, START_POSITION_FEN, SearchLimits,
+    UciEngineConfig, book_error_message, fen_error_message, pgn_error_message, play_match,
+    run_uci_engine, uci_error_message,
+};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+usage:
+  rendering                        start the 3D chess app
+  rendering perft <depth> [fen]    count leaf positions at <depth>
+  rendering divide <depth> [fen]   perft split by root move
+  rendering book <file> [fen]      list Polyglot book moves for a position
+  rendering uci                    run as a UCI engine on stdin/stdout
+  rendering match <engine1> <engine2> [--games N] [--movetime ms] [--depth d]
+                  [--openings file] [--pgn file] [--options1 A=1,B=2] [--options2 ...]
+                                   play two UCI engines against each other";
+
+/// Runs a headless command when the first argument names one. Returns the
+/// process exit code, or `None` to start the window as usual.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    let result = match command.as_str() {
+        "perft" => run_perft(rest, false),
+        "divide" => run_perft(rest, true),
+        "book" => run_book(rest),
+        "match" => run_match(rest),
+        "uci" => {
+            run_uci_engine(io::stdin().lock(), io::stdout());
+            Ok(())
+        }
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(format!("Unknown command `{command}`")),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            Some(2)
+        }
+    }
+}
+
+fn run_perft(args: &[String], divide: bool) -> Result<(), String> {
+    let (depth, fen_parts) = args.split_first().ok_or("Missing perft depth")?;
+    let depth = depth
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid perft depth `{depth}`"))?;
+    let state = parse_fen_args(fen_parts)?;
+
+    let started = Instant::now();
+    let nodes = if divide {
+        let breakdown = state.divide(depth);
+        for (mv, nodes) in &breakdown {
+            println!("{mv}: {nodes}");
+        }
+        println!();
+        breakdown.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        state.perft(depth)
+    };
+    let elapsed = started.elapsed();
+
+    println!("Nodes searched: {nodes}");
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+    Ok(())
+}
+
+fn run_book(args: &[String]) -> Result<(), String> {
+    let (path, fen_parts) = args.split_first().ok_or("Missing book file")?;
+    let book = OpeningBook::load(Path::new(path)).map_err(|err| book_error_message(&err))?;
+    let state = parse_fen_args(fen_parts)?;
+
+    let moves = book.moves(&state);
+    if moves.is_empty() {
+        println!("No book moves (key {:016x})", state.polyglot_key());
+    }
+    for book_move in moves {
+        let san = state
+            .move_to_san(book_move.mv)
+            .unwrap_or_else(|_| book_move.mv.to_string());
+        println!("{} {san} weight {}", book_move.mv, book_move.weight);
+    }
+    Ok(())
+}
+
+/// Each engine is a command line, `path [args...]`, passed as one
+/// argument. PGN goes to stdout unless `--pgn` names a file; progress and
+/// the summary go to stderr so the PGN stays clean.
+fn run_match(args: &[String]) -> Result<(), String> {
+    let [first, second, flags @ ..] = args else {
+        return Err("Missing engine commands".to_owned());
+    };
+
+    let mut engines = [parse_engine_command(first)?, parse_engine_command(second)?];
+    let mut games = 2;
+    let mut limits = SearchLimits::move_time(Duration::from_millis(100));
+    let mut openings = vec![GameState::new_start_position()];
+    let mut pgn_path = None;
+
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags
+            .next()
+            .ok_or_else(|| format!("Missing value for `{flag}`"))?;
+        match flag.as_str() {
+            "--games" => games = parse_number(flag, value)?,
+            "--movetime" => {
+                limits.move_time = Some(Duration::from_millis(parse_number(flag, value)?));
+            }
+            "--depth" => {
+                limits.max_depth = Some(parse_number(flag, value)?);
+                limits.move_time = None;
+            }
+            "--openings" => openings = read_openings(Path::new(value))?,
+            "--pgn" => pgn_path = Some(PathBuf::from(value)),
+            "--options1" => engines[0].options = UciEngineConfig::parse_options(value),
+            "--options2" => engines[1].options = UciEngineConfig::parse_options(value),
+            _ => return Err(format!("Unknown match option `{flag}`")),
+        }
+    }
+
+    let names = engines.clone().map(|engine| engine_name(&engine));
+    let config = MatchConfig {
+        engines,
+        names,
+        openings,
+        games,
+        limits,
+    };
+
+    let mut output: Box<dyn Write> = match &pgn_path {
+        Some(path) => Box::new(
+            fs::File::create(path)
+                .map_err(|err| format!("Could not create {}: {err}", path.display()))?,
+        ),
+        None => Box::new(io::stdout()),
+    };
+    let mut write_error = None;
+    let score = play_match(&config, |game, score| {
+        let written = game
+            .to_pgn()
+            .map_err(|err| pgn_error_message(&err))
+            .and_then(|pgn| {
+                output
+                    .write_all(pgn.as_bytes())
+                    .and_then(|()| output.flush())
+                    .map_err(|err| err.to_string())
+            });
+        if let Err(err) = written {
+            write_error.get_or_insert(err);
+        }
+        eprintln!(
+            "Game {}/{}: {} - {} {} ({})",
+            score.games(),
+            config.games,
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?"),
+            game.result,
+            score_text(score)
+        );
+    })
+    .map_err(|err| uci_error_message(&err))?;
+    if let Some(err) = write_error {
+        return Err(format!("Could not write PGN: {err}"));
+    }
+
+    eprintln!();
+    eprintln!("{} vs {}", config.names[0], config.names[1]);
+    eprintln!("{}", score_text(score));
+    match score.elo_difference() {
+        Some(elo) => eprintln!("Elo difference: {elo:+.1}"),
+        None if score.games() == 0 => eprintln!("Elo difference: -"),
+        None => eprintln!("Elo difference: unbounded (one engine scored every point)"),
+    }
+    Ok(())
+}
+
+/// Splits `path [args...]` on whitespace.
+fn parse_engine_command(command: &str) -> Result<UciEngineConfig, String> {
+    let mut parts = command.split_whitespace();
+    let path = parts.next().ok_or("Empty engine command")?;
+    Ok(UciEngineConfig {
+        path: PathBuf::from(path),
+        args: parts.map(str::to_owned).collect(),
+        options: Vec::new(),
+    })
+}
+
+/// The engine's file name with its arguments and options, enough to tell
+/// two configurations of one binary apart.
+fn engine_name(engine: &UciEngineConfig) -> String {
+    let mut name = engine.path.file_name().map_or_else(
+        || engine.path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    for arg in &engine.args {
+        name.push(' ');
+        name.push_str(arg);
+    }
+    for (option, value) in &engine.options {
+        name.push_str(&format!(" {option}={value}"));
+    }
+    name
+}
+
+/// One FEN per line; blank lines and `#` comments are skipped.
+fn read_openings(path: &Path) -> Result<Vec<GameState>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+    let openings = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|fen| {
+            GameState::from_fen(fen)
+                .map_err(|err| format!("Opening `{fen}`: {}", fen_error_message(&err)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if openings.is_empty() {
+        return Err(format!("No openings in {}", path.display()));
+    }
+    Ok(openings)
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value `{value}` for `{flag}`"))
+}
+
+/// `+3 =4 -1 (5.0/8)` from the first engine's point of view.
+fn score_text(score: MatchScore) -> String {
+    format!(
+        "+{} ={} -{} ({:.1}/{})",
+        score.wins,
+        score.draws,
+        score.losses,
+        score.score_fraction() * f64::from(score.games()),
+        score.games()
+    )
+}
+
+/// Joins the remaining arguments into a FEN so it can be passed quoted or
+/// unquoted; no arguments means the start position.
+fn parse_fen_args(args: &[String]) -> Result<GameState, String> {
+    let fen = if args.is_empty() {
+        START_POSITION_FEN.to_owned()
+    } else {
+        args.join(" ")
+    };
+    GameState::from_fen(&fen).map_err(|err| fen_error_message(&err))
+}
@@ -0,0 +1,154 @@
+use crate::game_logic::chess::bitboard::{
+    Bitboard, king_attacks, piece_index, square_bit, squares,
+};
+use crate::game_logic::chess::{Color, GameState, Move, Piece, PieceType, Square, Variant};
+
+/// Pieces an Atomic capture blew up around the capture square, besides the
+/// captured piece and the capturing piece itself. At most the eight
+/// neighbours of one square.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Explosion {
+    pieces: [Option<(Square, Piece)>; 8],
+}
+
+impl Explosion {
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.pieces.iter().flatten().copied()
+    }
+}
+
+impl GameState {
+    /// Non-pawn pieces of either color next to `center`, which a capture
+    /// there destroys.
+    pub(super) fn blast_radius(&self, center: Square) -> Bitboard {
+        king_attacks(center) & self.occupied() & !self.piece_bitboards[piece_index(PieceType::Pawn)]
+    }
+
+    /// What playing `mv` would blow up, or `None` when it is not an Atomic
+    /// capture. The moving piece's own square is left out, since it is
+    /// gone from there before the blast.
+    pub(super) fn explosion_for(&self, mv: Move, piece: Piece) -> Option<Explosion> {
+        if self.variant != Variant::Atomic || self.captured_by(mv, piece).is_none() {
+            return None;
+        }
+
+        let mut explosion = Explosion::default();
+        let blast = self.blast_radius(mv.to) & !square_bit(mv.from);
+        for (slot, square) in squares(blast).enumerate() {
+            explosion.pieces[slot] = self.piece_at(square).map(|piece| (square, piece));
+        }
+        Some(explosion)
+    }
+
+    /// Removes the capturing piece on `center` and everything in the blast
+    /// radius, dropping the castling rights of any king or rook destroyed.
+    pub(super) fn explode(&mut self, center: Square) {
+        for square in squares(self.blast_radius(center)) {
+            match self.piece_at(square) {
+                Some(Piece {
+                    piece_type: PieceType::King,
+                    color,
+                }) => self.clear_castling_rights(color),
+                Some(Piece {
+                    piece_type: PieceType::Rook,
+                    color,
+                }) => self.clear_rook_castling_right(color, square),
+                _ => {}
+            }
+            self.set_piece(square, None);
+        }
+        self.set_piece(center, None);
+    }
+
+    /// Touching kings never give check in Atomic: capturing the other king
+    /// would blow up one's own.
+    pub(super) fn kings_touch(&self) -> bool {
+        let white_king = self.pieces(Color::White, PieceType::King);
+        squares(white_king)
+            .any(|square| king_attacks(square) & self.pieces(Color::Black, PieceType::King) != 0)
+    }
+
+    /// Atomic moves are tried on a copy of the board: the mover's king must
+    /// survive, and must be out of check unless the opposing king was blown
+    /// up. Kings may not capture at all.
+    pub(super) fn atomic_legal_targets(&self, from: Square, piece: Piece) -> Bitboard {
+        let mut pseudo_legal = self.pseudo_legal_targets(from, piece);
+        if piece.piece_type == PieceType::King {
+            pseudo_legal &= !self.color_bitboard(piece.color.opposite());
+        }
+
+        squares(pseudo_legal)
+            .filter(|to| {
+                let mut next = self.clone();
+                next.apply_move_unchecked(Move::new(from, *to), piece);
+                next.pieces(piece.color, PieceType::King) != 0
+                    && (next.pieces(piece.color.opposite(), PieceType::King) == 0
+                        || !next.is_in_check(piece.color))
+            })
+            .fold(0, |targets, to| targets | square_bit(to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::chess::GameOutcome;
+
+    fn state(fen: &str) -> GameState {
+        GameState::from_fen(fen)
+            .expect("valid FEN")
+            .with_variant(Variant::Atomic)
+    }
+
+    /// Rxd5 blows up the d5 knight, the rook itself and the e6 and c4
+    /// bishops, but not the c6 and e4 pawns.
+    const BLAST: &str = "4k3/8/2p1b3/3n4/2B1p3/8/8/3RK3 w - - 0 1";
+
+    #[test]
+    fn explosion_spares_pawns() {
+        let mut game = state(BLAST);
+        let mv = game.parse_uci("d1d5").expect("legal capture");
+        game.apply_move(mv).expect("legal capture");
+        assert_eq!(game.to_fen(), "4k3/8/2p5/8/4p3/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn undo_restores_every_exploded_piece() {
+        let mut game = state(BLAST);
+        let key = game.zobrist_key();
+        let mv = game.parse_uci("d1d5").expect("legal capture");
+        game.apply_move(mv).expect("legal capture");
+        let record = game.undo_move().expect("a move to undo");
+        assert_eq!(record.exploded_pieces().count(), 2);
+        assert_eq!(game.to_fen(), BLAST);
+        assert_eq!(game.zobrist_key(), key);
+    }
+
+    #[test]
+    fn king_may_not_capture() {
+        let game = state("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1");
+        assert!(game.parse_uci("e1d2").is_err());
+        assert!(game.parse_uci("e1d1").is_ok());
+    }
+
+    #[test]
+    fn capture_next_to_own_king_is_illegal() {
+        let game = state("4k3/8/8/8/8/8/3n4/1N2K3 w - - 0 1");
+        assert!(game.parse_uci("b1d2").is_err());
+    }
+
+    #[test]
+    fn exploding_the_enemy_king_wins_even_in_check() {
+        // White is in check from h8 but Rxd8 blows up the e8 king.
+        let mut game = state("3nk2r/8/8/8/8/8/8/3R3K w - - 0 1");
+        assert!(game.is_in_check(Color::White));
+        let mv = game.parse_uci("d1d8").expect("legal capture");
+        game.apply_move(mv).expect("legal capture");
+        assert_eq!(
+            game.game_outcome(),
+            Some(GameOutcome::KingExploded {
+                winner: Color::White
+            })
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use crate::game_logic::chess::{Color, PieceType, Square};
+
+/// One bit per square, bit `rank * 8 + file`.
+pub(super) type Bitboard = u64;
+
+const NORTH: usize = 0;
+const NORTH_EAST: usize = 1;
+const EAST: usize = 2;
+const NORTH_WEST: usize = 3;
+const SOUTH: usize = 4;
+const SOUTH_WEST: usize = 5;
+const WEST: usize = 6;
+const SOUTH_EAST: usize = 7;
+
+/// File and rank steps for each ray direction. The first four directions
+/// walk towards higher square indices, the last four towards lower ones.
+const DIRECTIONS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (-1, 1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (1, -1),
+];
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+static KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_STEPS);
+static KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_STEPS);
+static WHITE_PAWN_ATTACKS: [Bitboard; 64] = leaper_attacks(&[(-1, 1), (1, 1)]);
+static BLACK_PAWN_ATTACKS: [Bitboard; 64] = leaper_attacks(&[(-1, -1), (1, -1)]);
+static RAYS: [[Bitboard; 64]; 8] = rays();
+static BETWEEN: [[Bitboard; 64]; 64] = between_table();
+
+const fn step(index: usize, file_step: i8, rank_step: i8) -> Option<usize> {
+    let file = (index % 8) as i8 + file_step;
+    let rank = (index / 8) as i8 + rank_step;
+    if file < 0 || file >= 8 || rank < 0 || rank >= 8 {
+        None
+    } else {
+        Some(rank as usize * 8 + file as usize)
+    }
+}
+
+const fn leaper_attacks(steps: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut index = 0;
+    while index < 64 {
+        let mut step_index = 0;
+        while step_index < steps.len() {
+            let (file_step, rank_step) = steps[step_index];
+            if let Some(target) = step(index, file_step, rank_step) {
+                table[index] |= 1 << target;
+            }
+            step_index += 1;
+        }
+        index += 1;
+    }
+    table
+}
+
+const fn rays() -> [[Bitboard; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (file_step, rank_step) = DIRECTIONS[direction];
+        let mut index = 0;
+        while index < 64 {
+            let mut current = step(index, file_step, rank_step);
+            while let Some(target) = current {
+                table[direction][index] |= 1 << target;
+                current = step(target, file_step, rank_step);
+            }
+            index += 1;
+        }
+        direction += 1;
+    }
+    table
+}
+
+const fn between_table() -> [[Bitboard; 64]; 64] {
+    let mut table = [[0; 64]; 64];
+    let mut from = 0;
+    while from < 64 {
+        let mut direction = 0;
+        while direction < 8 {
+            let (file_step, rank_step) = DIRECTIONS[direction];
+            let mut path: Bitboard = 0;
+            let mut current = step(from, file_step, rank_step);
+            while let Some(target) = current {
+                table[from][target] = path;
+                path |= 1 << target;
+                current = step(target, file_step, rank_step);
+            }
+            direction += 1;
+        }
+        from += 1;
+    }
+    table
+}
+
+pub(super) fn square_bit(square: Square) -> Bitboard {
+    1 << square.to_index()
+}
+
+/// Iterates the squares of every set bit, lowest index first.
+pub(super) fn squares(mut bitboard: Bitboard) -> impl Iterator<Item = Square> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+        let index = bitboard.trailing_zeros() as usize;
+        bitboard &= bitboard - 1;
+        Square::from_index(index)
+    })
+}
+
+pub(super) fn first_square(bitboard: Bitboard) -> Option<Square> {
+    squares(bitboard).next()
+}
+
+pub(super) fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+/// Piece types in `piece_index` order.
+pub(super) const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+pub(super) fn piece_index(piece_type: PieceType) -> usize {
+    match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    }
+}
+
+pub(super) fn knight_attacks(square: Square) -> Bitboard {
+    KNIGHT_ATTACKS[square.to_index()]
+}
+
+pub(super) fn king_attacks(square: Square) -> Bitboard {
+    KING_ATTACKS[square.to_index()]
+}
+
+/// Squares a pawn of `color` standing on `square` attacks.
+pub(super) fn pawn_attacks(color: Color, square: Square) -> Bitboard {
+    match color {
+        Color::White => WHITE_PAWN_ATTACKS[square.to_index()],
+        Color::Black => BLACK_PAWN_ATTACKS[square.to_index()],
+    }
+}
+
+pub(super) fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    [NORTH_EAST, NORTH_WEST, SOUTH_EAST, SOUTH_WEST]
+        .into_iter()
+        .fold(0, |attacks, direction| {
+            attacks | ray_attacks(direction, square, occupied)
+        })
+}
+
+pub(super) fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    [NORTH, EAST, SOUTH, WEST]
+        .into_iter()
+        .fold(0, |attacks, direction| {
+            attacks | ray_attacks(direction, square, occupied)
+        })
+}
+
+/// Squares strictly between two squares on a shared rank, file or diagonal;
+/// empty when they are not aligned.
+pub(super) fn between(from: Square, to: Square) -> Bitboard {
+    BETWEEN[from.to_index()][to.to_index()]
+}
+
+fn ray_attacks(direction: usize, square: Square, occupied: Bitboard) -> Bitboard {
+    let ray = RAYS[direction][square.to_index()];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+
+    let blocker = if direction < SOUTH {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray ^ RAYS[direction][blocker as usize]
+}
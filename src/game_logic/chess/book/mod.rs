@@ -0,0 +1,4 @@
+mod keys;
+mod polyglot;
+
+pub use polyglot::{BookError, OpeningBook};
@@ -0,0 +1,226 @@
+use std::fs;
+use std::path::Path;
+
+use crate::game_logic::chess::{GameState, Move, PieceType, Square};
+
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BookError {
+    Io(String),
+    /// The file length is not a whole number of 16-byte entries.
+    TruncatedEntry(usize),
+}
+
+/// A legal move found in the book with its Polyglot weight.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BookMove {
+    pub mv: Move,
+    pub weight: u16,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct BookEntry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+/// A Polyglot `.bin` opening book held in memory, sorted by position key.
+#[derive(Debug, Clone, Default)]
+pub struct OpeningBook {
+    entries: Vec<BookEntry>,
+}
+
+impl OpeningBook {
+    pub fn load(path: &Path) -> Result<Self, BookError> {
+        let bytes =
+            fs::read(path).map_err(|err| BookError::Io(format!("{}: {err}", path.display())))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Reads big-endian entries of key, move, weight and learn data.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::TruncatedEntry(bytes.len() / ENTRY_SIZE));
+        }
+
+        let mut entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| BookEntry {
+                key: u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes([entry[8], entry[9]]),
+                weight: u16::from_be_bytes([entry[10], entry[11]]),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.key);
+        Ok(Self { entries })
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Book moves for `state` that are legal there, heaviest first. Entries
+    /// with weight zero are skipped, as Polyglot does.
+    pub fn moves(&self, state: &GameState) -> Vec<BookMove> {
+        let key = state.polyglot_key();
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let legal_moves = state.generate_legal_moves();
+
+        let mut moves = self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter(|entry| entry.weight > 0)
+            .filter_map(|entry| {
+                let mv = decode_move(state, entry.raw_move)?;
+                legal_moves.contains(&mv).then_some(BookMove {
+                    mv,
+                    weight: entry.weight,
+                })
+            })
+            .collect::<Vec<_>>();
+        moves.sort_by_key(|book_move| std::cmp::Reverse(book_move.weight));
+        moves
+    }
+}
+
+/// Unpacks to/from squares and promotion piece. Polyglot writes castling as
+/// the king capturing its own rook, which outside Chess960 becomes e1g1 and
+/// so on.
+fn decode_move(state: &GameState, raw_move: u16) -> Option<Move> {
+    let field = |shift: u16| ((raw_move >> shift) & 0b111) as u8;
+    let from = Square::new(field(6), field(9))?;
+    let mut to = Square::new(field(0), field(3))?;
+
+    let is_king = state
+        .piece_at(from)
+        .is_some_and(|piece| piece.piece_type == PieceType::King);
+    if is_king && !state.is_chess960() && from.file() == 4 && to.rank() == from.rank() {
+        match to.file() {
+            7 => to = Square::new(6, to.rank())?,
+            0 => to = Square::new(2, to.rank())?,
+            _ => {}
+        }
+    }
+
+    let promotion = match field(12) {
+        0 => None,
+        1 => Some(PieceType::Knight),
+        2 => Some(PieceType::Bishop),
+        3 => Some(PieceType::Rook),
+        4 => Some(PieceType::Queen),
+        _ => return None,
+    };
+    Some(match promotion {
+        Some(piece_type) => Move::with_promotion(from, to, piece_type),
+        None => Move::new(from, to),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs a move the way Polyglot does: to file and rank, from file and
+    /// rank, then the promotion piece, three bits each.
+    fn raw_move(text: &str, promotion: u16) -> u16 {
+        let mv = text.parse::<Move>().expect("move");
+        u16::from(mv.to.file())
+            | u16::from(mv.to.rank()) << 3
+            | u16::from(mv.from.file()) << 6
+            | u16::from(mv.from.rank()) << 9
+            | promotion << 12
+    }
+
+    fn entry(key: u64, raw_move: u16, weight: u16) -> Vec<u8> {
+        let mut bytes = key.to_be_bytes().to_vec();
+        bytes.extend(raw_move.to_be_bytes());
+        bytes.extend(weight.to_be_bytes());
+        bytes.extend([0; 4]);
+        bytes
+    }
+
+    fn decoded(fen: &str, raw_move: u16) -> Option<String> {
+        let state = GameState::from_fen(fen).expect("valid FEN");
+        decode_move(&state, raw_move).map(|mv| mv.to_string())
+    }
+
+    #[test]
+    fn decodes_plain_moves_and_promotions() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(decoded(start, raw_move("g1f3", 0)).as_deref(), Some("g1f3"));
+
+        let promotion = "8/P7/8/8/8/8/8/k6K w - - 0 1";
+        assert_eq!(
+            decoded(promotion, raw_move("a7a8", 4)).as_deref(),
+            Some("a7a8q")
+        );
+        assert_eq!(
+            decoded(promotion, raw_move("a7a8", 1)).as_deref(),
+            Some("a7a8n")
+        );
+        assert_eq!(decoded(promotion, raw_move("a7a8", 5)), None);
+    }
+
+    #[test]
+    fn converts_castling_from_king_takes_rook() {
+        let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(
+            decoded(castling, raw_move("e1h1", 0)).as_deref(),
+            Some("e1g1")
+        );
+        assert_eq!(
+            decoded(castling, raw_move("e1a1", 0)).as_deref(),
+            Some("e1c1")
+        );
+        let black = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1";
+        assert_eq!(decoded(black, raw_move("e8h8", 0)).as_deref(), Some("e8g8"));
+
+        // A rook on h1 moving along the rank is not castling.
+        assert_eq!(
+            decoded(castling, raw_move("h1f1", 0)).as_deref(),
+            Some("h1f1")
+        );
+    }
+
+    #[test]
+    fn keeps_king_takes_rook_in_chess960() {
+        let chess960 = "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1";
+        assert_eq!(
+            decoded(chess960, raw_move("e1g1", 0)).as_deref(),
+            Some("e1g1")
+        );
+    }
+
+    #[test]
+    fn book_moves_are_legal_and_heaviest_first() {
+        let state = GameState::new_start_position();
+        let key = state.polyglot_key();
+        let bytes = [
+            entry(key, raw_move("d2d4", 0), 10),
+            entry(key, raw_move("e2e4", 0), 30),
+            entry(key, raw_move("e2e5", 0), 50),
+            entry(key, raw_move("c2c4", 0), 0),
+            entry(key ^ 1, raw_move("g1f3", 0), 40),
+        ]
+        .concat();
+
+        let book = OpeningBook::from_bytes(&bytes).expect("whole entries");
+        assert_eq!(book.entry_count(), 5);
+        let moves = book
+            .moves(&state)
+            .into_iter()
+            .map(|book_move| (book_move.mv.to_string(), book_move.weight))
+            .collect::<Vec<_>>();
+        assert_eq!(moves, [("e2e4".to_owned(), 30), ("d2d4".to_owned(), 10)]);
+    }
+
+    #[test]
+    fn rejects_a_truncated_file() {
+        assert_eq!(
+            OpeningBook::from_bytes(&[0; 20]).err(),
+            Some(BookError::TruncatedEntry(1))
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use crate::game_logic::chess::zobrist::splitmix64;
+use crate::game_logic::chess::{Color, GameState, Piece, PieceType, Square};
+
+const CHESS960_POSITION_COUNT: u16 = 960;
+
+/// Knight squares, as indices into the five files left after placing the
+/// bishops and queen.
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// Draws Chess960 start position numbers from a fixed seed, so a seed
+/// always gives the same sequence of games.
+#[derive(Debug, Clone)]
+pub struct Chess960Generator {
+    state: u64,
+}
+
+impl Chess960Generator {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_position(&mut self) -> u16 {
+        (splitmix64(&mut self.state) % u64::from(CHESS960_POSITION_COUNT)) as u16
+    }
+}
+
+/// Back rank of start position `number` in Scharnagl's numbering, where 518
+/// is the standard setup. Bishops go on opposite colors, the queen and
+/// knights fill the gaps, and the king lands between the two rooks.
+fn chess960_back_rank(number: u16) -> [PieceType; 8] {
+    let mut rank = [None; 8];
+    let mut n = usize::from(number % CHESS960_POSITION_COUNT);
+
+    rank[2 * (n % 4) + 1] = Some(PieceType::Bishop);
+    n /= 4;
+    rank[2 * (n % 4)] = Some(PieceType::Bishop);
+    n /= 4;
+
+    let empty_files = |rank: &[Option<PieceType>; 8]| {
+        (0..8)
+            .filter(|file| rank[*file].is_none())
+            .collect::<Vec<_>>()
+    };
+    rank[empty_files(&rank)[n % 6]] = Some(PieceType::Queen);
+    n /= 6;
+
+    let empty = empty_files(&rank);
+    let (first, second) = KNIGHT_PLACEMENTS[n];
+    rank[empty[first]] = Some(PieceType::Knight);
+    rank[empty[second]] = Some(PieceType::Knight);
+
+    for (file, piece_type) in
+        empty_files(&rank)
+            .into_iter()
+            .zip([PieceType::Rook, PieceType::King, PieceType::Rook])
+    {
+        rank[file] = Some(piece_type);
+    }
+
+    rank.map(|piece_type| piece_type.expect("every back rank file is filled"))
+}
+
+impl GameState {
+    /// Chess960 start position `number`, from 0 to 959.
+    pub fn new_chess960(number: u16) -> Self {
+        let mut game = Self::with_back_rank(chess960_back_rank(number));
+        game.chess960 = true;
+        game
+    }
+
+    /// The Scharnagl number of a Chess960 game's start position, read from
+    /// White's back rank. `None` for standard games or once that rank has
+    /// changed.
+    pub fn chess960_position(&self) -> Option<u16> {
+        if !self.chess960 {
+            return None;
+        }
+
+        let back_rank = (0..8)
+            .map(|file| {
+                Square::new(file, 0)
+                    .and_then(|square| self.piece_at(square))
+                    .filter(|piece| piece.color == Color::White)
+                    .map(|Piece { piece_type, .. }| piece_type)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        (0..CHESS960_POSITION_COUNT).find(|number| chess960_back_rank(*number) == *back_rank)
+    }
+}
@@ -0,0 +1,292 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::game_logic::chess::Color;
+use crate::game_logic::chess::bitboard::color_index;
+
+/// Time added for each completed move.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TimeBonus {
+    None,
+    /// Added in full after every move.
+    Fischer(Duration),
+    /// Gives back the time the move took, up to this much.
+    Bronstein(Duration),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub bonus: TimeBonus,
+}
+
+impl TimeControl {
+    pub const PRESETS: [TimeControl; 6] = [
+        TimeControl::fischer(1, 0),
+        TimeControl::fischer(3, 2),
+        TimeControl::fischer(5, 3),
+        TimeControl::bronstein(5, 3),
+        TimeControl::fischer(15, 10),
+        TimeControl::fischer(30, 0),
+    ];
+
+    /// `minutes` per side plus `increment_seconds` after each move.
+    pub const fn fischer(minutes: u64, increment_seconds: u64) -> Self {
+        Self {
+            base: Duration::from_secs(minutes * 60),
+            bonus: if increment_seconds == 0 {
+                TimeBonus::None
+            } else {
+                TimeBonus::Fischer(Duration::from_secs(increment_seconds))
+            },
+        }
+    }
+
+    /// `minutes` per side with a `delay_seconds` Bronstein delay.
+    pub const fn bronstein(minutes: u64, delay_seconds: u64) -> Self {
+        Self {
+            base: Duration::from_secs(minutes * 60),
+            bonus: TimeBonus::Bronstein(Duration::from_secs(delay_seconds)),
+        }
+    }
+}
+
+/// `5+3` for an increment, `5 d3` for a Bronstein delay.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = self.base.as_secs() / 60;
+        match self.bonus {
+            TimeBonus::None => write!(f, "{minutes}+0"),
+            TimeBonus::Fischer(increment) => write!(f, "{minutes}+{}", increment.as_secs()),
+            TimeBonus::Bronstein(delay) => write!(f, "{minutes} d{}", delay.as_secs()),
+        }
+    }
+}
+
+/// A two-sided game clock. Every method takes the current time, so callers
+/// decide where time comes from: the app passes `Instant::now()`, tests can
+/// pass any instant they like.
+#[derive(Debug, Clone)]
+pub struct ChessClock {
+    time_control: TimeControl,
+    remaining: [Duration; 2],
+    running: Option<Color>,
+    turn_started: Instant,
+}
+
+impl ChessClock {
+    /// Both sides start with the full base time; `side_to_move`'s clock runs
+    /// from `now`.
+    pub fn new(time_control: TimeControl, side_to_move: Color, now: Instant) -> Self {
+        Self {
+            time_control,
+            remaining: [time_control.base; 2],
+            running: Some(side_to_move),
+            turn_started: now,
+        }
+    }
+
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// The side whose time is running, `None` once the clock is stopped.
+    pub fn running(&self) -> Option<Color> {
+        self.running
+    }
+
+    /// `color`'s time left at `now`, counting the turn in progress.
+    pub fn remaining(&self, color: Color, now: Instant) -> Duration {
+        let remaining = self.remaining[color_index(color)];
+        if self.running == Some(color) {
+            remaining.saturating_sub(now.saturating_duration_since(self.turn_started))
+        } else {
+            remaining
+        }
+    }
+
+    /// The side whose time ran out, if any.
+    pub fn flagged(&self, now: Instant) -> Option<Color> {
+        self.running
+            .filter(|color| self.remaining(*color, now).is_zero())
+    }
+
+    /// Ends the running side's move: charges the time it took, adds the
+    /// increment or delay, and starts the opponent's clock. If the running
+    /// side's flag had already fallen, no bonus is added: the clock stops
+    /// at zero and that side is returned.
+    pub fn press(&mut self, now: Instant) -> Option<Color> {
+        let color = self.running?;
+        if self.flagged(now).is_some() {
+            self.stop(now);
+            return Some(color);
+        }
+        let elapsed = now.saturating_duration_since(self.turn_started);
+        let bonus = match self.time_control.bonus {
+            TimeBonus::None => Duration::ZERO,
+            TimeBonus::Fischer(increment) => increment,
+            TimeBonus::Bronstein(delay) => elapsed.min(delay),
+        };
+        let remaining = &mut self.remaining[color_index(color)];
+        *remaining = remaining.saturating_sub(elapsed) + bonus;
+        self.running = Some(color.opposite());
+        self.turn_started = now;
+        None
+    }
+
+    /// Charges the running side for the time used so far and starts
+    /// `color`'s clock without any bonus, as after an undo.
+    pub fn hand_over(&mut self, color: Color, now: Instant) {
+        self.stop(now);
+        self.running = Some(color);
+    }
+
+    /// Charges the running side for the time used so far and stops.
+    pub fn stop(&mut self, now: Instant) {
+        if let Some(color) = self.running {
+            self.remaining[color_index(color)] = self.remaining(color, now);
+        }
+        self.running = None;
+        self.turn_started = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn control(base: u64, bonus: TimeBonus) -> TimeControl {
+        TimeControl {
+            base: seconds(base),
+            bonus,
+        }
+    }
+
+    #[test]
+    fn fischer_adds_the_full_increment() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(
+            control(60, TimeBonus::Fischer(seconds(2))),
+            Color::White,
+            start,
+        );
+
+        assert_eq!(clock.press(start + seconds(10)), None);
+        assert_eq!(
+            clock.remaining(Color::White, start + seconds(10)),
+            seconds(52)
+        );
+        assert_eq!(clock.running(), Some(Color::Black));
+
+        assert_eq!(clock.press(start + seconds(11)), None);
+        assert_eq!(
+            clock.remaining(Color::Black, start + seconds(11)),
+            seconds(61)
+        );
+        assert_eq!(
+            clock.remaining(Color::White, start + seconds(15)),
+            seconds(48)
+        );
+    }
+
+    #[test]
+    fn bronstein_gives_back_at_most_the_delay() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(
+            control(60, TimeBonus::Bronstein(seconds(3))),
+            Color::White,
+            start,
+        );
+
+        clock.press(start + seconds(2));
+        assert_eq!(
+            clock.remaining(Color::White, start + seconds(2)),
+            seconds(60)
+        );
+        clock.press(start + seconds(12));
+        assert_eq!(
+            clock.remaining(Color::Black, start + seconds(12)),
+            seconds(53)
+        );
+    }
+
+    #[test]
+    fn flag_falls_at_zero() {
+        let start = Instant::now();
+        let clock = ChessClock::new(control(60, TimeBonus::None), Color::White, start);
+
+        assert_eq!(clock.flagged(start + seconds(59)), None);
+        assert_eq!(clock.flagged(start + seconds(60)), Some(Color::White));
+        assert_eq!(
+            clock.remaining(Color::White, start + seconds(90)),
+            Duration::ZERO
+        );
+        assert_eq!(
+            clock.remaining(Color::Black, start + seconds(90)),
+            seconds(60)
+        );
+    }
+
+    #[test]
+    fn press_after_the_flag_fell_credits_nothing() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(
+            control(60, TimeBonus::Fischer(seconds(5))),
+            Color::White,
+            start,
+        );
+
+        assert_eq!(clock.press(start + seconds(61)), Some(Color::White));
+        assert_eq!(clock.running(), None);
+        assert_eq!(
+            clock.remaining(Color::White, start + seconds(70)),
+            Duration::ZERO
+        );
+        assert_eq!(
+            clock.remaining(Color::Black, start + seconds(70)),
+            seconds(60)
+        );
+    }
+
+    #[test]
+    fn hand_over_after_an_undo_adds_no_bonus() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(
+            control(60, TimeBonus::Fischer(seconds(2))),
+            Color::White,
+            start,
+        );
+        clock.press(start + seconds(10));
+
+        // Black thinks for 5 seconds, then White's move is taken back.
+        clock.hand_over(Color::White, start + seconds(15));
+        assert_eq!(clock.running(), Some(Color::White));
+        assert_eq!(
+            clock.remaining(Color::Black, start + seconds(15)),
+            seconds(55)
+        );
+        assert_eq!(
+            clock.remaining(Color::White, start + seconds(20)),
+            seconds(47)
+        );
+    }
+
+    #[test]
+    fn stop_freezes_both_sides() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(control(60, TimeBonus::None), Color::White, start);
+        clock.stop(start + seconds(5));
+
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.press(start + seconds(6)), None);
+        assert_eq!(
+            clock.remaining(Color::White, start + seconds(100)),
+            seconds(55)
+        );
+        assert_eq!(clock.flagged(start + seconds(100)), None);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::game_logic::chess::bitboard::{
+    Bitboard, PIECE_TYPES, color_index, piece_index, square_bit, squares,
+};
+use crate::game_logic::chess::notation::piece_letter;
+use crate::game_logic::chess::state::MoveConstraints;
+use crate::game_logic::chess::zobrist::{pockets_key, side_key};
+use crate::game_logic::chess::{
+    Color, GameState, Move, MoveError, MoveRecord, Piece, PieceType, Square, Variant,
+};
+
+/// Pieces held in hand per color, indexed by `piece_index`. Kings are never
+/// captured, so there is no slot for them.
+pub(super) type Pockets = [[u8; 5]; 2];
+
+const BACK_RANKS: Bitboard = 0xFF | (0xFF << 56);
+
+impl GameState {
+    /// How many pieces of `piece_type` `color` holds in hand.
+    pub fn pocket_count(&self, color: Color, piece_type: PieceType) -> u8 {
+        self.pockets[color_index(color)]
+            .get(piece_index(piece_type))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Piece types `color` may drop, pawn first, with their counts.
+    pub fn pocket(&self, color: Color) -> impl Iterator<Item = (PieceType, u8)> + '_ {
+        PIECE_TYPES
+            .into_iter()
+            .map(move |piece_type| (piece_type, self.pocket_count(color, piece_type)))
+            .filter(|(_, count)| *count > 0)
+    }
+
+    /// `color`'s pocket as piece letters, such as `PPN`, or `-` when empty.
+    pub fn pocket_text(&self, color: Color) -> String {
+        let text: String = self
+            .pocket(color)
+            .flat_map(|(piece_type, count)| {
+                std::iter::repeat_n(piece_letter(piece_type), usize::from(count))
+            })
+            .collect();
+        if text.is_empty() {
+            "-".to_owned()
+        } else {
+            text
+        }
+    }
+
+    /// Whether the piece on `square` was a pawn before promoting; such a
+    /// piece goes back into the pocket as a pawn when captured.
+    pub fn is_promoted_piece(&self, square: Square) -> bool {
+        self.promoted & square_bit(square) != 0
+    }
+
+    pub(super) fn add_to_pocket(&mut self, color: Color, piece_type: PieceType) {
+        if let Some(count) = self.pockets[color_index(color)].get_mut(piece_index(piece_type)) {
+            *count += 1;
+        }
+    }
+
+    /// Empty squares `piece_type` may be dropped on: never a pawn on the
+    /// first or last rank, and only onto the checking line when in check.
+    pub(super) fn drop_targets(
+        &self,
+        piece_type: PieceType,
+        constraints: &MoveConstraints,
+    ) -> Bitboard {
+        let mut targets = !self.occupied() & constraints.check_mask;
+        if piece_type == PieceType::Pawn {
+            targets &= !BACK_RANKS;
+        }
+        targets
+    }
+
+    /// Every legal drop for the side to move; empty outside Crazyhouse.
+    pub(super) fn legal_drops(&self, constraints: &MoveConstraints) -> Vec<Move> {
+        self.pocket(self.side_to_move)
+            .flat_map(|(piece_type, _)| {
+                squares(self.drop_targets(piece_type, constraints))
+                    .map(move |to| Move::drop(piece_type, to))
+            })
+            .collect()
+    }
+
+    pub(super) fn apply_drop(&mut self, mv: Move, piece_type: PieceType) -> Result<(), MoveError> {
+        let color = self.side_to_move;
+        if self.variant != Variant::Crazyhouse || self.pocket_count(color, piece_type) == 0 {
+            return Err(MoveError::NotInPocket);
+        }
+        if self.piece_at(mv.to).is_some() {
+            return Err(MoveError::DropOnOccupiedSquare);
+        }
+        if piece_type == PieceType::Pawn && square_bit(mv.to) & BACK_RANKS != 0 {
+            return Err(MoveError::PawnDropOnBackRank);
+        }
+        let constraints = self.move_constraints(color);
+        if self.drop_targets(piece_type, &constraints) & square_bit(mv.to) == 0 {
+            return Err(MoveError::KingWouldBeInCheck);
+        }
+
+        let piece = Piece { piece_type, color };
+        let record = MoveRecord {
+            mv,
+            piece,
+            captured: None,
+            castling: None,
+            explosion: None,
+            previous_castling_rights: self.castling_rights,
+            previous_checks_given: self.checks_given,
+            previous_pockets: self.pockets,
+            previous_promoted: self.promoted,
+            previous_en_passant_target: self.en_passant_target,
+            previous_halfmove_clock: self.halfmove_clock,
+        };
+        self.apply_drop_unchecked(mv.to, piece);
+        self.move_history.push(record);
+        self.redo_moves.clear();
+        self.record_position();
+        Ok(())
+    }
+
+    /// Takes `piece` out of the pocket onto `to` and passes the turn.
+    fn apply_drop_unchecked(&mut self, to: Square, piece: Piece) {
+        let previous_pockets = self.pockets;
+        self.zobrist_key ^= self.en_passant_key();
+
+        self.set_piece(to, Some(piece));
+        self.pockets[color_index(piece.color)][piece_index(piece.piece_type)] -= 1;
+        self.halfmove_clock += 1;
+        self.en_passant_target = None;
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opposite();
+
+        self.zobrist_key ^= pockets_key(previous_pockets)
+            ^ pockets_key(self.pockets)
+            ^ side_key(Color::Black)
+            ^ self.en_passant_key();
+        debug_assert_eq!(
+            self.zobrist_key,
+            self.compute_zobrist_key(),
+            "incremental Zobrist key diverged after dropping on {to}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(fen: &str) -> GameState {
+        GameState::from_fen(fen)
+            .expect("valid FEN")
+            .with_variant(Variant::Crazyhouse)
+    }
+
+    fn play(game: &mut GameState, text: &str) {
+        let mv = game.parse_move(text).expect("legal move");
+        game.apply_move(mv).expect("legal move");
+    }
+
+    fn square(name: &str) -> Square {
+        name.parse().expect("square")
+    }
+
+    #[test]
+    fn captured_piece_goes_to_the_capturer() {
+        let mut game = state("4k3/8/8/3n4/8/8/8/3RK3 w - - 0 1");
+        play(&mut game, "Rxd5");
+        assert_eq!(game.pocket_count(Color::White, PieceType::Knight), 1);
+        assert_eq!(game.pocket_text(Color::White), "N");
+        assert_eq!(game.pocket_text(Color::Black), "-");
+
+        game.undo_move().expect("a move to undo");
+        assert_eq!(game.pocket_count(Color::White, PieceType::Knight), 0);
+    }
+
+    #[test]
+    fn captured_promoted_piece_goes_back_as_a_pawn() {
+        let mut game = state("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        play(&mut game, "b8=Q+");
+        assert!(game.is_promoted_piece(square("b8")));
+        play(&mut game, "Rxb8");
+        assert_eq!(game.pocket_count(Color::Black, PieceType::Pawn), 1);
+        assert_eq!(game.pocket_count(Color::Black, PieceType::Queen), 0);
+        assert!(!game.is_promoted_piece(square("b8")));
+    }
+
+    #[test]
+    fn pawns_are_not_dropped_on_the_back_ranks() {
+        let mut game = state("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1");
+        for name in ["a1", "h8"] {
+            assert_eq!(
+                game.apply_move(Move::drop(PieceType::Pawn, square(name))),
+                Err(MoveError::PawnDropOnBackRank)
+            );
+        }
+        assert_eq!(
+            game.apply_move(Move::drop(PieceType::Knight, square("e4"))),
+            Err(MoveError::NotInPocket)
+        );
+        assert_eq!(
+            game.apply_move(Move::drop(PieceType::Pawn, square("e1"))),
+            Err(MoveError::DropOnOccupiedSquare)
+        );
+        play(&mut game, "P@e4");
+        assert_eq!(game.pocket_text(Color::White), "-");
+    }
+
+    #[test]
+    fn only_blocking_drops_answer_a_check() {
+        let mut game = state("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1");
+        let mut drops = game
+            .generate_legal_moves()
+            .into_iter()
+            .filter(|mv| mv.drop.is_some())
+            .map(|mv| mv.to.to_string())
+            .collect::<Vec<_>>();
+        drops.sort();
+        assert_eq!(drops, ["b1", "c1", "d1"]);
+        assert_eq!(
+            game.apply_move(Move::drop(PieceType::Knight, square("c3"))),
+            Err(MoveError::KingWouldBeInCheck)
+        );
+        play(&mut game, "N@c1");
+    }
+
+    #[test]
+    fn pockets_round_trip_through_fen() {
+        let fen = "r3k3/8/8/8/8/8/8/4K3[QNPnpp] w - - 0 1";
+        let game = state(fen);
+        assert_eq!(game.pocket_text(Color::White), "PNQ");
+        assert_eq!(game.pocket_text(Color::Black), "PPN");
+        // Written White first, pawns first.
+        let written = game.to_fen();
+        assert_eq!(written, "r3k3/8/8/8/8/8/8/4K3[PNQppn] w - - 0 1");
+        assert_eq!(state(&written).zobrist_key(), game.zobrist_key());
+        assert_eq!(
+            state("r3k3/8/8/8/8/8/8/4K3[] w - - 0 1").pocket_text(Color::White),
+            "-"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use crate::game_logic::chess::{Color, GameOutcome, GameState, PieceType, Square, Variant};
+
+const FIFTY_MOVE_RULE_PLIES: u32 = 100;
+const SEVENTY_FIVE_MOVE_RULE_PLIES: u32 = 150;
+
+impl GameState {
+    /// Positions are compared by Zobrist key, which already treats an en
+    /// passant square nobody can use as absent.
+    pub(super) fn record_position(&mut self) {
+        self.position_history.push(self.zobrist_key);
+    }
+
+    /// Number of times the current position has occurred, counting only
+    /// positions since the last capture or pawn move.
+    pub fn repetition_count(&self) -> usize {
+        let Some(current) = self.position_history.last() else {
+            return 0;
+        };
+
+        self.position_history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize + 1)
+            .filter(|key| *key == current)
+            .count()
+    }
+
+    /// A draw the side to move may claim: the fifty-move rule or threefold
+    /// repetition.
+    pub fn claimable_draw(&self) -> Option<GameOutcome> {
+        if self.repetition_count() >= 3 {
+            Some(GameOutcome::ThreefoldRepetition)
+        } else if self.halfmove_clock >= FIFTY_MOVE_RULE_PLIES {
+            Some(GameOutcome::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// A draw that ends the game without a claim: fivefold repetition, the
+    /// 75-move rule, or neither side having enough material to mate.
+    pub fn automatic_draw(&self) -> Option<GameOutcome> {
+        if self.repetition_count() >= 5 {
+            Some(GameOutcome::FivefoldRepetition)
+        } else if self.halfmove_clock >= SEVENTY_FIVE_MOVE_RULE_PLIES {
+            Some(GameOutcome::SeventyFiveMoveRule)
+        } else if self.has_insufficient_material() {
+            Some(GameOutcome::InsufficientMaterial)
+        } else {
+            None
+        }
+    }
+
+    /// King against king, king and minor piece against king, or kings with
+    /// bishops that all stand on squares of one color. Never true in a
+    /// variant, where a lone king can still win.
+    pub fn has_insufficient_material(&self) -> bool {
+        if self.variant != Variant::Standard {
+            return false;
+        }
+
+        let mut knights = 0;
+        let mut bishop_square_colors = Vec::new();
+
+        for (square, piece) in self.iter_pieces() {
+            match piece.piece_type {
+                PieceType::King => {}
+                PieceType::Knight => knights += 1,
+                PieceType::Bishop => {
+                    bishop_square_colors.push((square.file() + square.rank()) % 2);
+                }
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen => return false,
+            }
+        }
+
+        match (knights, bishop_square_colors.as_slice()) {
+            (0 | 1, []) => true,
+            (0, [first, rest @ ..]) => rest.iter().all(|color| color == first),
+            _ => false,
+        }
+    }
+
+    /// Whether some legal sequence of moves could end with `color` giving
+    /// mate, the FIDE test for whether running out of time against `color`
+    /// loses or draws. The opponent's men count: they can block their own
+    /// king in. Follows the usual approximation: a lone knight needs
+    /// something other than queens to mate against, and bishops can never
+    /// mate while every bishop on the board stands on one square color and
+    /// there are no pawns or knights. Always true in a variant.
+    pub fn can_possibly_mate(&self, color: Color) -> bool {
+        if self.variant != Variant::Standard {
+            return true;
+        }
+
+        let mut own_knights = 0;
+        let mut own_bishops = 0;
+        let mut opponent_blockers = 0;
+        let mut pawns_or_knights = 0;
+        let mut bishop_square_colors = Vec::new();
+        for (square, piece) in self.iter_pieces() {
+            let own = piece.color == color;
+            match piece.piece_type {
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen if own => return true,
+                PieceType::King => continue,
+                PieceType::Knight if own => own_knights += 1,
+                PieceType::Bishop if own => own_bishops += 1,
+                _ => {}
+            }
+            if !own && piece.piece_type != PieceType::Queen {
+                opponent_blockers += 1;
+            }
+            match piece.piece_type {
+                PieceType::Pawn | PieceType::Knight => pawns_or_knights += 1,
+                PieceType::Bishop => bishop_square_colors.push((square.file() + square.rank()) % 2),
+                _ => {}
+            }
+        }
+
+        if own_knights > 0 {
+            return own_knights + own_bishops > 1 || opponent_blockers > 0;
+        }
+        if own_bishops > 0 {
+            let one_square_color = bishop_square_colors
+                .iter()
+                .all(|square_color| *square_color == bishop_square_colors[0]);
+            return !one_square_color || pawns_or_knights > 0;
+        }
+        false
+    }
+
+    pub(super) fn can_capture_en_passant(&self, target: Square) -> bool {
+        let capturer_rank = match self.side_to_move {
+            Color::White => target.rank().checked_sub(1),
+            Color::Black => Some(target.rank() + 1),
+        };
+        let Some(capturer_rank) = capturer_rank else {
+            return false;
+        };
+
+        [-1i8, 1].into_iter().any(|offset| {
+            let file = target.file() as i8 + offset;
+            (0..8).contains(&file)
+                && Square::new(file as u8, capturer_rank)
+                    .and_then(|square| self.piece_at(square))
+                    .is_some_and(|piece| {
+                        piece.piece_type == PieceType::Pawn && piece.color == self.side_to_move
+                    })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_logic::chess::{Color, GameOutcome, GameState};
+
+    fn play(state: &mut GameState, moves: &[&str]) {
+        for text in moves {
+            let mv = state.parse_san(text).expect("legal move");
+            state.apply_move(mv).expect("legal move");
+        }
+    }
+
+    fn state(fen: &str) -> GameState {
+        GameState::from_fen(fen).expect("valid FEN")
+    }
+
+    const KNIGHT_SHUFFLE: [&str; 4] = ["Nf3", "Nf6", "Ng1", "Ng8"];
+
+    #[test]
+    fn knight_shuffle_repeats_to_a_threefold_claim() {
+        let mut state = GameState::new_start_position();
+        play(&mut state, &KNIGHT_SHUFFLE);
+        assert_eq!(state.repetition_count(), 2);
+        assert_eq!(state.claimable_draw(), None);
+
+        play(&mut state, &KNIGHT_SHUFFLE);
+        assert_eq!(state.repetition_count(), 3);
+        assert_eq!(
+            state.claimable_draw(),
+            Some(GameOutcome::ThreefoldRepetition)
+        );
+        assert_eq!(state.automatic_draw(), None);
+    }
+
+    #[test]
+    fn en_passant_right_makes_a_position_differ() {
+        // After e4 Black can take en passant; after the king walk it cannot.
+        let mut state = state("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1");
+        let walk = ["Kd8", "Kd1", "Ke8", "Ke1"];
+        play(&mut state, &["e4"]);
+        play(&mut state, &walk);
+        assert_eq!(state.repetition_count(), 1);
+        play(&mut state, &walk);
+        assert_eq!(state.repetition_count(), 2);
+    }
+
+    #[test]
+    fn castling_right_makes_a_position_differ() {
+        let mut state = state("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        let walk = ["Rh2", "Kd8", "Rh1", "Ke8"];
+        play(&mut state, &walk);
+        assert_eq!(state.repetition_count(), 1);
+        play(&mut state, &walk);
+        assert_eq!(state.repetition_count(), 2);
+    }
+
+    #[test]
+    fn fifty_move_claim_starts_at_halfmove_one_hundred() {
+        let mut state = state("4k3/8/8/8/8/8/8/4K2R w - - 98 80");
+        play(&mut state, &["Rh2"]);
+        assert_eq!(state.claimable_draw(), None);
+        play(&mut state, &["Kd8"]);
+        assert_eq!(state.halfmove_clock(), 100);
+        assert_eq!(state.claimable_draw(), Some(GameOutcome::FiftyMoveRule));
+    }
+
+    #[test]
+    fn capture_or_pawn_move_resets_repetitions() {
+        let mut state = state("r3k3/8/8/8/8/8/4P3/R3K3 w - - 0 1");
+        let walk = ["Kd1", "Kd8", "Ke1", "Ke8"];
+        play(&mut state, &walk);
+        assert_eq!(state.repetition_count(), 2);
+
+        play(&mut state, &["e3"]);
+        assert_eq!(state.halfmove_clock(), 0);
+        assert_eq!(state.repetition_count(), 1);
+
+        // Only the position right after e3 comes back, not the two before.
+        play(&mut state, &["Kd8", "Kd1", "Ke8", "Ke1"]);
+        assert_eq!(state.repetition_count(), 2);
+        play(&mut state, &["Rxa1+"]);
+        assert_eq!(state.halfmove_clock(), 0);
+        assert_eq!(state.repetition_count(), 1);
+    }
+
+    fn can_mate(fen: &str, color: Color) -> bool {
+        state(fen).can_possibly_mate(color)
+    }
+
+    #[test]
+    fn lone_king_never_mates() {
+        assert!(!can_mate("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", Color::Black));
+        assert!(can_mate("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", Color::White));
+    }
+
+    #[test]
+    fn lone_knight_needs_something_to_mate_against() {
+        assert!(!can_mate("4k3/8/8/8/8/8/8/3NK3 w - - 0 1", Color::White));
+        assert!(can_mate("4k3/4p3/8/8/8/8/8/3NK3 w - - 0 1", Color::White));
+        assert!(!can_mate("3qk3/8/8/8/8/8/8/3NK3 w - - 0 1", Color::White));
+        assert!(can_mate("4k3/8/8/8/8/8/8/2NNK3 w - - 0 1", Color::White));
+    }
+
+    #[test]
+    fn bishops_on_one_square_color_never_mate() {
+        // c1 and f4 are both dark, as is the black bishop on b8.
+        assert!(!can_mate(
+            "1b2k3/8/8/8/5B2/8/8/2B1K3 w - - 0 1",
+            Color::White
+        ));
+        assert!(!can_mate(
+            "1b2k3/8/8/8/5B2/8/8/2B1K3 w - - 0 1",
+            Color::Black
+        ));
+        // The light-squared bishop on e6 can be mated against.
+        assert!(can_mate("4k3/8/4b3/8/8/8/8/2B1K3 w - - 0 1", Color::White));
+        assert!(can_mate("4k3/8/8/8/8/8/8/2BBK3 w - - 0 1", Color::White));
+        assert!(can_mate("4k3/8/8/8/8/8/4n3/2B1K3 w - - 0 1", Color::White));
+    }
+}
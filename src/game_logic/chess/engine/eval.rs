@@ -0,0 +1,165 @@
+use crate::game_logic::chess::bitboard::{PIECE_TYPES, piece_index, squares};
+use crate::game_logic::chess::{Color, GameState, PieceType, Square};
+
+/// Material values in centipawns, indexed by `piece_index`.
+pub(super) const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+/// Non-pawn material (both sides) at or below which the king table switches
+/// to its endgame form.
+const ENDGAME_MATERIAL: i32 = 1300;
+
+// Piece-square tables from White's point of view, written rank 8 first so
+// they read like a diagram.
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// Static evaluation in centipawns from the side to move's point of view.
+pub(super) fn evaluate(state: &GameState) -> i32 {
+    let non_pawn_material = [Color::White, Color::Black]
+        .into_iter()
+        .flat_map(|color| {
+            [
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+            ]
+            .map(|piece_type| {
+                state.pieces(color, piece_type).count_ones() as i32
+                    * PIECE_VALUES[piece_index(piece_type)]
+            })
+        })
+        .sum::<i32>();
+    let is_endgame = non_pawn_material <= ENDGAME_MATERIAL;
+
+    let white_score = side_score(state, Color::White, is_endgame);
+    let black_score = side_score(state, Color::Black, is_endgame);
+    match state.side_to_move() {
+        Color::White => white_score - black_score,
+        Color::Black => black_score - white_score,
+    }
+}
+
+/// Material and placement of `color`'s pieces, plus the bare material of
+/// any Crazyhouse pocket.
+fn side_score(state: &GameState, color: Color, is_endgame: bool) -> i32 {
+    let board = PIECE_TYPES
+        .into_iter()
+        .map(|piece_type| {
+            let table = piece_table(piece_type, is_endgame);
+            squares(state.pieces(color, piece_type))
+                .map(|square| {
+                    PIECE_VALUES[piece_index(piece_type)] + table[table_index(square, color)]
+                })
+                .sum::<i32>()
+        })
+        .sum::<i32>();
+    let pocket = state
+        .pocket(color)
+        .map(|(piece_type, count)| PIECE_VALUES[piece_index(piece_type)] * i32::from(count))
+        .sum::<i32>();
+    board + pocket
+}
+
+fn piece_table(piece_type: PieceType, is_endgame: bool) -> &'static [i32; 64] {
+    match piece_type {
+        PieceType::Pawn => &PAWN_TABLE,
+        PieceType::Knight => &KNIGHT_TABLE,
+        PieceType::Bishop => &BISHOP_TABLE,
+        PieceType::Rook => &ROOK_TABLE,
+        PieceType::Queen => &QUEEN_TABLE,
+        PieceType::King if is_endgame => &KING_ENDGAME_TABLE,
+        PieceType::King => &KING_MIDDLEGAME_TABLE,
+    }
+}
+
+/// Tables are drawn from White's side with rank 8 on top; Black reads them
+/// mirrored.
+fn table_index(square: Square, color: Color) -> usize {
+    let rank = match color {
+        Color::White => 7 - square.rank(),
+        Color::Black => square.rank(),
+    };
+    rank as usize * 8 + square.file() as usize
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::game_logic::chess::GameState;
+use crate::game_logic::chess::uci::{UciEngine, UciError, UciSearchRequest};
+
+mod eval;
+mod search;
+
+pub use search::{MATE_SCORE, SearchLimits, SearchResult, search};
+
+/// A search running on its own thread, either the built-in engine or an
+/// external UCI engine. Dropping the handle stops it.
+pub struct EngineSearch {
+    receiver: Receiver<Result<SearchResult, UciError>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl EngineSearch {
+    pub fn start(state: GameState, limits: SearchLimits) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        thread::spawn(move || {
+            let result = search(&state, limits, &thread_stop, |_| {});
+            let _ = sender.send(Ok(result));
+        });
+
+        Self { receiver, stop }
+    }
+
+    /// Queues the search on an external engine's worker thread. Dropping
+    /// the handle mid-search sends the engine `stop`.
+    pub fn start_external(engine: &UciEngine, state: GameState, limits: SearchLimits) -> Self {
+        let (reply, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        engine.submit(UciSearchRequest {
+            state,
+            limits,
+            reply,
+            cancelled: Arc::clone(&stop),
+        });
+
+        Self { receiver, stop }
+    }
+
+    /// The finished result, without blocking.
+    pub fn try_result(&self) -> Option<Result<SearchResult, UciError>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for EngineSearch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,458 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::game_logic::chess::bitboard::piece_index;
+use crate::game_logic::chess::engine::eval::{PIECE_VALUES, evaluate};
+use crate::game_logic::chess::{GameState, Move, PieceType};
+
+/// Score of a mate delivered at the root; mates further away score lower.
+pub const MATE_SCORE: i32 = 30_000;
+const INFINITY: i32 = 32_000;
+const MAX_PLY: usize = 128;
+const MAX_DEPTH: u32 = 64;
+const TRANSPOSITION_TABLE_SIZE: usize = 1 << 18;
+const NODES_BETWEEN_STOP_CHECKS: u64 = 2048;
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct SearchLimits {
+    pub max_depth: Option<u32>,
+    pub move_time: Option<Duration>,
+}
+
+impl SearchLimits {
+    pub fn move_time(move_time: Duration) -> Self {
+        Self {
+            max_depth: None,
+            move_time: Some(move_time),
+        }
+    }
+}
+
+/// Outcome of the deepest completed iteration. `score` is in centipawns
+/// from the side to move's point of view, or `MATE_SCORE - plies` for a
+/// forced mate.
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub principal_variation: Vec<Move>,
+}
+
+impl SearchResult {
+    /// Moves until mate (positive when the side to move mates), if the score
+    /// is a mate score.
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score.abs() < MATE_SCORE - MAX_PLY as i32 {
+            return None;
+        }
+        let plies = MATE_SCORE - self.score.abs();
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Copy, Clone)]
+struct TranspositionEntry {
+    key: u64,
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best_move: Option<Move>,
+}
+
+/// Iterative-deepening negamax with alpha-beta, quiescence search and a
+/// transposition table. `on_iteration` is called after every completed
+/// depth; the search stops at the limits or when `stop` is set.
+pub fn search(
+    state: &GameState,
+    limits: SearchLimits,
+    stop: &AtomicBool,
+    mut on_iteration: impl FnMut(&SearchResult),
+) -> SearchResult {
+    let mut searcher = Searcher {
+        state: state.clone(),
+        table: vec![None; TRANSPOSITION_TABLE_SIZE],
+        killers: [[None; 2]; MAX_PLY],
+        nodes: 0,
+        started: Instant::now(),
+        deadline: limits.move_time.map(|time| Instant::now() + time),
+        stop,
+        aborted: false,
+        root_best_move: None,
+    };
+
+    let root_moves = searcher.state.generate_legal_moves();
+    let mut result = SearchResult {
+        best_move: root_moves.first().copied(),
+        ..SearchResult::default()
+    };
+    if root_moves.is_empty() {
+        return result;
+    }
+
+    let max_depth = limits.max_depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+    for depth in 1..=max_depth {
+        searcher.root_best_move = None;
+        let score = searcher.negamax(depth, 0, -INFINITY, INFINITY);
+        if searcher.aborted {
+            break;
+        }
+
+        result = SearchResult {
+            best_move: searcher.root_best_move.or(result.best_move),
+            score,
+            depth,
+            nodes: searcher.nodes,
+            elapsed: searcher.started.elapsed(),
+            principal_variation: searcher.principal_variation(depth),
+        };
+        on_iteration(&result);
+
+        if score.abs() >= MATE_SCORE - depth as i32 {
+            break;
+        }
+    }
+
+    result.nodes = searcher.nodes;
+    result.elapsed = searcher.started.elapsed();
+    result
+}
+
+struct Searcher<'a> {
+    state: GameState,
+    table: Vec<Option<TranspositionEntry>>,
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    nodes: u64,
+    started: Instant,
+    deadline: Option<Instant>,
+    stop: &'a AtomicBool,
+    aborted: bool,
+    root_best_move: Option<Move>,
+}
+
+impl Searcher<'_> {
+    fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply > 0 && self.state.variant_outcome().is_some() {
+            return -MATE_SCORE + ply as i32;
+        }
+        if ply > 0 && self.is_draw() {
+            return 0;
+        }
+
+        let in_check = self.state.is_in_check(self.state.side_to_move());
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        let key = self.state.zobrist_key();
+        let table_move = match self.probe(key) {
+            Some(entry) => {
+                if ply > 0 && entry.depth >= depth {
+                    let score = score_from_table(entry.score, ply);
+                    match entry.bound {
+                        Bound::Exact => return score,
+                        Bound::Lower if score >= beta => return score,
+                        Bound::Upper if score <= alpha => return score,
+                        _ => {}
+                    }
+                }
+                entry.best_move
+            }
+            None => None,
+        };
+
+        let mut moves = self.state.generate_legal_moves();
+        if moves.is_empty() {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+        self.order_moves(&mut moves, table_move, ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for mv in moves {
+            let is_quiet = self.is_quiet(mv);
+            if self.state.apply_move(mv).is_err() {
+                continue;
+            }
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            self.state.undo_move();
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                if ply == 0 {
+                    self.root_best_move = Some(mv);
+                }
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                if is_quiet {
+                    self.store_killer(mv, ply);
+                }
+                break;
+            }
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.store(TranspositionEntry {
+            key,
+            depth,
+            score: score_to_table(best_score, ply),
+            bound,
+            best_move,
+        });
+
+        best_score
+    }
+
+    /// Searches captures and promotions only, so the static evaluation is
+    /// never taken in the middle of an exchange.
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if self.state.variant_outcome().is_some() {
+            return -MATE_SCORE + ply as i32;
+        }
+        let stand_pat = evaluate(&self.state);
+        if stand_pat >= beta || ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves = self
+            .state
+            .generate_legal_moves()
+            .into_iter()
+            .filter(|mv| !self.is_quiet(*mv))
+            .collect::<Vec<_>>();
+        self.order_moves(&mut moves, None, ply);
+
+        for mv in moves {
+            if self.state.apply_move(mv).is_err() {
+                continue;
+            }
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.state.undo_move();
+            if self.aborted {
+                return 0;
+            }
+
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        if self.nodes.is_multiple_of(NODES_BETWEEN_STOP_CHECKS) {
+            let out_of_time = self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+            self.aborted = out_of_time || self.stop.load(Ordering::Relaxed);
+        }
+        self.aborted
+    }
+
+    fn is_draw(&self) -> bool {
+        self.state.halfmove_clock() >= 100
+            || self.state.repetition_count() >= 2
+            || self.state.has_insufficient_material()
+    }
+
+    fn is_quiet(&self, mv: Move) -> bool {
+        self.captured_value(mv).is_none() && mv.promotion.is_none()
+    }
+
+    /// Value of the piece `mv` captures, counting en passant.
+    fn captured_value(&self, mv: Move) -> Option<i32> {
+        if let Some(target) = self.state.piece_at(mv.to) {
+            return Some(PIECE_VALUES[piece_index(target.piece_type)]);
+        }
+        let is_en_passant = self
+            .state
+            .piece_at(mv.from)
+            .is_some_and(|piece| piece.piece_type == PieceType::Pawn)
+            && mv.from.file() != mv.to.file();
+        is_en_passant.then_some(PIECE_VALUES[piece_index(PieceType::Pawn)])
+    }
+
+    /// Transposition table move first, then captures by most valuable
+    /// victim / least valuable attacker, promotions, killers and the rest.
+    fn order_moves(&self, moves: &mut [Move], table_move: Option<Move>, ply: usize) {
+        moves.sort_by_cached_key(|mv| {
+            if Some(*mv) == table_move {
+                return i32::MIN;
+            }
+            let mut priority = 0;
+            if let Some(victim) = self.captured_value(*mv) {
+                let attacker = self
+                    .state
+                    .piece_at(mv.from)
+                    .map_or(0, |piece| PIECE_VALUES[piece_index(piece.piece_type)]);
+                priority -= 10_000 + victim * 10 - attacker / 10;
+            }
+            if let Some(promotion) = mv.promotion {
+                priority -= 5_000 + PIECE_VALUES[piece_index(promotion)];
+            }
+            if priority == 0 && self.killers[ply].contains(&Some(*mv)) {
+                priority -= 1_000;
+            }
+            priority
+        });
+    }
+
+    fn store_killer(&mut self, mv: Move, ply: usize) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+    }
+
+    fn probe(&self, key: u64) -> Option<TranspositionEntry> {
+        self.table[key as usize % TRANSPOSITION_TABLE_SIZE].filter(|entry| entry.key == key)
+    }
+
+    fn store(&mut self, entry: TranspositionEntry) {
+        let slot = &mut self.table[entry.key as usize % TRANSPOSITION_TABLE_SIZE];
+        if slot.is_none_or(|existing| existing.key != entry.key || existing.depth <= entry.depth) {
+            *slot = Some(entry);
+        }
+    }
+
+    /// Follows transposition table moves from the root.
+    fn principal_variation(&mut self, depth: u32) -> Vec<Move> {
+        let mut line = Vec::new();
+        while line.len() < depth as usize {
+            let Some(mv) = self
+                .probe(self.state.zobrist_key())
+                .and_then(|entry| entry.best_move)
+            else {
+                break;
+            };
+            if self.state.apply_move(mv).is_err() {
+                break;
+            }
+            line.push(mv);
+        }
+        for _ in &line {
+            self.state.undo_move();
+        }
+        line
+    }
+}
+
+/// Mate scores are stored relative to the node so they stay correct when
+/// the entry is reached at a different ply.
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_SCORE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_SCORE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search_to_depth(fen: &str, depth: u32) -> SearchResult {
+        let state = GameState::from_fen(fen).expect("valid FEN");
+        let limits = SearchLimits {
+            max_depth: Some(depth),
+            move_time: None,
+        };
+        search(&state, limits, &AtomicBool::new(false), |_| {})
+    }
+
+    fn uci(text: &str) -> Option<Move> {
+        Some(text.parse().expect("move"))
+    }
+
+    #[test]
+    fn finds_a_back_rank_mate_in_one() {
+        let result = search_to_depth("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+        assert_eq!(result.best_move, uci("a1a8"));
+        assert_eq!(result.mate_in(), Some(1));
+    }
+
+    #[test]
+    fn finds_a_rook_ladder_mate_in_two() {
+        // Ra7 or Rb7 cuts the king off before the other rook mates.
+        let result = search_to_depth("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 5);
+        assert_eq!(result.mate_in(), Some(2));
+        assert!(matches!(
+            result.best_move.map(|mv| mv.to_string()).as_deref(),
+            Some("a2a7" | "b1b7")
+        ));
+    }
+
+    #[test]
+    fn plays_the_only_legal_move() {
+        let fen = "1r5k/8/8/8/8/8/8/K6r w - - 0 1";
+        let state = GameState::from_fen(fen).expect("valid FEN");
+        assert_eq!(state.generate_legal_moves().len(), 1);
+        for depth in 1..=4 {
+            assert_eq!(search_to_depth(fen, depth).best_move, uci("a1a2"));
+        }
+    }
+
+    #[test]
+    fn returns_no_move_when_mated() {
+        let result = search_to_depth("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", 3);
+        assert_eq!(result.best_move, None);
+    }
+}
@@ -0,0 +1,237 @@
+use std::io::Write;
+
+use crate::game_logic::chess::{
+    Color, GameOutcome, GameState, Move, MoveError, Piece, Square, game_event_message,
+};
+
+/// Something that happened in a game. A move produces `MoveMade` first,
+/// then whichever of the others it caused, in the order listed here.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GameEvent {
+    MoveMade {
+        mv: Move,
+        /// The piece that moved, before any promotion.
+        piece: Piece,
+        san: String,
+    },
+    /// One for the captured piece, and in Atomic one more for every piece
+    /// the blast destroyed, the capturer included.
+    Captured {
+        square: Square,
+        piece: Piece,
+    },
+    Castled {
+        color: Color,
+        kingside: bool,
+    },
+    /// An en passant capture; the captured pawn stood on `captured`.
+    EnPassant {
+        captured: Square,
+    },
+    Promoted {
+        square: Square,
+        piece: Piece,
+    },
+    /// `color`'s king is in check.
+    Check {
+        color: Color,
+    },
+    /// The side to move may claim this draw, if the game goes on.
+    DrawClaimable(GameOutcome),
+    GameOver(GameOutcome),
+}
+
+/// Anything that reacts to game events: the scene's animations and
+/// messages, or a log or sound player.
+pub trait GameEventSubscriber {
+    fn on_game_event(&mut self, event: &GameEvent);
+}
+
+/// Writes one line per event, for following a game from a terminal.
+pub struct EventLog<W> {
+    output: W,
+}
+
+impl<W: Write> EventLog<W> {
+    pub fn new(output: W) -> Self {
+        Self { output }
+    }
+}
+
+impl<W: Write> GameEventSubscriber for EventLog<W> {
+    fn on_game_event(&mut self, event: &GameEvent) {
+        let _ = writeln!(self.output, "{}", game_event_message(event));
+    }
+}
+
+impl GameState {
+    /// Applies `mv` like `apply_move` and reports what it did, ending with
+    /// `GameOver` when the rules finish the game in the new position, or
+    /// `DrawClaimable` when the side to move could claim a draw.
+    pub fn play_move(&mut self, mv: Move) -> Result<Vec<GameEvent>, MoveError> {
+        let piece = self.moving_piece(mv).ok_or(MoveError::NoPieceAtSource)?;
+        // Everything but the check suffix depends on the position before
+        // the move, so the move is only checked and made once.
+        let san = self.san_body(mv, piece);
+        self.apply_move(mv)?;
+        let Some(record) = self.move_history().last().copied() else {
+            return Ok(Vec::new());
+        };
+
+        let mut events = vec![GameEvent::MoveMade {
+            mv,
+            piece: record.piece,
+            san: san + self.check_suffix(),
+        }];
+        if let Some((square, piece)) = record.captured {
+            events.push(GameEvent::Captured { square, piece });
+        }
+        if record.explosion.is_some() {
+            events.push(GameEvent::Captured {
+                square: record.destination(),
+                piece: record.piece,
+            });
+        }
+        for (square, piece) in record.exploded_pieces() {
+            events.push(GameEvent::Captured { square, piece });
+        }
+        if record.castling_rook_squares().is_some() {
+            events.push(GameEvent::Castled {
+                color: record.piece.color,
+                kingside: record.destination().file() == 6,
+            });
+        }
+        if let Some((captured, _)) = record.captured
+            && captured != mv.to
+        {
+            events.push(GameEvent::EnPassant { captured });
+        }
+        if mv.promotion.is_some()
+            && record.explosion.is_none()
+            && let Some(piece) = self.piece_at(mv.to)
+        {
+            events.push(GameEvent::Promoted {
+                square: mv.to,
+                piece,
+            });
+        }
+        let side_to_move = self.side_to_move();
+        if self.is_in_check(side_to_move) {
+            events.push(GameEvent::Check {
+                color: side_to_move,
+            });
+        }
+        if let Some(outcome) = self.game_outcome() {
+            events.push(GameEvent::GameOver(outcome));
+        } else if let Some(claimable) = self.claimable_draw() {
+            events.push(GameEvent::DrawClaimable(claimable));
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::chess::PieceType;
+
+    fn play(fen: &str, text: &str) -> Vec<GameEvent> {
+        let mut state = GameState::from_fen(fen).expect("valid FEN");
+        let mv = state.parse_move(text).expect("legal move");
+        let san = state.move_to_san(mv).expect("legal move");
+        let events = state.play_move(mv).expect("legal move");
+        assert!(
+            matches!(&events[0], GameEvent::MoveMade { san: played, .. } if *played == san),
+            "{events:?}"
+        );
+        events
+    }
+
+    fn square(name: &str) -> Square {
+        name.parse().expect("square")
+    }
+
+    #[test]
+    fn castling_reports_the_side() {
+        let events = play("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "O-O-O");
+        assert_eq!(
+            events[1..],
+            [GameEvent::Castled {
+                color: Color::Black,
+                kingside: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn en_passant_reports_the_captured_pawn() {
+        let events = play("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6");
+        let pawn = Piece {
+            piece_type: PieceType::Pawn,
+            color: Color::Black,
+        };
+        assert_eq!(
+            events[1..],
+            [
+                GameEvent::Captured {
+                    square: square("d5"),
+                    piece: pawn,
+                },
+                GameEvent::EnPassant {
+                    captured: square("d5"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn promotion_with_mate_ends_the_game() {
+        let events = play("6k1/4P3/6K1/8/8/8/8/8 w - - 0 1", "e8=Q#");
+        let queen = Piece {
+            piece_type: PieceType::Queen,
+            color: Color::White,
+        };
+        assert!(
+            matches!(&events[0], GameEvent::MoveMade { san, .. } if san == "e8=Q#"),
+            "{events:?}"
+        );
+        assert_eq!(
+            events[1..],
+            [
+                GameEvent::Promoted {
+                    square: square("e8"),
+                    piece: queen,
+                },
+                GameEvent::Check {
+                    color: Color::Black,
+                },
+                GameEvent::GameOver(GameOutcome::Checkmate {
+                    winner: Color::White,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn redo_reports_the_move_again() {
+        let mut state =
+            GameState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").expect("valid FEN");
+        let mv = state.parse_move("exd6").expect("legal move");
+        let played = state.play_move(mv).expect("legal move");
+        assert!(state.undo_move().is_some());
+        assert_eq!(state.redo_move(), Some(played));
+        assert_eq!(state.redo_move(), None);
+    }
+
+    #[test]
+    fn illegal_move_leaves_the_position_alone() {
+        let mut state = GameState::new_start_position();
+        let mv = Move::new(square("e2"), square("e5"));
+        assert!(state.play_move(mv).is_err());
+        assert!(state.move_history().is_empty());
+        assert_eq!(
+            state.play_move(Move::new(square("e4"), square("e5"))),
+            Err(MoveError::NoPieceAtSource)
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use crate::game_logic::chess::bitboard::PIECE_TYPES;
+use crate::game_logic::chess::state::{CastlingRights, home_rank};
+use crate::game_logic::chess::{Color, GameState, Piece, PieceType, Square, Variant};
+
+pub const START_POSITION_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FenError {
+    WrongFieldCount(usize),
+    WrongRankCount(usize),
+    InvalidRankLength { rank: u8, squares: usize },
+    InvalidPiece(char),
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassantSquare(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    InvalidCheckCount(String),
+}
+
+impl GameState {
+    /// Parses a position in Forsyth-Edwards Notation. The halfmove clock and
+    /// fullmove number may be omitted (as in EPD), defaulting to `0 1`. A
+    /// Crazyhouse pocket may follow the placement in brackets, as in
+    /// `.../RNBQKBNR[Qn]`, and a last `+1+0` field gives the checks White
+    /// and Black have given in Three-check.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut fields = fen.split_whitespace().collect::<Vec<_>>();
+        let checks_given = match fields.last() {
+            Some(field) if fields.len() > 4 && field.starts_with('+') => {
+                let checks_given = parse_checks_given(field)?;
+                fields.pop();
+                checks_given
+            }
+            _ => [0; 2],
+        };
+        if !(4..=6).contains(&fields.len()) {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut game = Self::empty();
+        let (placement, pocket) = match fields[0].strip_suffix(']') {
+            Some(rest) => rest.split_once('[').unwrap_or((rest, "")),
+            None => (fields[0], ""),
+        };
+        parse_placement(&mut game, placement)?;
+        for symbol in pocket.chars() {
+            let piece = piece_from_char(symbol)
+                .filter(|piece| piece.piece_type != PieceType::King)
+                .ok_or(FenError::InvalidPiece(symbol))?;
+            game.add_to_pocket(piece.color, piece.piece_type);
+        }
+
+        game.side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::InvalidSideToMove(other.to_owned())),
+        };
+
+        game.castling_rights = parse_castling_rights(&game, fields[2])?;
+        game.chess960 = [Color::White, Color::Black].into_iter().any(|color| {
+            let king_moved_off_e = king_file(&game, color).is_some_and(|file| file != 4);
+            [(true, 7), (false, 0)]
+                .into_iter()
+                .any(|(kingside, standard_file)| {
+                    game.castling_rights
+                        .rook_file(color, kingside)
+                        .is_some_and(|file| file != standard_file || king_moved_off_e)
+                })
+        });
+
+        game.en_passant_target = match fields[3] {
+            "-" => None,
+            name => {
+                let square = name
+                    .parse::<Square>()
+                    .map_err(|_| FenError::InvalidEnPassantSquare(name.to_owned()))?;
+                let expected_rank = match game.side_to_move {
+                    Color::White => 5,
+                    Color::Black => 2,
+                };
+                if square.rank() != expected_rank {
+                    return Err(FenError::InvalidEnPassantSquare(name.to_owned()));
+                }
+                Some(square)
+            }
+        };
+
+        if let Some(field) = fields.get(4) {
+            game.halfmove_clock = field
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock((*field).to_owned()))?;
+        }
+
+        if let Some(field) = fields.get(5) {
+            game.fullmove_number = field
+                .parse()
+                .ok()
+                .filter(|number| *number > 0)
+                .ok_or_else(|| FenError::InvalidFullmoveNumber((*field).to_owned()))?;
+        }
+
+        game.checks_given = checks_given;
+        game.zobrist_key = game.compute_zobrist_key();
+        game.record_position();
+        Ok(game)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                let square = Square::new(file, rank).expect("valid board square");
+                match self.piece_at(square) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece_to_char(piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+
+        if self.variant == Variant::Crazyhouse {
+            placement.push('[');
+            for color in [Color::White, Color::Black] {
+                for piece_type in PIECE_TYPES {
+                    for _ in 0..self.pocket_count(color, piece_type) {
+                        placement.push(piece_to_char(Piece { piece_type, color }));
+                    }
+                }
+            }
+            placement.push(']');
+        }
+
+        let side_to_move = match self.side_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let mut castling = String::new();
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                let Some(file) = self.castling_rights.rook_file(color, kingside) else {
+                    continue;
+                };
+                let symbol = if !self.chess960 || default_rook_file(self, color, kingside) == file {
+                    if kingside { 'K' } else { 'Q' }
+                } else {
+                    (b'A' + file) as char
+                };
+                castling.push(match color {
+                    Color::White => symbol,
+                    Color::Black => symbol.to_ascii_lowercase(),
+                });
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant_target
+            .map(|square| square.to_string())
+            .unwrap_or_else(|| "-".to_owned());
+
+        let mut fen = format!(
+            "{placement} {side_to_move} {castling} {en_passant} {} {}",
+            self.halfmove_clock, self.fullmove_number
+        );
+        if self.variant == Variant::ThreeCheck {
+            fen.push_str(&checks_given_field(self.checks_given));
+        }
+        fen
+    }
+}
+
+/// ` +1+0`: checks given by White, then Black.
+pub(super) fn checks_given_field(checks_given: [u8; 2]) -> String {
+    format!(" +{}+{}", checks_given[0], checks_given[1])
+}
+
+fn parse_checks_given(field: &str) -> Result<[u8; 2], FenError> {
+    let invalid = || FenError::InvalidCheckCount(field.to_owned());
+    let (white, black) = field
+        .strip_prefix('+')
+        .and_then(|counts| counts.split_once('+'))
+        .ok_or_else(invalid)?;
+    // The third check ends the game, so no count goes past it.
+    let count = |text: &str| {
+        text.parse()
+            .ok()
+            .filter(|count| *count <= 3)
+            .ok_or_else(invalid)
+    };
+    Ok([count(white)?, count(black)?])
+}
+
+fn parse_placement(game: &mut GameState, placement: &str) -> Result<(), FenError> {
+    let ranks = placement.split('/').collect::<Vec<_>>();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+
+    for (row, rank_text) in ranks.into_iter().enumerate() {
+        let rank = 7 - row as u8;
+        let mut file = 0usize;
+        for symbol in rank_text.chars() {
+            if let Some(skip) = symbol.to_digit(10) {
+                if skip == 0 || skip > 8 {
+                    return Err(FenError::InvalidPiece(symbol));
+                }
+                file += skip as usize;
+                continue;
+            }
+
+            let piece = piece_from_char(symbol).ok_or(FenError::InvalidPiece(symbol))?;
+            if let Some(square) = Square::new(file as u8, rank) {
+                game.set_piece(square, Some(piece));
+            }
+            file += 1;
+        }
+
+        if file != 8 {
+            return Err(FenError::InvalidRankLength {
+                rank: rank + 1,
+                squares: file,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads `KQkq` as well as Shredder-FEN rook files such as `HAha`.
+fn parse_castling_rights(game: &GameState, field: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::default();
+    if field == "-" {
+        return Ok(rights);
+    }
+
+    let invalid = || FenError::InvalidCastlingRights(field.to_owned());
+    for symbol in field.chars() {
+        let color = if symbol.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let (kingside, rook_file) = match symbol.to_ascii_lowercase() {
+            'k' => (true, default_rook_file(game, color, true)),
+            'q' => (false, default_rook_file(game, color, false)),
+            file @ 'a'..='h' => {
+                let file = file as u8 - b'a';
+                (file > king_file(game, color).unwrap_or(4), file)
+            }
+            _ => return Err(invalid()),
+        };
+
+        let right = rights.rook_file_mut(color, kingside);
+        if right.is_some() {
+            return Err(invalid());
+        }
+        *right = Some(rook_file);
+    }
+
+    Ok(rights)
+}
+
+fn king_file(game: &GameState, color: Color) -> Option<u8> {
+    let king = Piece {
+        piece_type: PieceType::King,
+        color,
+    };
+    (0..8).find(|file| {
+        Square::new(*file, home_rank(color)).and_then(|square| game.piece_at(square)) == Some(king)
+    })
+}
+
+/// The rook a bare `K` or `Q` stands for: the corner rook when the king is
+/// on the e-file, as in standard chess, otherwise the outermost rook on that
+/// side (X-FEN).
+fn default_rook_file(game: &GameState, color: Color, kingside: bool) -> u8 {
+    let corner = if kingside { 7 } else { 0 };
+    let king_file = match king_file(game, color) {
+        Some(4) | None => return corner,
+        Some(file) => file,
+    };
+
+    let rook = Piece {
+        piece_type: PieceType::Rook,
+        color,
+    };
+    let mut files = if kingside {
+        (king_file + 1..8).rev().collect::<Vec<_>>()
+    } else {
+        (0..king_file).collect()
+    };
+    files.retain(|file| {
+        Square::new(*file, home_rank(color)).and_then(|square| game.piece_at(square)) == Some(rook)
+    });
+    files.first().copied().unwrap_or(corner)
+}
+
+fn piece_from_char(symbol: char) -> Option<Piece> {
+    let color = if symbol.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let piece_type = match symbol.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+    };
+    Some(Piece { piece_type, color })
+}
+
+fn piece_to_char(piece: Piece) -> char {
+    let symbol = match piece.piece_type {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+    };
+    match piece.color {
+        Color::White => symbol.to_ascii_uppercase(),
+        Color::Black => symbol,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_round_trip(fen: &str) {
+        let state = GameState::from_fen(fen).expect("valid FEN");
+        assert_eq!(state.to_fen(), fen);
+    }
+
+    #[test]
+    fn round_trips_standard_positions() {
+        assert_round_trip(START_POSITION_FEN);
+        assert_round_trip("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_round_trip("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+        assert_round_trip("8/8/8/8/8/8/8/K1k5 b - - 42 87");
+    }
+
+    #[test]
+    fn omitted_counters_default_to_zero_and_one() {
+        let state = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 w -  -").expect("valid EPD");
+        assert_eq!(state.halfmove_clock(), 0);
+        assert_eq!(state.fullmove_number(), 1);
+    }
+
+    #[test]
+    fn reads_shredder_and_x_fen_castling_fields() {
+        // Shredder files for the standard corners mean plain KQkq.
+        let shredder = GameState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1").expect("valid");
+        assert!(!shredder.is_chess960());
+        assert_eq!(shredder.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        // Chess960: the outermost rooks are written K and Q...
+        assert_round_trip("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+        let shredder = GameState::from_fen(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        )
+        .expect("valid FEN");
+        assert!(shredder.is_chess960());
+        assert_eq!(
+            shredder.to_fen(),
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+        );
+
+        // ...and an inner rook by its file.
+        assert_round_trip("4k3/8/8/8/8/8/8/1R2K1RR w Gb - 0 1");
+    }
+
+    #[test]
+    fn rejects_malformed_fields() {
+        let cases = [
+            ("8/8/8/8/8/8/8/8 w", FenError::WrongFieldCount(2)),
+            ("8/8/8/8/8/8/8 w - - 0 1", FenError::WrongRankCount(7)),
+            (
+                "ppppp4/8/8/8/8/8/8/8 w - - 0 1",
+                FenError::InvalidRankLength {
+                    rank: 8,
+                    squares: 9,
+                },
+            ),
+            ("4x3/8/8/8/8/8/8/8 w - - 0 1", FenError::InvalidPiece('x')),
+            (
+                "8/8/8/8/8/8/8/8 x - - 0 1",
+                FenError::InvalidSideToMove("x".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w KZ - 0 1",
+                FenError::InvalidCastlingRights("KZ".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - e4 0 1",
+                FenError::InvalidEnPassantSquare("e4".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - -1 1",
+                FenError::InvalidHalfmoveClock("-1".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+                FenError::InvalidFullmoveNumber("0".to_owned()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1 +4+0",
+                FenError::InvalidCheckCount("+4+0".to_owned()),
+            ),
+        ];
+        for (fen, expected) in cases {
+            assert_eq!(GameState::from_fen(fen).err(), Some(expected), "{fen}");
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use crate::game_logic::chess::bitboard::{Bitboard, square_bit};
+use crate::game_logic::chess::crazyhouse::Pockets;
+use crate::game_logic::chess::state::{CastlingMove, CastlingRights};
+use crate::game_logic::chess::{
+    Color, Explosion, GameEvent, GameState, Move, Piece, PieceType, Square,
+};
+
+/// Everything needed to take a move back: the piece that moved (before any
+/// promotion), what it captured or blew up and where, and the state the
+/// move cleared.
+#[derive(Debug, Copy, Clone)]
+pub struct MoveRecord {
+    pub mv: Move,
+    pub piece: Piece,
+    pub captured: Option<(Square, Piece)>,
+    pub(super) castling: Option<CastlingMove>,
+    /// Set for an Atomic capture, which also destroyed the moving piece.
+    pub explosion: Option<Explosion>,
+    pub(super) previous_castling_rights: CastlingRights,
+    pub(super) previous_checks_given: [u8; 2],
+    pub(super) previous_pockets: Pockets,
+    pub(super) previous_promoted: Bitboard,
+    pub previous_en_passant_target: Option<Square>,
+    pub previous_halfmove_clock: u32,
+}
+
+impl MoveRecord {
+    /// The square the moved piece ended on, which is the king's castling
+    /// square rather than `mv.to` for Chess960 castling.
+    pub fn destination(&self) -> Square {
+        self.castling
+            .map_or(self.mv.to, |castling| castling.king_to)
+    }
+
+    /// Pieces the move blew up around the capture square.
+    pub fn exploded_pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.explosion.iter().flat_map(Explosion::pieces)
+    }
+
+    /// The piece a Crazyhouse capture puts in the mover's hand: the
+    /// captured piece in the mover's color, or a pawn if it had promoted.
+    pub fn pocketed_piece(&self) -> Option<Piece> {
+        let (square, captured) = self.captured?;
+        let piece_type = if self.previous_promoted & square_bit(square) != 0 {
+            PieceType::Pawn
+        } else {
+            captured.piece_type
+        };
+        Some(Piece {
+            piece_type,
+            color: self.piece.color,
+        })
+    }
+
+    pub fn castling_rook_squares(&self) -> Option<(Square, Square)> {
+        self.castling
+            .map(|castling| (castling.rook_from, castling.rook_to))
+    }
+}
+
+impl GameState {
+    pub fn move_history(&self) -> &[MoveRecord] {
+        &self.move_history
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.move_history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_moves.is_empty()
+    }
+
+    pub fn next_redo_move(&self) -> Option<Move> {
+        self.redo_moves.last().copied()
+    }
+
+    /// Takes back the last move, restoring the captured piece, castling
+    /// rights, pockets, en passant square and move counters. Unlike
+    /// `redo_move` it reports no `GameEvent`s: none of them describes a
+    /// move being taken back.
+    pub fn undo_move(&mut self) -> Option<MoveRecord> {
+        let record = self.move_history.pop()?;
+        let mv = record.mv;
+
+        let rook = record.castling.and_then(|castling| {
+            let rook = self.piece_at(castling.rook_to);
+            self.set_piece(castling.rook_to, None);
+            rook
+        });
+        self.set_piece(record.destination(), None);
+        if mv.drop.is_none() {
+            self.set_piece(mv.from, Some(record.piece));
+        }
+        if let Some((rook_from, _)) = record.castling_rook_squares() {
+            self.set_piece(rook_from, rook);
+        }
+
+        if let Some((square, piece)) = record.captured {
+            self.set_piece(square, Some(piece));
+        }
+        for (square, piece) in record.exploded_pieces() {
+            self.set_piece(square, Some(piece));
+        }
+
+        self.castling_rights = record.previous_castling_rights;
+        self.checks_given = record.previous_checks_given;
+        self.pockets = record.previous_pockets;
+        self.promoted = record.previous_promoted;
+        self.en_passant_target = record.previous_en_passant_target;
+        self.halfmove_clock = record.previous_halfmove_clock;
+        self.side_to_move = record.piece.color;
+        if self.side_to_move == Color::Black {
+            self.fullmove_number = self.fullmove_number.saturating_sub(1).max(1);
+        }
+        self.position_history.pop();
+        if let Some(key) = self.position_history.last() {
+            self.zobrist_key = *key;
+        }
+        debug_assert_eq!(self.zobrist_key, self.compute_zobrist_key());
+        self.redo_moves.push(mv);
+
+        Some(record)
+    }
+
+    /// Replays the most recently undone move through `play_move` and
+    /// returns its events.
+    pub fn redo_move(&mut self) -> Option<Vec<GameEvent>> {
+        let mv = self.redo_moves.pop()?;
+        let redo_moves = std::mem::take(&mut self.redo_moves);
+        let result = self.play_move(mv);
+        self.redo_moves = redo_moves;
+
+        match result {
+            Ok(events) => Some(events),
+            Err(_) => {
+                self.redo_moves.clear();
+                None
+            }
+        }
+    }
+
+    pub(super) fn captured_by(&self, mv: Move, piece: Piece) -> Option<(Square, Piece)> {
+        if let Some(target) = self.piece_at(mv.to) {
+            return (target.color != piece.color).then_some((mv.to, target));
+        }
+
+        let is_en_passant_capture = piece.piece_type == PieceType::Pawn
+            && mv.from.file() != mv.to.file()
+            && self.en_passant_target == Some(mv.to);
+        if !is_en_passant_capture {
+            return None;
+        }
+
+        let captured_square = Square::new(mv.to.file(), mv.from.rank())?;
+        self.piece_at(captured_square)
+            .map(|captured| (captured_square, captured))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_logic::chess::GameState;
+
+    /// Takes in en passant, castling that clears both White rights, and a
+    /// capturing promotion that clears Black's queenside right.
+    const START: &str = "r3k2r/1P6/8/8/3p4/8/4P3/R3K2R w KQkq - 0 1";
+    const MOVES: [&str; 6] = ["e4", "dxe3", "O-O", "e2", "bxa8=Q+", "Ke7"];
+
+    fn play(state: &mut GameState, moves: &[&str]) {
+        for text in moves {
+            let mv = state.parse_san(text).expect("legal move");
+            state.apply_move(mv).expect("legal move");
+        }
+    }
+
+    #[test]
+    fn undo_restores_every_position_and_redo_replays_it() {
+        let mut state = GameState::from_fen(START).expect("valid FEN");
+        let mut positions = vec![(state.to_fen(), state.zobrist_key())];
+        for text in MOVES {
+            play(&mut state, &[text]);
+            positions.push((state.to_fen(), state.zobrist_key()));
+        }
+        let history = state.move_history().to_vec();
+        assert!(positions[3].0.contains(" b kq "), "{}", positions[3].0);
+        assert!(positions[5].0.contains(" b k "), "{}", positions[5].0);
+
+        for expected in positions.iter().rev().skip(1) {
+            assert!(state.undo_move().is_some());
+            assert_eq!(&(state.to_fen(), state.zobrist_key()), expected);
+        }
+        assert!(state.undo_move().is_none());
+        assert_eq!(state.to_fen(), START);
+
+        for expected in positions.iter().skip(1) {
+            assert!(state.redo_move().is_some());
+            assert_eq!(&(state.to_fen(), state.zobrist_key()), expected);
+        }
+        assert!(!state.can_redo());
+        assert_eq!(
+            state
+                .move_history()
+                .iter()
+                .map(|record| record.mv)
+                .collect::<Vec<_>>(),
+            history.iter().map(|record| record.mv).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn new_move_clears_the_redo_moves() {
+        let mut state = GameState::from_fen(START).expect("valid FEN");
+        play(&mut state, &MOVES);
+        state.undo_move();
+        state.undo_move();
+        assert!(state.can_redo());
+
+        play(&mut state, &["Kg2"]);
+        assert!(!state.can_redo());
+        assert!(state.redo_move().is_none());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::game_logic::chess::{Color, GameOutcome, MoveError};
+use crate::game_logic::chess::{
+    BookError, Color, FenError, GameEvent, GameOutcome, MoveError, NotationError, PgnError,
+    PuzzleError, SetupError, UciError,
+};
 
 pub fn move_error_message(err: MoveError) -> String {
     match err {
@@ -7,6 +10,15 @@ pub fn move_error_message(err: MoveError) -> String {
         MoveError::DestinationOccupiedByOwnPiece => "Destination occupied by own piece".to_owned(),
         MoveError::IllegalPieceMovement => "Illegal move for selected piece".to_owned(),
         MoveError::KingWouldBeInCheck => "King in check".to_owned(),
+        MoveError::PromotionRequired => "Choose a piece to promote to".to_owned(),
+        MoveError::InvalidPromotion => "Invalid promotion piece".to_owned(),
+        MoveError::KingCannotCapture => "Kings cannot capture in Atomic chess".to_owned(),
+        MoveError::ExplodesOwnKing => "Capture would explode your own king".to_owned(),
+        MoveError::NotInPocket => "No such piece in your pocket".to_owned(),
+        MoveError::DropOnOccupiedSquare => "Pieces can only be dropped on empty squares".to_owned(),
+        MoveError::PawnDropOnBackRank => {
+            "Pawns cannot be dropped on the first or last rank".to_owned()
+        }
     }
 }
 
@@ -19,5 +31,208 @@ pub fn game_outcome_message(outcome: GameOutcome) -> String {
             winner: Color::Black,
         } => "Checkmate: Black wins".to_owned(),
         GameOutcome::Stalemate => "Stalemate: Draw".to_owned(),
+        GameOutcome::FiftyMoveRule => "Fifty-move rule: Draw".to_owned(),
+        GameOutcome::ThreefoldRepetition => "Threefold repetition: Draw".to_owned(),
+        GameOutcome::FivefoldRepetition => "Fivefold repetition: Draw".to_owned(),
+        GameOutcome::SeventyFiveMoveRule => "75-move rule: Draw".to_owned(),
+        GameOutcome::InsufficientMaterial => "Insufficient material: Draw".to_owned(),
+        GameOutcome::KingOfTheHill {
+            winner: Color::White,
+        } => "King of the Hill: White wins".to_owned(),
+        GameOutcome::KingOfTheHill {
+            winner: Color::Black,
+        } => "King of the Hill: Black wins".to_owned(),
+        GameOutcome::ThreeCheck {
+            winner: Color::White,
+        } => "Third check: White wins".to_owned(),
+        GameOutcome::ThreeCheck {
+            winner: Color::Black,
+        } => "Third check: Black wins".to_owned(),
+        GameOutcome::KingExploded {
+            winner: Color::White,
+        } => "King exploded: White wins".to_owned(),
+        GameOutcome::KingExploded {
+            winner: Color::Black,
+        } => "King exploded: Black wins".to_owned(),
+        GameOutcome::Timeout {
+            winner: Color::White,
+        } => "Black ran out of time: White wins".to_owned(),
+        GameOutcome::Timeout {
+            winner: Color::Black,
+        } => "White ran out of time: Black wins".to_owned(),
+        GameOutcome::TimeoutVsInsufficientMaterial => {
+            "Time out with no mating material left: Draw".to_owned()
+        }
+        GameOutcome::Resignation {
+            winner: Color::White,
+        } => "Black resigns: White wins".to_owned(),
+        GameOutcome::Resignation {
+            winner: Color::Black,
+        } => "White resigns: Black wins".to_owned(),
+        GameOutcome::Forfeit {
+            winner: Color::White,
+        } => "Black forfeits: White wins".to_owned(),
+        GameOutcome::Forfeit {
+            winner: Color::Black,
+        } => "White forfeits: Black wins".to_owned(),
+        GameOutcome::DrawByAgreement => "Draw agreed".to_owned(),
+    }
+}
+
+pub fn fen_error_message(err: &FenError) -> String {
+    match err {
+        FenError::WrongFieldCount(count) => {
+            format!("FEN needs 4 to 6 space-separated fields, found {count}")
+        }
+        FenError::WrongRankCount(count) => {
+            format!("FEN piece placement needs 8 ranks separated by '/', found {count}")
+        }
+        FenError::InvalidRankLength { rank, squares } => {
+            format!("FEN rank {rank} describes {squares} squares instead of 8")
+        }
+        FenError::InvalidPiece(symbol) => format!("Unknown piece symbol '{symbol}' in FEN"),
+        FenError::InvalidSideToMove(field) => {
+            format!("Side to move must be 'w' or 'b', found '{field}'")
+        }
+        FenError::InvalidCastlingRights(field) => {
+            format!(
+                "Castling rights must be '-', a subset of 'KQkq' or rook files like 'HAha', found '{field}'"
+            )
+        }
+        FenError::InvalidEnPassantSquare(field) => {
+            format!("Invalid en passant square '{field}'")
+        }
+        FenError::InvalidHalfmoveClock(field) => {
+            format!("Halfmove clock must be a non-negative number, found '{field}'")
+        }
+        FenError::InvalidFullmoveNumber(field) => {
+            format!("Fullmove number must be a positive number, found '{field}'")
+        }
+        FenError::InvalidCheckCount(field) => {
+            format!("Three-check counts must look like '+1+0', found '{field}'")
+        }
+    }
+}
+
+pub fn setup_error_message(err: &SetupError) -> String {
+    let color_name = |color: &Color| match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    };
+    match err {
+        SetupError::MissingKing(color) => format!("{} has no king", color_name(color)),
+        SetupError::ExtraKings(color) => format!("{} has more than one king", color_name(color)),
+        SetupError::PawnOnBackRank(square) => {
+            format!("Pawn on {square}: pawns cannot stand on the first or last rank")
+        }
+        SetupError::SideNotToMoveInCheck(color) => {
+            format!("{} is in check but not to move", color_name(color))
+        }
+        SetupError::CastlingMismatch { color, kingside } => format!(
+            "{} cannot castle {}: king and rook are not on their home rank",
+            color_name(color),
+            if *kingside { "kingside" } else { "queenside" }
+        ),
+        SetupError::InvalidEnPassant(square) => {
+            format!("No pawn can just have passed {square} for en passant")
+        }
+        SetupError::Fen(err) => fen_error_message(err),
+    }
+}
+
+pub fn notation_error_message(err: &NotationError) -> String {
+    match err {
+        NotationError::Empty => "Enter a move such as e4, Nf3 or e2e4".to_owned(),
+        NotationError::InvalidSquare(text) => format!("'{text}' is not a square name"),
+        NotationError::Malformed(text) => format!("Cannot read move '{text}'"),
+        NotationError::IllegalMove(text) => format!("'{text}' is not a legal move here"),
+        NotationError::AmbiguousMove(text) => {
+            format!("'{text}' matches more than one move; add the source file or rank")
+        }
+    }
+}
+
+pub fn pgn_error_message(err: &PgnError) -> String {
+    match err {
+        PgnError::NoGame => "PGN contains no game".to_owned(),
+        PgnError::MalformedTag(line) => format!("Malformed PGN tag: {line}"),
+        PgnError::InvalidFen(err) => format!("Invalid FEN tag: {}", fen_error_message(err)),
+        PgnError::InvalidResult(token) => format!("Unknown game result '{token}'"),
+        PgnError::UnterminatedComment => "PGN comment is missing its closing '}'".to_owned(),
+        PgnError::IllegalMove { ply, text, err } => format!(
+            "Move {} ({text}): {}",
+            ply / 2 + 1,
+            notation_error_message(err)
+        ),
+        PgnError::UnplayableMove { ply, err } => {
+            format!("Move {}: {}", ply / 2 + 1, move_error_message(*err))
+        }
+    }
+}
+
+pub fn uci_error_message(err: &UciError) -> String {
+    match err {
+        UciError::Spawn(reason) => format!("Could not start engine ({reason})"),
+        UciError::Io(reason) => format!("Engine communication failed ({reason})"),
+        UciError::EngineExited => "Engine exited unexpectedly".to_owned(),
+        UciError::Timeout => "Engine stopped responding".to_owned(),
+        UciError::IllegalBestMove(text) => format!("Engine played an illegal move '{text}'"),
+        UciError::UnsupportedVariant(variant) => format!("Engine does not play {variant}"),
+    }
+}
+
+pub fn book_error_message(err: &BookError) -> String {
+    match err {
+        BookError::Io(reason) => format!("Could not read opening book ({reason})"),
+        BookError::TruncatedEntry(index) => {
+            format!("Opening book is cut off in entry {}", index + 1)
+        }
+    }
+}
+
+pub fn puzzle_error_message(err: &PuzzleError) -> String {
+    match err {
+        PuzzleError::Io(reason) => format!("Could not read puzzles ({reason})"),
+        PuzzleError::NoPuzzles => "Puzzle file contains no puzzles".to_owned(),
+        PuzzleError::InvalidFen { line, err } => {
+            format!("Puzzle on line {line}: {}", fen_error_message(err))
+        }
+        PuzzleError::MissingSolution(line) => format!("Puzzle on line {line} has no solution"),
+        PuzzleError::InvalidMove { line, text, err } => format!(
+            "Puzzle on line {line}: bad move '{text}' ({})",
+            notation_error_message(err)
+        ),
+        PuzzleError::MissingColumn(line) => format!("Puzzle on line {line} is missing a column"),
+    }
+}
+
+pub fn game_event_message(event: &GameEvent) -> String {
+    match event {
+        GameEvent::MoveMade { mv, piece, san } => {
+            format!("{:?} plays {san} ({mv})", piece.color)
+        }
+        GameEvent::Captured { square, piece } => {
+            format!(
+                "{:?} {:?} captured on {square}",
+                piece.color, piece.piece_type
+            )
+        }
+        GameEvent::Castled {
+            color,
+            kingside: true,
+        } => format!("{color:?} castles kingside"),
+        GameEvent::Castled {
+            color,
+            kingside: false,
+        } => format!("{color:?} castles queenside"),
+        GameEvent::EnPassant { captured } => format!("En passant, pawn on {captured} taken"),
+        GameEvent::Promoted { square, piece } => {
+            format!("Pawn promotes to {:?} on {square}", piece.piece_type)
+        }
+        GameEvent::Check { color } => format!("{color:?} is in check"),
+        GameEvent::DrawClaimable(outcome) => {
+            format!("Draw can be claimed ({})", game_outcome_message(*outcome))
+        }
+        GameEvent::GameOver(outcome) => game_outcome_message(*outcome),
     }
 }
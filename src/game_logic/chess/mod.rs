@@ -1,11 +1,56 @@
+mod atomic;
+mod bitboard;
+mod book;
+mod chess960;
+mod clock;
 mod coords;
+mod crazyhouse;
+mod draw_rules;
+mod engine;
+mod events;
+mod fen;
+mod history;
 mod messages;
+mod move_list;
+mod notation;
+mod perft;
+mod pgn;
+mod puzzle;
 mod scene;
+mod setup;
 mod state;
+mod threats;
 mod types;
+mod uci;
+mod variant;
+mod zobrist;
 
+pub use atomic::Explosion;
+pub use book::{BookError, OpeningBook};
+pub use chess960::Chess960Generator;
+pub use clock::{ChessClock, TimeControl};
 pub use coords::{square_to_world, world_to_square};
-pub use messages::{game_outcome_message, move_error_message};
-pub use scene::{ChessSceneState, ModelMoveUpdate, parse_piece_template_name};
+pub use engine::{EngineSearch, SearchLimits, SearchResult};
+pub use events::{EventLog, GameEvent, GameEventSubscriber};
+pub use fen::{FenError, START_POSITION_FEN};
+pub use history::MoveRecord;
+pub use messages::{
+    book_error_message, fen_error_message, game_event_message, game_outcome_message,
+    move_error_message, notation_error_message, pgn_error_message, puzzle_error_message,
+    setup_error_message, uci_error_message,
+};
+pub use move_list::LegalMove;
+pub use notation::NotationError;
+pub use pgn::{GameResult, PgnError, PgnGame, parse_pgn};
+pub use puzzle::{PuzzleAttempt, PuzzleError, PuzzleSession, load_puzzles};
+pub use scene::{
+    ChessSceneState, ModelMoveUpdate, MoveHighlight, PromotedModelUpdate, PromotionChoiceModel,
+    RestoredModelUpdate, ThreatMark, parse_piece_template_name,
+};
+pub use setup::{BoardSetup, SetupError};
 pub use state::GameState;
 pub use types::{Color, GameOutcome, Move, MoveError, Piece, PieceType, Square};
+pub use uci::{
+    MatchConfig, MatchScore, UciEngine, UciEngineConfig, UciError, play_match, run_uci_engine,
+};
+pub use variant::Variant;
@@ -0,0 +1,152 @@
+use crate::game_logic::chess::{GameState, Move, Piece, PieceType, Square};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CastleSide {
+    Kingside,
+    Queenside,
+}
+
+/// A legal move together with what it does on the board.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct LegalMove {
+    pub mv: Move,
+    pub piece: Piece,
+    /// The captured piece and its square, which is not `mv.to` for en
+    /// passant.
+    pub captured: Option<(Square, Piece)>,
+    pub is_en_passant: bool,
+    pub castle: Option<CastleSide>,
+    pub is_double_push: bool,
+    pub gives_check: bool,
+    /// The check is mate.
+    pub gives_mate: bool,
+}
+
+impl LegalMove {
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        self.mv.promotion.is_some()
+    }
+}
+
+impl GameState {
+    /// Every legal move for the side to move with its flags, one entry per
+    /// promotion choice.
+    pub fn legal_moves(&self) -> Vec<LegalMove> {
+        let mut next = self.clone();
+        self.generate_legal_moves()
+            .into_iter()
+            .filter_map(|mv| {
+                let piece = self.moving_piece(mv)?;
+                let captured = self.captured_by(mv, piece);
+                let rank_distance = mv.to.rank().abs_diff(mv.from.rank());
+                let castle = self.castling_move(mv, piece).map(|castling| {
+                    if castling.kingside {
+                        CastleSide::Kingside
+                    } else {
+                        CastleSide::Queenside
+                    }
+                });
+
+                next.apply_move(mv).ok()?;
+                let gives_check = next.is_in_check(next.side_to_move());
+                let gives_mate = gives_check && next.is_checkmate(next.side_to_move());
+                next.undo_move();
+
+                Some(LegalMove {
+                    mv,
+                    piece,
+                    captured,
+                    is_en_passant: captured.is_some_and(|(square, _)| square != mv.to),
+                    castle,
+                    is_double_push: piece.piece_type == PieceType::Pawn && rank_distance == 2,
+                    gives_check,
+                    gives_mate,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_logic::chess::Color;
+
+    fn legal_move(state: &GameState, uci: &str) -> LegalMove {
+        let mv = state.parse_uci(uci).expect("legal move");
+        state
+            .legal_moves()
+            .into_iter()
+            .find(|legal_move| legal_move.mv == mv)
+            .expect("listed move")
+    }
+
+    fn piece(piece_type: PieceType, color: Color) -> Piece {
+        Piece { piece_type, color }
+    }
+
+    #[test]
+    fn flags_each_kind_of_move() {
+        // White can castle either way, take en passant on d6 and take the
+        // a8 rook while promoting; h6 leaves the Black king a flight square.
+        let state =
+            GameState::from_fen("r5k1/1P3pp1/7p/3pP3/8/8/8/R3K2R w KQ d6 0 1").expect("valid FEN");
+
+        let quiet = legal_move(&state, "a1a2");
+        assert!(!quiet.is_capture() && !quiet.gives_check && quiet.castle.is_none());
+        assert!(!quiet.is_en_passant && !quiet.is_promotion() && !quiet.is_double_push);
+
+        let en_passant = legal_move(&state, "e5d6");
+        assert!(en_passant.is_en_passant);
+        assert_eq!(
+            en_passant.captured,
+            Some((
+                "d5".parse().expect("square"),
+                piece(PieceType::Pawn, Color::Black)
+            ))
+        );
+
+        assert_eq!(
+            legal_move(&state, "e1c1").castle,
+            Some(CastleSide::Queenside)
+        );
+        assert_eq!(
+            legal_move(&state, "e1g1").castle,
+            Some(CastleSide::Kingside)
+        );
+
+        let promotion = legal_move(&state, "b7a8q");
+        assert!(promotion.is_promotion() && promotion.is_capture());
+        assert_eq!(
+            promotion.captured,
+            Some((
+                "a8".parse().expect("square"),
+                piece(PieceType::Rook, Color::Black)
+            ))
+        );
+        assert!(promotion.gives_check && !promotion.gives_mate);
+
+        let underpromotion = legal_move(&state, "b7a8n");
+        assert!(underpromotion.is_promotion() && !underpromotion.gives_check);
+    }
+
+    #[test]
+    fn flags_mate() {
+        let state = GameState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").expect("valid FEN");
+        let mate = legal_move(&state, "a1a8");
+        assert!(mate.gives_check && mate.gives_mate);
+        assert!(!legal_move(&state, "a1a7").gives_check);
+    }
+
+    #[test]
+    fn flags_a_double_push() {
+        let state = GameState::new_start_position();
+        assert!(legal_move(&state, "e2e4").is_double_push);
+        assert!(!legal_move(&state, "e2e3").is_double_push);
+        assert_eq!(state.legal_moves().len(), 20);
+    }
+}
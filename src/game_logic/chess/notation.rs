@@ -0,0 +1,445 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game_logic::chess::{GameState, Move, MoveError, Piece, PieceType, Square};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NotationError {
+    Empty,
+    InvalidSquare(String),
+    Malformed(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, self.rank() + 1)
+    }
+}
+
+impl FromStr for Square {
+    type Err = NotationError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let bytes = name.as_bytes();
+        if bytes.len() != 2
+            || !(b'a'..=b'h').contains(&bytes[0])
+            || !(b'1'..=b'8').contains(&bytes[1])
+        {
+            return Err(NotationError::InvalidSquare(name.to_owned()));
+        }
+        Square::new(bytes[0] - b'a', bytes[1] - b'1')
+            .ok_or_else(|| NotationError::InvalidSquare(name.to_owned()))
+    }
+}
+
+/// Long algebraic (UCI) form: `e2e4`, `e7e8q`, or `N@f3` for a drop.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(piece_type) = self.drop {
+            return write!(f, "{}@{}", piece_letter(piece_type), self.to);
+        }
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", piece_letter(promotion).to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Move {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(NotationError::Empty);
+        }
+        if let Some((letter, to)) = text.split_once('@') {
+            return parse_drop(text, letter, to);
+        }
+        if !text.is_ascii() || !(4..=5).contains(&text.len()) {
+            return Err(NotationError::Malformed(text.to_owned()));
+        }
+
+        let from = text[0..2].parse::<Square>()?;
+        let to = text[2..4].parse::<Square>()?;
+        let promotion = match text[4..].chars().next() {
+            None => None,
+            Some(symbol) => Some(
+                piece_type_from_letter(symbol.to_ascii_uppercase())
+                    .filter(|piece_type| piece_type.is_promotion_choice())
+                    .ok_or_else(|| NotationError::Malformed(text.to_owned()))?,
+            ),
+        };
+
+        Ok(Move {
+            from,
+            to,
+            promotion,
+            drop: None,
+        })
+    }
+}
+
+/// `N@f3`; a pawn drop may leave out its letter, as in `@e4`.
+fn parse_drop(text: &str, letter: &str, to: &str) -> Result<Move, NotationError> {
+    let malformed = || NotationError::Malformed(text.to_owned());
+    let piece_type = match letter.chars().collect::<Vec<_>>().as_slice() {
+        [] => PieceType::Pawn,
+        [symbol] => piece_type_from_letter(symbol.to_ascii_uppercase())
+            .filter(|piece_type| *piece_type != PieceType::King)
+            .ok_or_else(malformed)?,
+        _ => return Err(malformed()),
+    };
+    Ok(Move::drop(piece_type, to.parse::<Square>()?))
+}
+
+impl GameState {
+    /// Formats a legal move in Standard Algebraic Notation, including
+    /// disambiguation and the check (`+`) or mate (`#`) suffix. En passant
+    /// captures are written plainly (`exd6`, not `exd6 e.p.`): this SAN is
+    /// also PGN movetext, where the export format leaves the suffix out and
+    /// its space would split the move into two tokens. `parse_san` still
+    /// accepts the suffix.
+    pub fn move_to_san(&self, mv: Move) -> Result<String, MoveError> {
+        let piece = self.moving_piece(mv).ok_or(MoveError::NoPieceAtSource)?;
+        let mut next = self.clone();
+        next.apply_move(mv)?;
+        Ok(self.san_body(mv, piece) + next.check_suffix())
+    }
+
+    /// SAN for `mv` by `piece` in this position, before the move is made,
+    /// without the check suffix.
+    pub(super) fn san_body(&self, mv: Move, piece: Piece) -> String {
+        if mv.drop.is_some() {
+            return mv.to_string();
+        }
+        if let Some(castling) = self.castling_move(mv, piece) {
+            return if castling.kingside { "O-O" } else { "O-O-O" }.to_owned();
+        }
+
+        let is_capture = self.piece_at(mv.to).is_some()
+            || (piece.piece_type == PieceType::Pawn && mv.from.file() != mv.to.file());
+        let mut san = String::new();
+
+        if piece.piece_type == PieceType::Pawn {
+            if is_capture {
+                san.push((b'a' + mv.from.file()) as char);
+            }
+        } else {
+            san.push(piece_letter(piece.piece_type));
+            san.push_str(&self.disambiguation(mv, piece));
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+
+        if let Some(promotion) = mv.promotion {
+            san.push('=');
+            san.push(piece_letter(promotion));
+        }
+        san
+    }
+
+    /// `#` or `+` when the side to move has just been mated or checked.
+    pub(super) fn check_suffix(&self) -> &'static str {
+        let side_to_move = self.side_to_move();
+        if self.is_checkmate(side_to_move) {
+            "#"
+        } else if self.is_in_check(side_to_move) {
+            "+"
+        } else {
+            ""
+        }
+    }
+
+    /// Parses a SAN move such as `Nbd7`, `exd6 e.p.`, `O-O-O`, `e8=Q+` or
+    /// the drop `N@f3` against the current position. Check and annotation suffixes are
+    /// accepted but not required.
+    pub fn parse_san(&self, text: &str) -> Result<Move, NotationError> {
+        let trimmed = text.trim();
+        let mut core = trimmed.trim_end_matches(['+', '#', '!', '?']).trim_end();
+        for suffix in ["e.p.", "ep"] {
+            if let Some(stripped) = core.strip_suffix(suffix) {
+                core = stripped.trim_end();
+            }
+        }
+        if core.is_empty() {
+            return Err(NotationError::Empty);
+        }
+
+        let castling_side = match core.replace('0', "O").as_str() {
+            "O-O" => Some(true),
+            "O-O-O" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = castling_side {
+            return self.unique_legal_move(trimmed, |mv, piece| {
+                self.castling_move(mv, piece)
+                    .is_some_and(|castling| castling.kingside == kingside)
+            });
+        }
+
+        let malformed = || NotationError::Malformed(trimmed.to_owned());
+        if !core.is_ascii() {
+            return Err(malformed());
+        }
+
+        if let Some((letter, to)) = core.split_once('@') {
+            let drop = parse_drop(trimmed, letter, to)?;
+            return self.unique_legal_move(trimmed, |mv, _| mv == drop);
+        }
+
+        let (body, promotion) = match core.char_indices().rev().find(|(_, c)| c.is_ascii_digit()) {
+            Some((index, _)) => {
+                let suffix = core[index + 1..].trim_start_matches('=');
+                let promotion = match suffix.chars().next() {
+                    None => None,
+                    Some(symbol) if suffix.len() == 1 => Some(
+                        piece_type_from_letter(symbol.to_ascii_uppercase())
+                            .filter(|piece_type| piece_type.is_promotion_choice())
+                            .ok_or_else(malformed)?,
+                    ),
+                    Some(_) => return Err(malformed()),
+                };
+                (&core[..=index], promotion)
+            }
+            None => return Err(malformed()),
+        };
+
+        if body.len() < 2 {
+            return Err(malformed());
+        }
+        let to = body[body.len() - 2..].parse::<Square>()?;
+        let mut rest = &body[..body.len() - 2];
+
+        let piece_type = match rest.chars().next() {
+            Some(symbol) if symbol.is_ascii_uppercase() => {
+                rest = &rest[1..];
+                piece_type_from_letter(symbol).ok_or_else(malformed)?
+            }
+            _ => PieceType::Pawn,
+        };
+
+        let rest = rest.strip_suffix(['x', ':']).unwrap_or(rest);
+        let mut from_file = None;
+        let mut from_rank = None;
+        for symbol in rest.chars() {
+            match symbol {
+                'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                    from_file = Some(symbol as u8 - b'a');
+                }
+                '1'..='8' if from_rank.is_none() => from_rank = Some(symbol as u8 - b'1'),
+                _ => return Err(malformed()),
+            }
+        }
+
+        self.unique_legal_move(trimmed, |mv, piece| {
+            mv.drop.is_none()
+                && piece.piece_type == piece_type
+                && mv.to == to
+                && mv.promotion == promotion
+                && from_file.is_none_or(|file| mv.from.file() == file)
+                && from_rank.is_none_or(|rank| mv.from.rank() == rank)
+        })
+    }
+
+    /// Parses a long algebraic (UCI) move such as `e2e4`, `e7e8q` or `N@f3` and
+    /// checks that it is legal in the current position.
+    pub fn parse_uci(&self, text: &str) -> Result<Move, NotationError> {
+        let mv = text.parse::<Move>()?;
+        let mut next = self.clone();
+        next.apply_move(mv)
+            .map_err(|_| NotationError::IllegalMove(text.trim().to_owned()))?;
+        Ok(mv)
+    }
+
+    /// Accepts either UCI or SAN input.
+    pub fn parse_move(&self, text: &str) -> Result<Move, NotationError> {
+        match text.parse::<Move>() {
+            Ok(_) => self.parse_uci(text),
+            Err(_) => self.parse_san(text),
+        }
+    }
+
+    fn disambiguation(&self, mv: Move, piece: Piece) -> String {
+        let rivals = self
+            .iter_pieces()
+            .filter(|(square, other)| *other == piece && *square != mv.from)
+            .filter(|(square, _)| self.legal_moves_from(*square).contains(&mv.to))
+            .map(|(square, _)| square)
+            .collect::<Vec<_>>();
+
+        if rivals.is_empty() {
+            return String::new();
+        }
+
+        let file = ((b'a' + mv.from.file()) as char).to_string();
+        let rank = (mv.from.rank() + 1).to_string();
+        if rivals.iter().all(|square| square.file() != mv.from.file()) {
+            file
+        } else if rivals.iter().all(|square| square.rank() != mv.from.rank()) {
+            rank
+        } else {
+            format!("{file}{rank}")
+        }
+    }
+
+    fn unique_legal_move(
+        &self,
+        text: &str,
+        matches: impl Fn(Move, Piece) -> bool,
+    ) -> Result<Move, NotationError> {
+        let mut found = None;
+        for mv in self.generate_legal_moves() {
+            let Some(piece) = self.moving_piece(mv) else {
+                continue;
+            };
+            if !matches(mv, piece) {
+                continue;
+            }
+            if found.is_some() {
+                return Err(NotationError::AmbiguousMove(text.to_owned()));
+            }
+            found = Some(mv);
+        }
+
+        found.ok_or_else(|| NotationError::IllegalMove(text.to_owned()))
+    }
+}
+
+pub(super) fn piece_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::Pawn => 'P',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+    }
+}
+
+pub(super) fn piece_type_from_letter(letter: char) -> Option<PieceType> {
+    match letter {
+        'P' => Some(PieceType::Pawn),
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(fen: &str) -> GameState {
+        GameState::from_fen(fen).expect("valid FEN")
+    }
+
+    fn san(fen: &str, uci: &str) -> String {
+        let state = state(fen);
+        let mv = state.parse_uci(uci).expect("legal move");
+        state.move_to_san(mv).expect("legal move")
+    }
+
+    #[test]
+    fn disambiguates_by_file_rank_or_square() {
+        // Knights on b1 and f1 both reach d2.
+        assert_eq!(san("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1d2"), "Nbd2");
+        // Rooks on a1 and a5 share the file.
+        assert_eq!(san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+        // Queens on a1, a5 and e1 all reach c3, sharing a file and a rank.
+        assert_eq!(san("6k1/8/8/Q7/8/8/8/Q3Q2K w - - 0 1", "a1c3"), "Qa1c3");
+    }
+
+    #[test]
+    fn pinned_rival_does_not_disambiguate() {
+        // The c3 knight is pinned by the a5 bishop, so only Nge2 is legal.
+        assert_eq!(san("4k3/8/8/b7/8/2N5/8/4K1N1 w - - 0 1", "g1e2"), "Ne2");
+    }
+
+    #[test]
+    fn promotion_castling_and_check_suffixes() {
+        assert_eq!(san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n"), "b8=N");
+        assert_eq!(san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q+");
+        assert_eq!(san("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"), "O-O");
+        assert_eq!(san("r3k3/8/8/8/8/8/8/3K4 b q - 0 1", "e8c8"), "O-O-O+");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+    }
+
+    #[test]
+    fn parses_castling_with_zeros_and_suffixes() {
+        let state = state("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert_eq!(state.parse_san("0-0"), state.parse_uci("e1g1"));
+        assert_eq!(state.parse_san("0-0-0"), state.parse_uci("e1c1"));
+        assert_eq!(state.parse_san("O-O+"), state.parse_uci("e1g1"));
+    }
+
+    #[test]
+    fn parses_en_passant_with_or_without_the_suffix() {
+        let state = state("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        let expected = state.parse_uci("e5d6");
+        assert!(expected.is_ok());
+        assert_eq!(state.parse_san("exd6 e.p."), expected);
+        assert_eq!(state.parse_san("exd6"), expected);
+        assert_eq!(state.parse_san("ed6ep"), expected);
+    }
+
+    #[test]
+    fn parses_promotions_and_drops() {
+        let state = state("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(state.parse_san("b8=Q"), state.parse_uci("b7b8q"));
+        assert_eq!(state.parse_san("b8N"), state.parse_uci("b7b8n"));
+        assert_eq!(
+            "N@f3".parse::<Move>(),
+            Ok(Move::drop(PieceType::Knight, "f3".parse().expect("square")))
+        );
+        assert_eq!(
+            "@e4".parse::<Move>().map(|mv| mv.drop),
+            Ok(Some(PieceType::Pawn))
+        );
+    }
+
+    #[test]
+    fn rejects_ambiguous_illegal_and_malformed_input() {
+        let state = state("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+        assert_eq!(
+            state.parse_san("Nd2"),
+            Err(NotationError::AmbiguousMove("Nd2".to_owned()))
+        );
+        assert_eq!(
+            state.parse_san("Nd3"),
+            Err(NotationError::IllegalMove("Nd3".to_owned()))
+        );
+        assert_eq!(
+            state.parse_uci("e1e3"),
+            Err(NotationError::IllegalMove("e1e3".to_owned()))
+        );
+        assert_eq!(state.parse_san(""), Err(NotationError::Empty));
+        assert_eq!(
+            state.parse_san("Nz9"),
+            Err(NotationError::InvalidSquare("z9".to_owned()))
+        );
+        assert_eq!(
+            "e2e9".parse::<Move>(),
+            Err(NotationError::InvalidSquare("e9".to_owned()))
+        );
+    }
+
+    #[test]
+    fn parse_move_accepts_uci_or_san() {
+        let state = GameState::new_start_position();
+        assert_eq!(state.parse_move("g1f3"), state.parse_move("Nf3"));
+        assert_eq!(
+            state.parse_uci("g1f3").map(|mv| mv.to_string()),
+            Ok("g1f3".to_owned())
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use crate::game_logic::chess::{GameState, Move};
+
+impl GameState {
+    /// Number of leaf positions reachable in exactly `depth` plies. Used to
+    /// check the move generator against published reference counts.
+    pub fn perft(&self, depth: u32) -> u64 {
+        let mut state = self.clone();
+        state.perft_recursive(depth)
+    }
+
+    /// Perft split by root move, in the order the moves are generated.
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        let mut state = self.clone();
+        let moves = state.generate_legal_moves();
+        moves
+            .into_iter()
+            .map(|mv| {
+                let nodes = state.perft_after(mv, depth.saturating_sub(1));
+                (mv, nodes)
+            })
+            .collect()
+    }
+
+    fn perft_recursive(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.generate_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves
+            .into_iter()
+            .map(|mv| self.perft_after(mv, depth - 1))
+            .sum()
+    }
+
+    fn perft_after(&mut self, mv: Move, depth: u32) -> u64 {
+        if self.apply_move(mv).is_err() {
+            return 0;
+        }
+        let nodes = self.perft_recursive(depth);
+        self.undo_move();
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_logic::chess::GameState;
+
+    /// Reference counts from the Chess Programming Wiki's perft results
+    /// page, kept shallow enough for a debug build.
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let state = GameState::from_fen(fen).expect("valid FEN");
+        for (depth, &nodes) in (1..).zip(expected) {
+            assert_eq!(state.perft(depth), nodes, "{fen} at depth {depth}");
+        }
+    }
+
+    #[test]
+    fn start_position() {
+        assert_perft(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8_902],
+        );
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2_039, 97_862],
+        );
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2_812, 43_238],
+        );
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9_467],
+        );
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1_486, 62_379],
+        );
+    }
+
+    #[test]
+    fn position_6() {
+        assert_perft(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2_079, 89_890],
+        );
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let state = GameState::new_start_position();
+        let total = state.divide(3).iter().map(|(_, nodes)| nodes).sum::<u64>();
+        assert_eq!(total, state.perft(3));
+    }
+
+    /// Scharnagl's Chess960 positions from the same page.
+    #[test]
+    fn chess960_positions() {
+        for (fen, expected) in [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                [21, 528, 12_189],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18_002],
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                [20, 479, 10_471],
+            ),
+            (
+                "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+                [22, 593, 13_440],
+            ),
+            (
+                "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+                [28, 1_120, 31_058],
+            ),
+            (
+                "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+                [29, 899, 26_578],
+            ),
+        ] {
+            assert_perft(fen, &expected);
+        }
+    }
+
+    /// Plays `castle`, checks where the king and rook end up, then undoes
+    /// it and checks the position is back exactly as it was.
+    fn assert_castle(fen: &str, castle: &str, after: &str) {
+        let mut state = GameState::from_fen(fen).expect("valid FEN");
+        let key = state.zobrist_key();
+        let mv = state.parse_san(castle).expect("legal castle");
+        state.apply_move(mv).expect("legal castle");
+        assert_eq!(state.to_fen(), after);
+
+        state.undo_move().expect("a move to undo");
+        assert_eq!(state.to_fen(), fen);
+        assert_eq!(state.zobrist_key(), key);
+    }
+
+    #[test]
+    fn chess960_castle_without_moving_the_king() {
+        assert_castle(
+            "4k3/8/8/8/8/8/8/6KR w K - 0 1",
+            "O-O",
+            "4k3/8/8/8/8/8/8/5RK1 b - - 1 1",
+        );
+    }
+
+    #[test]
+    fn chess960_castle_without_moving_the_rook() {
+        assert_castle(
+            "4k3/8/8/8/8/8/8/3RK3 w D - 0 1",
+            "O-O-O",
+            "4k3/8/8/8/8/8/8/2KR4 b - - 1 1",
+        );
+    }
+
+    #[test]
+    fn chess960_castle_onto_the_rook() {
+        assert_castle(
+            "4k3/8/8/8/8/8/8/4K1R1 w G - 0 1",
+            "O-O",
+            "4k3/8/8/8/8/8/8/5RK1 b - - 1 1",
+        );
+    }
+
+    #[test]
+    fn chess960_castle_blocked_by_a_piece_on_the_rook_path() {
+        let state = GameState::from_fen("4k3/8/8/8/8/8/8/RN1K4 w A - 0 1").expect("valid FEN");
+        assert!(state.parse_san("O-O-O").is_err());
+    }
+}
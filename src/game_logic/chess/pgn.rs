@@ -0,0 +1,581 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game_logic::chess::{
+    Color, FenError, GameEvent, GameOutcome, GameState, Move, MoveError, NotationError,
+    START_POSITION_FEN, Variant,
+};
+
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+const MOVETEXT_LINE_WIDTH: usize = 80;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Ongoing,
+}
+
+impl GameResult {
+    pub fn from_outcome(outcome: Option<GameOutcome>) -> Self {
+        match outcome {
+            Some(
+                GameOutcome::Checkmate {
+                    winner: Color::White,
+                }
+                | GameOutcome::KingOfTheHill {
+                    winner: Color::White,
+                }
+                | GameOutcome::ThreeCheck {
+                    winner: Color::White,
+                }
+                | GameOutcome::KingExploded {
+                    winner: Color::White,
+                }
+                | GameOutcome::Timeout {
+                    winner: Color::White,
+                }
+                | GameOutcome::Resignation {
+                    winner: Color::White,
+                }
+                | GameOutcome::Forfeit {
+                    winner: Color::White,
+                },
+            ) => Self::WhiteWins,
+            Some(
+                GameOutcome::Checkmate {
+                    winner: Color::Black,
+                }
+                | GameOutcome::KingOfTheHill {
+                    winner: Color::Black,
+                }
+                | GameOutcome::ThreeCheck {
+                    winner: Color::Black,
+                }
+                | GameOutcome::KingExploded {
+                    winner: Color::Black,
+                }
+                | GameOutcome::Timeout {
+                    winner: Color::Black,
+                }
+                | GameOutcome::Resignation {
+                    winner: Color::Black,
+                }
+                | GameOutcome::Forfeit {
+                    winner: Color::Black,
+                },
+            ) => Self::BlackWins,
+            Some(
+                GameOutcome::Stalemate
+                | GameOutcome::FiftyMoveRule
+                | GameOutcome::ThreefoldRepetition
+                | GameOutcome::FivefoldRepetition
+                | GameOutcome::SeventyFiveMoveRule
+                | GameOutcome::InsufficientMaterial
+                | GameOutcome::TimeoutVsInsufficientMaterial
+                | GameOutcome::DrawByAgreement,
+            ) => Self::Draw,
+            None => Self::Ongoing,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+            Self::Ongoing => "*",
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = PgnError;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        match token {
+            "1-0" => Ok(Self::WhiteWins),
+            "0-1" => Ok(Self::BlackWins),
+            "1/2-1/2" => Ok(Self::Draw),
+            "*" => Ok(Self::Ongoing),
+            _ => Err(PgnError::InvalidResult(token.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PgnError {
+    NoGame,
+    MalformedTag(String),
+    InvalidFen(FenError),
+    InvalidResult(String),
+    UnterminatedComment,
+    IllegalMove {
+        ply: usize,
+        text: String,
+        err: NotationError,
+    },
+    UnplayableMove {
+        ply: usize,
+        err: MoveError,
+    },
+}
+
+#[derive(Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub initial_state: GameState,
+    pub moves: Vec<Move>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    pub fn new(initial_state: GameState) -> Self {
+        let mut game = Self {
+            tags: Vec::new(),
+            initial_state,
+            moves: Vec::new(),
+            result: GameResult::Ongoing,
+        };
+        for (name, value) in [
+            ("Event", "Casual game"),
+            ("Site", "3D Rendering Chess"),
+            ("Date", "????.??.??"),
+            ("Round", "-"),
+            ("White", "?"),
+            ("Black", "?"),
+        ] {
+            game.set_tag(name, value);
+        }
+        game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, existing)) => *existing = value.to_owned(),
+            None => self.tags.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    /// Replays the move list and returns the final position together with
+    /// the SAN of every move.
+    pub fn replay(&self) -> Result<(GameState, Vec<String>), PgnError> {
+        self.replay_with_events(|_| {})
+    }
+
+    /// Like `replay`, handing every move's `GameEvent`s to `on_event` as it
+    /// is played.
+    pub fn replay_with_events(
+        &self,
+        mut on_event: impl FnMut(&GameEvent),
+    ) -> Result<(GameState, Vec<String>), PgnError> {
+        let mut state = self.initial_state.clone();
+        let mut notation = Vec::with_capacity(self.moves.len());
+        for (ply, mv) in self.moves.iter().copied().enumerate() {
+            let events = state
+                .play_move(mv)
+                .map_err(|err| PgnError::UnplayableMove { ply, err })?;
+            for event in &events {
+                if let GameEvent::MoveMade { san, .. } = event {
+                    notation.push(san.clone());
+                }
+                on_event(event);
+            }
+        }
+        Ok((state, notation))
+    }
+
+    pub fn to_pgn(&self) -> Result<String, PgnError> {
+        let (_, notation) = self.replay()?;
+        let mut text = String::new();
+
+        for name in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => self.result.as_str(),
+                _ => self.tag(name).unwrap_or("?"),
+            };
+            write_tag(&mut text, name, value);
+        }
+
+        let initial_fen = self.initial_state.to_fen();
+        let custom_setup = initial_fen != START_POSITION_FEN;
+        if custom_setup {
+            write_tag(&mut text, "SetUp", "1");
+            write_tag(&mut text, "FEN", &initial_fen);
+        }
+        let variant = self.initial_state.variant();
+        let chess960 = self.initial_state.is_chess960();
+        let variant_tag = if variant != Variant::Standard {
+            Some(variant.name())
+        } else {
+            chess960.then_some("Chess960")
+        };
+        if let Some(variant_tag) = variant_tag {
+            write_tag(&mut text, "Variant", variant_tag);
+        }
+
+        for (name, value) in &self.tags {
+            let is_written = SEVEN_TAG_ROSTER.contains(&name.as_str())
+                || (custom_setup && (name == "SetUp" || name == "FEN"))
+                || (variant_tag.is_some() && name == "Variant");
+            if !is_written {
+                write_tag(&mut text, name, value);
+            }
+        }
+        text.push('\n');
+
+        let mut tokens = Vec::with_capacity(notation.len() + 1);
+        let mut fullmove = self.initial_state.fullmove_number();
+        let mut side = self.initial_state.side_to_move();
+        for (ply, san) in notation.into_iter().enumerate() {
+            match side {
+                Color::White => tokens.push(format!("{fullmove}. {san}")),
+                Color::Black if ply == 0 => tokens.push(format!("{fullmove}... {san}")),
+                Color::Black => tokens.push(san),
+            }
+            if side == Color::Black {
+                fullmove += 1;
+            }
+            side = side.opposite();
+        }
+        tokens.push(self.result.as_str().to_owned());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MOVETEXT_LINE_WIDTH {
+                text.push_str(&line);
+                text.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        text.push_str(&line);
+        text.push_str("\n\n");
+
+        Ok(text)
+    }
+}
+
+/// Reads every game in a PGN file. Comments, NAGs and variations are
+/// skipped; only the main line is kept.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut tags: Vec<(String, String)> = Vec::new();
+    let mut movetext = String::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('%') {
+            continue;
+        }
+
+        if trimmed.starts_with('[') && !in_open_comment(&movetext) {
+            if !movetext.trim().is_empty() {
+                games.push(parse_game(std::mem::take(&mut tags), &movetext)?);
+                movetext.clear();
+            }
+            tags.push(parse_tag(trimmed)?);
+            continue;
+        }
+
+        movetext.push_str(line);
+        movetext.push('\n');
+    }
+
+    if !tags.is_empty() || !movetext.trim().is_empty() {
+        games.push(parse_game(tags, &movetext)?);
+    }
+
+    if games.is_empty() {
+        return Err(PgnError::NoGame);
+    }
+    Ok(games)
+}
+
+fn parse_game(tags: Vec<(String, String)>, movetext: &str) -> Result<PgnGame, PgnError> {
+    let fen = tags
+        .iter()
+        .find(|(name, _)| name == "FEN")
+        .map(|(_, value)| value.as_str());
+    let mut initial_state = match fen {
+        Some(fen) => GameState::from_fen(fen).map_err(PgnError::InvalidFen)?,
+        None => GameState::new_start_position(),
+    };
+    if let Some((_, variant)) = tags.iter().find(|(name, _)| name == "Variant") {
+        initial_state.chess960 |= variant.eq_ignore_ascii_case("chess960");
+        if let Some(variant) = Variant::from_name(variant) {
+            initial_state = initial_state.with_variant(variant);
+        }
+    }
+
+    let mut game = PgnGame {
+        tags,
+        initial_state: initial_state.clone(),
+        moves: Vec::new(),
+        result: GameResult::Ongoing,
+    };
+    if let Some(result) = game.tag("Result").and_then(|value| value.parse().ok()) {
+        game.result = result;
+    }
+
+    let mut state = initial_state;
+    for token in tokenize_movetext(movetext)? {
+        if let Ok(result) = token.parse::<GameResult>() {
+            game.result = result;
+            break;
+        }
+
+        let san = strip_move_number(&token);
+        if san.is_empty() {
+            continue;
+        }
+
+        let ply = game.moves.len();
+        let mv = state.parse_san(san).map_err(|err| PgnError::IllegalMove {
+            ply,
+            text: san.to_owned(),
+            err,
+        })?;
+        state
+            .apply_move(mv)
+            .map_err(|err| PgnError::UnplayableMove { ply, err })?;
+        game.moves.push(mv);
+    }
+
+    Ok(game)
+}
+
+fn tokenize_movetext(movetext: &str) -> Result<Vec<String>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut variation_depth = 0usize;
+    let mut chars = movetext.chars();
+
+    while let Some(symbol) = chars.next() {
+        match symbol {
+            '{' => {
+                if !chars.by_ref().any(|c| c == '}') {
+                    return Err(PgnError::UnterminatedComment);
+                }
+                flush_token(&mut current, &mut tokens, variation_depth);
+            }
+            ';' => {
+                chars.by_ref().find(|c| *c == '\n');
+                flush_token(&mut current, &mut tokens, variation_depth);
+            }
+            '(' => {
+                flush_token(&mut current, &mut tokens, variation_depth);
+                variation_depth += 1;
+            }
+            ')' => {
+                flush_token(&mut current, &mut tokens, variation_depth);
+                variation_depth = variation_depth.saturating_sub(1);
+            }
+            c if c.is_whitespace() => flush_token(&mut current, &mut tokens, variation_depth),
+            c => current.push(c),
+        }
+    }
+    flush_token(&mut current, &mut tokens, variation_depth);
+
+    Ok(tokens)
+}
+
+fn flush_token(current: &mut String, tokens: &mut Vec<String>, variation_depth: usize) {
+    let token = std::mem::take(current);
+    if token.is_empty() || variation_depth > 0 || token.starts_with('$') {
+        return;
+    }
+    tokens.push(token);
+}
+
+/// Removes a leading move number such as `12.` or `12...` from a token.
+fn strip_move_number(token: &str) -> &str {
+    let digits = token.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return token;
+    }
+    let rest = &token[digits..];
+    if rest.starts_with('.') {
+        rest.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+fn in_open_comment(movetext: &str) -> bool {
+    movetext.matches('{').count() > movetext.matches('}').count()
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), PgnError> {
+    let malformed = || PgnError::MalformedTag(line.to_owned());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(malformed)?
+        .trim();
+    let (name, value) = inner
+        .split_once(char::is_whitespace)
+        .ok_or_else(malformed)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(malformed)?;
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(symbol) = chars.next() {
+        if symbol == '\\' {
+            if let Some(escaped) = chars.next() {
+                unescaped.push(escaped);
+            }
+        } else {
+            unescaped.push(symbol);
+        }
+    }
+
+    Ok((name.to_owned(), unescaped))
+}
+
+fn write_tag(text: &mut String, name: &str, value: &str) {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    text.push_str(&format!("[{name} \"{escaped}\"]\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_game(text: &str) -> PgnGame {
+        let mut games = parse_pgn(text).expect("valid PGN");
+        assert_eq!(games.len(), 1);
+        games.remove(0)
+    }
+
+    fn play(state: &GameState, moves: &[&str]) -> Vec<Move> {
+        let mut state = state.clone();
+        moves
+            .iter()
+            .map(|text| {
+                let mv = state.parse_san(text).expect("legal move");
+                state.apply_move(mv).expect("legal move");
+                mv
+            })
+            .collect()
+    }
+
+    #[test]
+    fn export_then_import_keeps_moves_and_tags() {
+        let mut game = PgnGame::new(GameState::new_start_position());
+        game.set_tag("White", "Anderssen, \"Adolf\"");
+        game.set_tag("Annotator", "Nobody");
+        game.moves = play(
+            &game.initial_state,
+            &["e4", "e5", "f4", "exf4", "Bc4", "Qh4+", "Kf1", "b5"],
+        );
+        game.result = GameResult::Draw;
+
+        let text = game.to_pgn().expect("playable game");
+        assert!(text.contains("1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ 4. Kf1 b5 1/2-1/2"));
+        let imported = single_game(&text);
+        assert_eq!(imported.moves, game.moves);
+        assert_eq!(imported.result, GameResult::Draw);
+        for name in ["Event", "Site", "White", "Black", "Annotator"] {
+            assert_eq!(imported.tag(name), game.tag(name), "{name}");
+        }
+        assert_eq!(imported.to_pgn(), Ok(text));
+    }
+
+    #[test]
+    fn skips_comments_variations_and_nags() {
+        let game = single_game(
+            "[Event \"Test\"]\n\n\
+             1. e4 {best by test} e5 $1 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 ; a remark\n\
+             Nc6 3. Bb5!? a6 *\n",
+        );
+        assert_eq!(
+            game.moves,
+            play(
+                &GameState::new_start_position(),
+                &["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]
+            )
+        );
+        assert_eq!(game.result, GameResult::Ongoing);
+    }
+
+    #[test]
+    fn reads_setup_and_variant_tags() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 12";
+        let game = single_game(&format!(
+            "[SetUp \"1\"]\n[FEN \"{fen}\"]\n\n12... Kd7 13. e4 *\n"
+        ));
+        assert_eq!(game.initial_state.to_fen(), fen);
+        assert_eq!(game.moves.len(), 2);
+        let text = game.to_pgn().expect("playable game");
+        assert!(text.contains("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]"));
+        assert!(text.contains("12... Kd7 13. e4 *"));
+
+        let game = single_game("[Variant \"Crazyhouse\"]\n\n1. e4 d5 2. exd5 Qxd5 3. P@e4 *\n");
+        assert_eq!(game.initial_state.variant(), Variant::Crazyhouse);
+        assert_eq!(game.moves.len(), 5);
+        assert!(
+            game.to_pgn()
+                .expect("playable game")
+                .contains("[Variant \"Crazyhouse\"]")
+        );
+
+        let game = single_game(
+            "[Variant \"Chess960\"]\n\
+             [FEN \"4k3/8/8/8/8/8/8/5K1R w K - 0 9\"]\n\n\
+             9. O-O *\n",
+        );
+        assert!(game.initial_state.is_chess960());
+        assert_eq!(
+            game.moves,
+            [Move::new(
+                "f1".parse().expect("square"),
+                "h1".parse().expect("square")
+            )]
+        );
+    }
+
+    #[test]
+    fn illegal_movetext_is_an_error() {
+        assert_eq!(
+            parse_pgn("1. e4 e5 2. Ke3 *\n").err(),
+            Some(PgnError::IllegalMove {
+                ply: 2,
+                text: "Ke3".to_owned(),
+                err: NotationError::IllegalMove("Ke3".to_owned()),
+            })
+        );
+        assert_eq!(
+            parse_pgn("1. e4 {unfinished\n").err(),
+            Some(PgnError::UnterminatedComment)
+        );
+        assert_eq!(
+            parse_pgn("[Event Test]\n1. e4 *\n").err(),
+            Some(PgnError::MalformedTag("[Event Test]".to_owned()))
+        );
+        assert_eq!(parse_pgn("\n").err(), Some(PgnError::NoGame));
+    }
+}
@@ -0,0 +1,438 @@
+use std::fs;
+use std::path::Path;
+
+use crate::game_logic::chess::{Color, FenError, GameState, Move, NotationError};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PuzzleError {
+    Io(String),
+    NoPuzzles,
+    InvalidFen {
+        line: usize,
+        err: FenError,
+    },
+    /// An EPD line without a `bm` opcode, or a CSV row without moves.
+    MissingSolution(usize),
+    InvalidMove {
+        line: usize,
+        text: String,
+        err: NotationError,
+    },
+    /// A CSV row with fewer columns than the header asks for.
+    MissingColumn(usize),
+}
+
+/// A position with the line that solves it. `moves` starts from
+/// `position` and alternates sides; the solver plays the last move, so a
+/// Lichess puzzle begins with the opponent's move that sets it up.
+#[derive(Clone)]
+pub struct Puzzle {
+    pub id: String,
+    pub position: GameState,
+    pub moves: Vec<Move>,
+    /// Other first moves accepted, from extra `bm` operands in EPD.
+    pub alternatives: Vec<Move>,
+    pub rating: Option<u32>,
+}
+
+impl Puzzle {
+    /// The side that has to find the moves.
+    pub fn solver(&self) -> Color {
+        let side_to_move = self.position.side_to_move();
+        if self.moves.len() % 2 == 1 {
+            side_to_move
+        } else {
+            side_to_move.opposite()
+        }
+    }
+
+    /// Whether the solver plays move number `ply` of the line.
+    pub fn is_solver_ply(&self, ply: usize) -> bool {
+        ply.is_multiple_of(2) == (self.position.side_to_move() == self.solver())
+    }
+
+    /// Whether `mv`, played in `state` at `ply`, follows the line. Another
+    /// move that mates is as good as the one in the line.
+    fn accepts(&self, ply: usize, state: &GameState, mv: Move) -> bool {
+        if self.moves.get(ply) == Some(&mv) || (ply == 0 && self.alternatives.contains(&mv)) {
+            return true;
+        }
+        let mut after = state.clone();
+        after.apply_move(mv).is_ok() && after.is_checkmate(after.side_to_move())
+    }
+}
+
+/// What happened to a move the solver tried.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PuzzleAttempt {
+    /// Not a legal move at all; it is left to `apply_move` to explain.
+    Illegal,
+    Wrong,
+    Correct,
+    Solved,
+}
+
+/// A run through a list of puzzles, counting the ones solved and failed.
+/// A puzzle counts as failed after one wrong move, or when skipped.
+#[derive(Clone)]
+pub struct PuzzleSession {
+    puzzles: Vec<Puzzle>,
+    current: usize,
+    ply: usize,
+    missed: bool,
+    solved: u32,
+    failed: u32,
+}
+
+impl PuzzleSession {
+    pub fn new(puzzles: Vec<Puzzle>) -> Self {
+        Self {
+            puzzles,
+            current: 0,
+            ply: 0,
+            missed: false,
+            solved: 0,
+            failed: 0,
+        }
+    }
+
+    /// The puzzle being played, `None` once the list is used up.
+    pub fn current(&self) -> Option<&Puzzle> {
+        self.puzzles.get(self.current)
+    }
+
+    /// Position of the current puzzle, counting from one.
+    pub fn number(&self) -> usize {
+        self.current + 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn solved_count(&self) -> u32 {
+        self.solved
+    }
+
+    pub fn failed_count(&self) -> u32 {
+        self.failed
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.current()
+            .is_some_and(|puzzle| self.ply == puzzle.moves.len())
+    }
+
+    pub fn is_solver_to_move(&self) -> bool {
+        self.current()
+            .is_some_and(|puzzle| self.ply < puzzle.moves.len() && puzzle.is_solver_ply(self.ply))
+    }
+
+    /// The line's next move when it is the opponent's turn.
+    pub fn next_reply(&self) -> Option<Move> {
+        let puzzle = self.current()?;
+        if puzzle.is_solver_ply(self.ply) {
+            return None;
+        }
+        puzzle.moves.get(self.ply).copied()
+    }
+
+    /// Moves past the reply returned by `next_reply` once it is played.
+    pub fn reply_played(&mut self) {
+        self.ply += 1;
+    }
+
+    /// Checks the solver's `mv` in `state` against the line.
+    pub fn try_move(&mut self, state: &GameState, mv: Move) -> PuzzleAttempt {
+        let Some(puzzle) = self.current() else {
+            return PuzzleAttempt::Illegal;
+        };
+        if state.clone().apply_move(mv).is_err() {
+            return PuzzleAttempt::Illegal;
+        }
+        if !puzzle.accepts(self.ply, state, mv) {
+            if !self.missed {
+                self.missed = true;
+                self.failed += 1;
+            }
+            return PuzzleAttempt::Wrong;
+        }
+
+        let line_length = puzzle.moves.len();
+        let mates = puzzle.moves.get(self.ply) != Some(&mv);
+        self.ply += 1;
+        if mates || self.ply == line_length {
+            self.ply = line_length;
+            if !self.missed {
+                self.solved += 1;
+            }
+            PuzzleAttempt::Solved
+        } else {
+            PuzzleAttempt::Correct
+        }
+    }
+
+    /// Moves on to the next puzzle. Leaving one unsolved without a wrong
+    /// move counts it as failed.
+    pub fn advance(&mut self) -> Option<&Puzzle> {
+        if self.current().is_some() && !self.is_solved() && !self.missed {
+            self.failed += 1;
+        }
+        self.current += 1;
+        self.ply = 0;
+        self.missed = false;
+        self.current()
+    }
+}
+
+pub fn load_puzzles(path: &Path) -> Result<Vec<Puzzle>, PuzzleError> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| PuzzleError::Io(format!("{}: {err}", path.display())))?;
+    parse_puzzles(&text)
+}
+
+/// Reads EPD lines with `bm` opcodes, or Lichess-style CSV when the first
+/// line has commas in it.
+pub fn parse_puzzles(text: &str) -> Result<Vec<Puzzle>, PuzzleError> {
+    let lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect::<Vec<_>>();
+    let is_csv = lines.first().is_some_and(|(_, line)| line.contains(','));
+
+    let puzzles = if is_csv {
+        parse_csv(&lines)?
+    } else {
+        lines
+            .into_iter()
+            .map(|(line_number, line)| parse_epd_line(line_number, line))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if puzzles.is_empty() {
+        return Err(PuzzleError::NoPuzzles);
+    }
+    Ok(puzzles)
+}
+
+/// `<4 FEN fields> bm Qxf7#; id "mate 1";`. Every `bm` operand is a SAN
+/// move accepted as the answer.
+fn parse_epd_line(line_number: usize, line: &str) -> Result<Puzzle, PuzzleError> {
+    let fen = line
+        .split_whitespace()
+        .take(4)
+        .collect::<Vec<_>>()
+        .join(" ");
+    // Whatever follows the fourth field, however much whitespace is
+    // between the fields.
+    let mut operations = line.trim();
+    for _ in 0..4 {
+        operations = operations
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest.trim_start());
+    }
+    let position = GameState::from_fen(&fen).map_err(|err| PuzzleError::InvalidFen {
+        line: line_number,
+        err,
+    })?;
+
+    let mut id = format!("line {line_number}");
+    let mut best_moves = Vec::new();
+    for operation in operations.split(';') {
+        let operation = operation.trim();
+        let (opcode, operands) = operation
+            .split_once(char::is_whitespace)
+            .unwrap_or((operation, ""));
+        match opcode {
+            "bm" => {
+                for text in operands.split_whitespace() {
+                    let mv = position
+                        .parse_san(text)
+                        .map_err(|err| PuzzleError::InvalidMove {
+                            line: line_number,
+                            text: text.to_owned(),
+                            err,
+                        })?;
+                    best_moves.push(mv);
+                }
+            }
+            "id" => id = operands.trim().trim_matches('"').to_owned(),
+            _ => {}
+        }
+    }
+
+    let (first, alternatives) = best_moves
+        .split_first()
+        .ok_or(PuzzleError::MissingSolution(line_number))?;
+    Ok(Puzzle {
+        id,
+        position,
+        moves: vec![*first],
+        alternatives: alternatives.to_vec(),
+        rating: None,
+    })
+}
+
+/// Columns are found by a `FEN`/`Moves`/`Rating`/`PuzzleId` header when
+/// there is one. Without a header, a row starting with a FEN is read as
+/// `FEN,moves,rating`, anything else in the Lichess database order
+/// `PuzzleId,FEN,Moves,Rating,...`. Moves are UCI, separated by spaces.
+fn parse_csv(lines: &[(usize, &str)]) -> Result<Vec<Puzzle>, PuzzleError> {
+    let Some((_, first)) = lines.first() else {
+        return Ok(Vec::new());
+    };
+    let header = first.split(',').map(str::trim).collect::<Vec<_>>();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|title| title.eq_ignore_ascii_case(name))
+    };
+
+    let (rows, id, fen, moves, rating) = match (column("FEN"), column("Moves")) {
+        (Some(fen), Some(moves)) => (
+            &lines[1..],
+            column("PuzzleId"),
+            fen,
+            moves,
+            column("Rating"),
+        ),
+        _ if header.first().is_some_and(|field| field.contains('/')) => {
+            (lines, None, 0, 1, Some(2))
+        }
+        _ => (lines, Some(0), 1, 2, Some(3)),
+    };
+
+    rows.iter()
+        .map(|(line_number, line)| {
+            let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+            let field = |index: usize| {
+                fields
+                    .get(index)
+                    .copied()
+                    .ok_or(PuzzleError::MissingColumn(*line_number))
+            };
+
+            let position =
+                GameState::from_fen(field(fen)?).map_err(|err| PuzzleError::InvalidFen {
+                    line: *line_number,
+                    err,
+                })?;
+            let mut state = position.clone();
+            let mut line_moves = Vec::new();
+            for text in field(moves)?.split_whitespace() {
+                let mv = state
+                    .parse_uci(text)
+                    .map_err(|err| PuzzleError::InvalidMove {
+                        line: *line_number,
+                        text: text.to_owned(),
+                        err,
+                    })?;
+                state.apply_move(mv).map_err(|_| PuzzleError::InvalidMove {
+                    line: *line_number,
+                    text: text.to_owned(),
+                    err: NotationError::IllegalMove(text.to_owned()),
+                })?;
+                line_moves.push(mv);
+            }
+            if line_moves.is_empty() {
+                return Err(PuzzleError::MissingSolution(*line_number));
+            }
+
+            Ok(Puzzle {
+                id: id
+                    .and_then(|index| fields.get(index))
+                    .map_or_else(|| format!("line {line_number}"), |id| (*id).to_owned()),
+                position,
+                moves: line_moves,
+                alternatives: Vec::new(),
+                rating: rating
+                    .and_then(|index| fields.get(index))
+                    .and_then(|rating| rating.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(text: &str) -> (PuzzleSession, GameState) {
+        let puzzles = parse_puzzles(text).expect("puzzles");
+        let position = puzzles[0].position.clone();
+        (PuzzleSession::new(puzzles), position)
+    }
+
+    #[test]
+    fn epd_fields_may_be_separated_by_any_whitespace() {
+        let puzzles = parse_puzzles(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR  w\tKQkq -   bm  Qxf7#;\tid \"scholar\";",
+        )
+        .expect("puzzles");
+        assert_eq!(puzzles[0].id, "scholar");
+        assert_eq!(puzzles[0].moves.len(), 1);
+        assert_eq!(puzzles[0].position.side_to_move(), Color::White);
+    }
+
+    #[test]
+    fn any_bm_operand_solves_an_epd_puzzle() {
+        let text =
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7# Qxe5+;";
+        let (mut session, state) = session(text);
+        assert_eq!(
+            session.current().map(|puzzle| puzzle.alternatives.len()),
+            Some(1)
+        );
+
+        let alternative = state.parse_san("Qxe5+").expect("legal move");
+        assert_eq!(session.try_move(&state, alternative), PuzzleAttempt::Solved);
+        assert_eq!(session.solved_count(), 1);
+    }
+
+    #[test]
+    fn epd_without_bm_is_rejected() {
+        assert_eq!(
+            parse_puzzles("4k3/8/8/8/8/8/8/4K2R w K - id \"none\";").err(),
+            Some(PuzzleError::MissingSolution(1))
+        );
+    }
+
+    #[test]
+    fn lichess_rows_start_with_the_opponents_move() {
+        let text = "PuzzleId,FEN,Moves,Rating,RatingDeviation\n\
+                    abc12,r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 3 3,g8f6 h5f7,612,80\n";
+        let (mut session, mut state) = session(text);
+        let puzzle = session.current().expect("puzzle");
+        assert_eq!(puzzle.id, "abc12");
+        assert_eq!(puzzle.rating, Some(612));
+        assert_eq!(puzzle.solver(), Color::White);
+
+        assert!(!session.is_solver_to_move());
+        let reply = session.next_reply().expect("setup move");
+        state.apply_move(reply).expect("legal move");
+        session.reply_played();
+
+        assert!(session.is_solver_to_move());
+        let mate = state.parse_uci("h5f7").expect("legal move");
+        assert_eq!(session.try_move(&state, mate), PuzzleAttempt::Solved);
+        assert!(session.is_solved());
+    }
+
+    #[test]
+    fn another_mate_is_as_good_as_the_line() {
+        let (mut session, state) = session("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - bm Ra8#;");
+
+        let wrong = state.parse_san("Kf1").expect("legal move");
+        assert_eq!(session.try_move(&state, wrong), PuzzleAttempt::Wrong);
+        let illegal = Move::new("a1".parse().expect("square"), "b3".parse().expect("square"));
+        assert_eq!(session.try_move(&state, illegal), PuzzleAttempt::Illegal);
+
+        let other_mate = state.parse_san("Re8#").expect("legal move");
+        assert_eq!(session.try_move(&state, other_mate), PuzzleAttempt::Solved);
+        // The wrong move already counted the puzzle as failed.
+        assert_eq!((session.solved_count(), session.failed_count()), (0, 1));
+        assert!(session.advance().is_none());
+        assert_eq!(session.failed_count(), 1);
+    }
+}
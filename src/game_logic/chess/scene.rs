@@ -1,8 +1,13 @@
 use glam::Vec3;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::time::Instant;
 
+use crate::game_logic::chess::bitboard::piece_index;
 use crate::game_logic::chess::{
-    Color, GameOutcome, GameState, Move, PieceType, Square, square_to_world, world_to_square,
+    BoardSetup, ChessClock, Color, GameEvent, GameOutcome, GameResult, GameState, LegalMove, Move,
+    MoveError, MoveRecord, PgnError, PgnGame, Piece, PieceType, Square, TimeControl, Variant,
+    setup_error_message, square_to_world, world_to_square,
 };
 
 pub struct ChessSceneState {
@@ -13,14 +18,141 @@ pub struct ChessSceneState {
     pub board_max: Vec3,
     pub model_by_square: HashMap<Square, usize>,
     pub square_by_model: HashMap<usize, Square>,
-    pub highlight_model_indices: Vec<usize>,
+    /// Reusable highlight models, one pool per style since each model's
+    /// color is baked into its texture.
+    pub highlight_models: HashMap<MoveHighlight, Vec<usize>>,
+    pub threat_overlay_models: HashMap<ThreatMark, Vec<usize>>,
+    /// Zobrist key of the position the overlay was last drawn for.
+    pub threat_overlay_key: Option<u64>,
     pub selected_square: Option<Square>,
+    /// Pocket piece picked for a Crazyhouse drop, instead of a square.
+    pub selected_drop: Option<PieceType>,
+    pub pending_promotion: Option<Move>,
+    pub promotion_choice_models: Vec<PromotionChoiceModel>,
+    /// Crazyhouse pocket models in the order they were pocketed; the last
+    /// one of a piece is dropped first.
+    pub pocket_models: Vec<PocketModel>,
+    pub initial_state: GameState,
+    pub move_notation: Vec<String>,
+    pub captured_model_history: Vec<RemovedModels>,
+    /// Models of the pieces the last move played captured, for the events
+    /// that report those captures.
+    pub captured_models: Vec<CapturedModel>,
+    pub pgn_tags: Vec<(String, String)>,
     pub last_error: Option<String>,
+    /// `None` for an untimed game.
+    pub clock: Option<ChessClock>,
+    /// The side whose draw offer is waiting for an answer.
+    pub draw_offer: Option<Color>,
+    /// Set while the board is being edited in setup mode instead of played.
+    pub setup: Option<BoardSetup>,
+    /// What a click in setup mode puts on a square; `None` clears it.
+    pub setup_palette: Option<Piece>,
+    /// Events not yet handed to subscribers, oldest first.
+    events: Vec<GameEvent>,
 }
 
+/// One square changed in setup mode: the model to take off the board and
+/// the piece, if any, that needs a new model there.
+pub struct SetupEdit {
+    pub square: Square,
+    pub removed_model: Option<usize>,
+    pub placed: Option<Piece>,
+}
+
+#[derive(Default)]
 pub struct ModelMoveUpdate {
     pub moved_models: Vec<PieceMotionUpdate>,
-    pub captured_model_index: Option<usize>,
+    pub promoted_model: Option<PromotedModelUpdate>,
+    /// Models swapped to another template as they change sides on their
+    /// way into or back out of a Crazyhouse pocket.
+    pub recolored_models: Vec<PromotedModelUpdate>,
+    pub restored_models: Vec<RestoredModelUpdate>,
+}
+
+/// Models one move took off the board, kept so an undo can put them back.
+#[derive(Debug, Clone, Default)]
+pub struct RemovedModels {
+    pub captured: Option<usize>,
+    /// The capturing piece's own model, when an Atomic capture blew it up.
+    pub capturer: Option<usize>,
+    pub exploded: Vec<(Square, usize)>,
+}
+
+/// A captured piece brought back by an undo. `model_index` is `None` when
+/// the piece was captured before the scene was loaded and has no model yet.
+pub struct RestoredModelUpdate {
+    pub model_index: Option<usize>,
+    pub square: Square,
+    pub piece: Piece,
+}
+
+/// The model of a piece a move captured. In Crazyhouse it goes into the
+/// capturer's pocket, changing sides, rather than off the board.
+#[derive(Debug, Copy, Clone)]
+pub struct CapturedModel {
+    pub square: Square,
+    pub piece: Piece,
+    pub model_index: usize,
+    /// The piece it becomes in the pocket and where it sits there.
+    pub pocketed: Option<(Piece, Vec3)>,
+}
+
+pub struct PromotedModelUpdate {
+    pub model_index: usize,
+    pub piece: Piece,
+}
+
+/// How a legal destination is marked on the board, from least to most
+/// notable. A square reached by several moves takes the highest.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum MoveHighlight {
+    Quiet,
+    Check,
+    Capture,
+    Special,
+}
+
+impl MoveHighlight {
+    /// Castling, en passant and promotions are special; other captures and
+    /// checking moves come next.
+    pub fn for_move(legal_move: &LegalMove) -> Self {
+        if legal_move.castle.is_some() || legal_move.is_en_passant || legal_move.is_promotion() {
+            Self::Special
+        } else if legal_move.is_capture() {
+            Self::Capture
+        } else if legal_move.gives_check {
+            Self::Check
+        } else {
+            Self::Quiet
+        }
+    }
+}
+
+/// A mark of the threat overlay: a square tint for the side with more
+/// pieces attacking it, or a marker on a hanging or pinned piece and along
+/// its pin line.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ThreatMark {
+    WhiteControl,
+    BlackControl,
+    Contested,
+    Hanging,
+    Pinned,
+    PinLine,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct PromotionChoiceModel {
+    pub model_index: usize,
+    pub piece: Piece,
+}
+
+/// A piece waiting in a Crazyhouse pocket, colored for the side holding it.
+#[derive(Debug, Copy, Clone)]
+pub struct PocketModel {
+    pub model_index: usize,
+    pub piece: Piece,
 }
 
 pub struct PieceMotionUpdate {
@@ -38,6 +170,7 @@ impl ChessSceneState {
         square_by_model: HashMap<usize, Square>,
     ) -> Self {
         Self {
+            initial_state: game_state.clone(),
             game_state,
             game_outcome: None,
             board_model_index,
@@ -45,9 +178,24 @@ impl ChessSceneState {
             board_max,
             model_by_square,
             square_by_model,
-            highlight_model_indices: Vec::new(),
+            highlight_models: HashMap::new(),
+            threat_overlay_models: HashMap::new(),
+            threat_overlay_key: None,
             selected_square: None,
+            selected_drop: None,
+            pending_promotion: None,
+            promotion_choice_models: Vec::new(),
+            pocket_models: Vec::new(),
+            move_notation: Vec::new(),
+            captured_model_history: Vec::new(),
+            captured_models: Vec::new(),
+            pgn_tags: Vec::new(),
             last_error: None,
+            clock: None,
+            draw_offer: None,
+            setup: None,
+            setup_palette: None,
+            events: Vec::new(),
         }
     }
 
@@ -55,108 +203,746 @@ impl ChessSceneState {
         self.last_error = None;
     }
 
+    /// Checks whether the variant's win condition is met, the side to move is
+    /// mated or stalemated, or the game is drawn automatically, and records
+    /// the outcome with a `GameOver` event.
+    pub fn update_game_outcome(&mut self) -> Option<GameOutcome> {
+        let outcome = self.game_state.game_outcome();
+        if let Some(outcome) = outcome {
+            self.game_outcome = Some(outcome);
+            self.events.push(GameEvent::GameOver(outcome));
+        }
+        outcome
+    }
+
+    /// Plays `mv` in `game_state`, notes it in the move list, rebinds the
+    /// piece models to their new squares and presses the clock. The move's
+    /// events are queued for `take_events`; moving the models on screen is
+    /// left to whoever handles them.
+    pub fn play_move(&mut self, mv: Move, now: Instant) -> Result<(), MoveError> {
+        let events = self.game_state.play_move(mv)?;
+        self.queue_move_events(mv, events);
+        self.press_clock(now);
+        Ok(())
+    }
+
+    fn queue_move_events(&mut self, mv: Move, events: Vec<GameEvent>) {
+        self.apply_mapping_after_move(mv);
+        for event in &events {
+            match event {
+                GameEvent::MoveMade { san, .. } => self.record_move(san.clone()),
+                GameEvent::GameOver(outcome) => self.game_outcome = Some(*outcome),
+                _ => {}
+            }
+        }
+        self.events.extend(events);
+    }
+
+    /// Hands over the events queued since the last call.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Move list text such as `1. e4 e5 2. Nf3`, numbered from the fullmove
+    /// number the scene started at.
+    pub fn move_list_text(&self) -> String {
+        let mut fullmove = self.game_state.fullmove_number() as usize;
+        let mut side = self.game_state.side_to_move();
+        for _ in &self.move_notation {
+            side = side.opposite();
+            if side == Color::Black {
+                fullmove -= 1;
+            }
+        }
+
+        let mut text = String::new();
+        for san in &self.move_notation {
+            if side == Color::White {
+                text.push_str(&format!("{fullmove}. "));
+            } else if text.is_empty() {
+                text.push_str(&format!("{fullmove}... "));
+            }
+            text.push_str(san);
+            text.push(' ');
+            if side == Color::Black {
+                fullmove += 1;
+            }
+            side = side.opposite();
+        }
+        text.trim_end().to_owned()
+    }
+
+    pub fn claim_draw(&mut self) -> Option<GameOutcome> {
+        if self.game_outcome.is_some() {
+            return None;
+        }
+
+        let outcome = self.game_state.claimable_draw()?;
+        self.end_game(outcome)
+    }
+
+    /// `color` gives up; the opponent wins.
+    pub fn resign(&mut self, color: Color) -> Option<GameOutcome> {
+        if self.game_outcome.is_some() {
+            return None;
+        }
+        self.end_game(GameOutcome::Resignation {
+            winner: color.opposite(),
+        })
+    }
+
+    /// Records a draw offer from `color`. Fails once the game is over or
+    /// while an offer is already waiting.
+    pub fn offer_draw(&mut self, color: Color) -> bool {
+        if self.game_outcome.is_some() || self.draw_offer.is_some() {
+            return false;
+        }
+        self.draw_offer = Some(color);
+        true
+    }
+
+    /// `color` accepts the opponent's standing draw offer.
+    pub fn accept_draw(&mut self, color: Color) -> Option<GameOutcome> {
+        if self.game_outcome.is_some() || self.draw_offer != Some(color.opposite()) {
+            return None;
+        }
+        self.end_game(GameOutcome::DrawByAgreement)
+    }
+
+    /// `color` turns down the opponent's standing draw offer.
+    pub fn decline_draw(&mut self, color: Color) -> bool {
+        if self.draw_offer != Some(color.opposite()) {
+            return false;
+        }
+        self.draw_offer = None;
+        true
+    }
+
+    fn end_game(&mut self, outcome: GameOutcome) -> Option<GameOutcome> {
+        self.game_outcome = Some(outcome);
+        self.draw_offer = None;
+        self.events.push(GameEvent::GameOver(outcome));
+        Some(outcome)
+    }
+
+    /// Restarts both clocks from the full base time of `time_control`, or
+    /// takes the clocks away for `None`.
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>, now: Instant) {
+        self.clock = time_control.map(|time_control| {
+            let mut clock = ChessClock::new(time_control, self.game_state.side_to_move(), now);
+            if self.game_outcome.is_some() {
+                clock.stop(now);
+            }
+            clock
+        });
+    }
+
+    /// Switches the clock after a move, or stops it if the move ended the
+    /// game. A move made after the mover's flag fell ends the game on time.
+    pub fn press_clock(&mut self, now: Instant) {
+        let game_over = self.game_outcome.is_some();
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        if game_over {
+            clock.stop(now);
+        } else if let Some(loser) = clock.press(now) {
+            self.end_on_time(loser);
+        }
+    }
+
+    /// Hands the running clock to the side to move after an undo or redo.
+    pub fn sync_clock(&mut self, now: Instant) {
+        let side_to_move = self.game_state.side_to_move();
+        let game_over = self.game_outcome.is_some();
+        if let Some(clock) = &mut self.clock {
+            if game_over {
+                clock.stop(now);
+            } else {
+                clock.hand_over(side_to_move, now);
+            }
+        }
+    }
+
+    /// Stops the clock once the game is over some other way, and ends it on
+    /// time when the running side's flag falls: a loss, unless the opponent
+    /// could never mate.
+    pub fn update_clock(&mut self, now: Instant) -> Option<GameOutcome> {
+        let clock = self.clock.as_mut()?;
+        if self.game_outcome.is_some() {
+            clock.stop(now);
+            return None;
+        }
+
+        let loser = clock.flagged(now)?;
+        clock.stop(now);
+        self.end_on_time(loser)
+    }
+
+    /// `loser` ran out of time: a loss, unless the opponent could never
+    /// mate.
+    fn end_on_time(&mut self, loser: Color) -> Option<GameOutcome> {
+        let winner = loser.opposite();
+        self.end_game(if self.game_state.can_possibly_mate(winner) {
+            GameOutcome::Timeout { winner }
+        } else {
+            GameOutcome::TimeoutVsInsufficientMaterial
+        })
+    }
+
+    /// Switches to setup mode, starting from the current position. The clock
+    /// stops and any selection, promotion or draw offer is dropped.
+    pub fn begin_setup(&mut self, now: Instant) {
+        self.setup = Some(BoardSetup::from_state(&self.game_state));
+        self.pending_promotion = None;
+        self.draw_offer = None;
+        self.clear_selection();
+        if let Some(clock) = &mut self.clock {
+            clock.stop(now);
+        }
+    }
+
+    /// The square a setup-mode click lands on: the square of a piece model,
+    /// or the board square under the hit point.
+    pub fn setup_click_square(
+        &self,
+        model_index: usize,
+        hit_point: Option<Vec3>,
+    ) -> Option<Square> {
+        if let Some(square) = self.square_by_model.get(&model_index) {
+            return Some(*square);
+        }
+        if model_index != self.board_model_index {
+            return None;
+        }
+        self.world_to_square(hit_point?)
+    }
+
+    /// Puts the palette piece on `square`, or clears the square when the
+    /// palette is empty or the same piece is already there.
+    pub fn edit_setup_square(&mut self, square: Square) -> Option<SetupEdit> {
+        let setup = self.setup.as_mut()?;
+        let current = setup.piece_at(square);
+        let placed = self.setup_palette.filter(|piece| current != Some(*piece));
+        if current.is_none() && placed.is_none() {
+            return None;
+        }
+
+        setup.set_piece(square, placed);
+        Some(SetupEdit {
+            square,
+            removed_model: self.unbind_square(square),
+            placed,
+        })
+    }
+
+    /// Empties the setup board and returns the models to take away.
+    pub fn clear_setup_board(&mut self) -> Vec<usize> {
+        let Some(setup) = &mut self.setup else {
+            return Vec::new();
+        };
+        setup.clear();
+        self.square_by_model.clear();
+        self.model_by_square
+            .drain()
+            .map(|(_, model_index)| model_index)
+            .collect()
+    }
+
+    /// Builds the edited position. Stays in setup mode with the reason in
+    /// `last_error` when the position cannot be played.
+    pub fn finish_setup(&mut self) -> Option<GameState> {
+        match self.setup.as_ref()?.build() {
+            Ok(state) => {
+                self.setup = None;
+                Some(state)
+            }
+            Err(err) => {
+                self.last_error = Some(setup_error_message(&err));
+                None
+            }
+        }
+    }
+
+    /// Adds the move just played to the move list. A move by the side a
+    /// draw was offered to declines the offer.
+    fn record_move(&mut self, notation: String) {
+        self.move_notation.push(notation);
+        if self.draw_offer == Some(self.game_state.side_to_move()) {
+            self.draw_offer = None;
+        }
+    }
+
+    /// Takes back the last move in `game_state` and returns the model
+    /// motions that reverse it. No events are queued, so subscribers only
+    /// hear about the move again if it is redone.
+    pub fn undo_move(&mut self) -> Option<ModelMoveUpdate> {
+        let record = self.game_state.undo_move()?;
+        let removed = self.captured_model_history.pop().unwrap_or_default();
+        self.move_notation.pop();
+        self.game_outcome = None;
+        self.draw_offer = None;
+        self.pending_promotion = None;
+        self.clear_selection();
+
+        if record.mv.drop.is_some() {
+            let model_index = self.unbind_square(record.mv.to)?;
+            let destination_world_position = self.push_pocket_model(model_index, record.piece);
+            return Some(ModelMoveUpdate {
+                moved_models: vec![PieceMotionUpdate {
+                    model_index,
+                    destination_world_position,
+                }],
+                ..ModelMoveUpdate::default()
+            });
+        }
+
+        let from = record.mv.from;
+        let mut moved_models = Vec::new();
+        let mut recolored_models = Vec::new();
+        let mut restored_models = Vec::new();
+        let moving_model_index = match removed.capturer {
+            Some(model_index) => Some(model_index),
+            None => self.model_by_square.remove(&record.destination()),
+        };
+        // Lifted before the king is rebound: in Chess960 the rook may have
+        // landed on the king's start square.
+        let rook_model = record
+            .castling_rook_squares()
+            .and_then(|(rook_from, rook_to)| {
+                let rook_model_index = self.model_by_square.remove(&rook_to)?;
+                Some((rook_model_index, rook_from))
+            });
+        match moving_model_index {
+            Some(model_index) if record.explosion.is_none() => {
+                moved_models.push(PieceMotionUpdate {
+                    model_index,
+                    destination_world_position: self.square_to_world(from),
+                });
+                self.bind_model(from, model_index);
+            }
+            model_index => {
+                if let Some(model_index) = model_index {
+                    self.bind_model(from, model_index);
+                }
+                restored_models.push(RestoredModelUpdate {
+                    model_index,
+                    square: from,
+                    piece: record.piece,
+                });
+            }
+        }
+
+        if let Some((rook_model_index, rook_from)) = rook_model {
+            self.bind_model(rook_from, rook_model_index);
+            moved_models.push(PieceMotionUpdate {
+                model_index: rook_model_index,
+                destination_world_position: self.square_to_world(rook_from),
+            });
+        }
+
+        let pocket_model = self
+            .pocketed_piece(&record)
+            .and_then(|pocketed| self.pop_pocket_model(pocketed));
+        let captured = match (record.captured, pocket_model) {
+            (Some((square, piece)), Some(model_index)) => {
+                self.bind_model(square, model_index);
+                moved_models.push(PieceMotionUpdate {
+                    model_index,
+                    destination_world_position: self.square_to_world(square),
+                });
+                recolored_models.push(PromotedModelUpdate { model_index, piece });
+                None
+            }
+            (captured, _) => captured.map(|(square, piece)| (square, piece, removed.captured)),
+        };
+        let exploded = record.exploded_pieces().map(|(square, piece)| {
+            let model_index = removed
+                .exploded
+                .iter()
+                .find(|(exploded_square, _)| *exploded_square == square)
+                .map(|(_, model_index)| *model_index);
+            (square, piece, model_index)
+        });
+        for (square, piece, model_index) in captured.into_iter().chain(exploded) {
+            if let Some(model_index) = model_index {
+                self.bind_model(square, model_index);
+            }
+            restored_models.push(RestoredModelUpdate {
+                model_index,
+                square,
+                piece,
+            });
+        }
+
+        // A promoting Atomic capture blew up the pawn before its model was
+        // swapped, so there is nothing to swap back.
+        let promoted_model = record
+            .mv
+            .promotion
+            .zip(moving_model_index)
+            .filter(|_| record.explosion.is_none())
+            .map(|(_, model_index)| PromotedModelUpdate {
+                model_index,
+                piece: record.piece,
+            });
+
+        Some(ModelMoveUpdate {
+            moved_models,
+            promoted_model,
+            recolored_models,
+            restored_models,
+        })
+    }
+
+    /// Replays the last undone move like `play_move`, queueing its events,
+    /// but leaves the clock alone. Returns whether there was one to redo.
+    pub fn redo_move(&mut self) -> bool {
+        let Some(mv) = self.game_state.next_redo_move() else {
+            return false;
+        };
+        let Some(events) = self.game_state.redo_move() else {
+            return false;
+        };
+        self.pending_promotion = None;
+        self.clear_selection();
+        self.queue_move_events(mv, events);
+        true
+    }
+
+    pub fn bind_model(&mut self, square: Square, model_index: usize) {
+        self.model_by_square.insert(square, model_index);
+        self.square_by_model.insert(model_index, square);
+    }
+
+    /// Restores the history of a loaded PGN game; `game_state` is expected to
+    /// already hold the final position of `game`.
+    pub fn restore_pgn_history(&mut self, game: &PgnGame) -> Result<(), PgnError> {
+        let (_, notation) = game.replay()?;
+        self.initial_state = game.initial_state.clone();
+        self.move_notation = notation;
+        self.captured_model_history = vec![RemovedModels::default(); game.moves.len()];
+        self.pgn_tags = game.tags.clone();
+        Ok(())
+    }
+
+    pub fn to_pgn_game(&self) -> PgnGame {
+        let mut game = PgnGame::new(self.initial_state.clone());
+        for (name, value) in &self.pgn_tags {
+            game.set_tag(name, value);
+        }
+        game.moves = self
+            .game_state
+            .move_history()
+            .iter()
+            .map(|record| record.mv)
+            .collect();
+        game.result = GameResult::from_outcome(self.game_outcome);
+        game
+    }
+
     pub fn clear_selection(&mut self) {
         self.selected_square = None;
+        self.selected_drop = None;
+    }
+
+    /// Overlay marks for the current position: every attacked square gets
+    /// a control tint, then hanging and pinned pieces and the squares of
+    /// their pin lines get their own marks.
+    pub fn threat_marks(&self) -> Vec<(Square, ThreatMark)> {
+        let state = &self.game_state;
+        let white = state.control_counts(Color::White);
+        let black = state.control_counts(Color::Black);
+        let mut marks = (0..64)
+            .filter_map(|index| {
+                let mark = match white[index].cmp(&black[index]) {
+                    Ordering::Greater => ThreatMark::WhiteControl,
+                    Ordering::Less => ThreatMark::BlackControl,
+                    Ordering::Equal if white[index] > 0 => ThreatMark::Contested,
+                    Ordering::Equal => return None,
+                };
+                Some((Square::from_index(index)?, mark))
+            })
+            .collect::<Vec<_>>();
+
+        for color in [Color::White, Color::Black] {
+            for square in state.hanging_pieces(color) {
+                marks.push((square, ThreatMark::Hanging));
+            }
+            for pin in state.pins(color) {
+                marks.push((pin.pinned, ThreatMark::Pinned));
+                marks.extend(
+                    pin.line
+                        .into_iter()
+                        .filter(|square| *square != pin.pinned)
+                        .map(|square| (square, ThreatMark::PinLine)),
+                );
+            }
+        }
+        marks
+    }
+
+    /// Whether `mv` starts from the current selection: the selected square,
+    /// or a drop of the selected pocket piece.
+    pub fn is_selected_move(&self, mv: Move) -> bool {
+        match (self.selected_drop, mv.drop) {
+            (Some(selected), Some(dropped)) => selected == dropped,
+            (None, None) => self.selected_square == Some(mv.from),
+            _ => false,
+        }
     }
 
     pub fn is_highlight_model(&self, model_index: usize) -> bool {
-        self.highlight_model_indices.contains(&model_index)
+        self.highlight_models
+            .values()
+            .any(|models| models.contains(&model_index))
+    }
+
+    pub fn promotion_choice_model(&self, piece: Piece) -> Option<usize> {
+        self.promotion_choice_models
+            .iter()
+            .find(|choice| choice.piece == piece)
+            .map(|choice| choice.model_index)
+    }
+
+    pub fn try_pick_promotion_model(&self, model_index: usize) -> Option<Move> {
+        let pending = self.pending_promotion?;
+        let color = self.game_state.piece_at(pending.from)?.color;
+        let choice = self
+            .promotion_choice_models
+            .iter()
+            .find(|choice| choice.model_index == model_index && choice.piece.color == color)?;
+        Some(Move::with_promotion(
+            pending.from,
+            pending.to,
+            choice.piece.piece_type,
+        ))
+    }
+
+    /// World position of the `slot`-th promotion choice, lined up outside the
+    /// board edge behind the promotion square.
+    pub fn promotion_choice_world(&self, to: Square, slot: usize) -> Vec3 {
+        let square_depth = (self.board_max.z - self.board_min.z) / 8.0;
+        let base = self.square_to_world(to);
+        let offset = (slot as f32 + 1.0) * square_depth;
+        let z = if to.rank() == 7 {
+            base.z + offset
+        } else {
+            base.z - offset
+        };
+        Vec3::new(base.x, base.y, z)
     }
 
     pub fn try_select_piece_model(&mut self, model_index: usize) -> Option<Square> {
         let square = self.square_by_model.get(&model_index).copied()?;
         let piece = self.game_state.piece_at(square)?;
-        if piece.color == self.game_state.side_to_move() {
+        if piece.color == self.game_state.side_to_move() && !self.is_castling_target(square) {
             self.selected_square = Some(square);
+            self.selected_drop = None;
             Some(square)
         } else {
             None
         }
     }
 
+    /// Selects the side to move's pocket piece behind `model_index` for a
+    /// drop.
+    pub fn try_select_pocket_model(&mut self, model_index: usize) -> Option<PieceType> {
+        let pocket_model = self
+            .pocket_models
+            .iter()
+            .find(|pocket_model| pocket_model.model_index == model_index)?;
+        if pocket_model.piece.color != self.game_state.side_to_move() {
+            return None;
+        }
+        self.selected_square = None;
+        self.selected_drop = Some(pocket_model.piece.piece_type);
+        self.selected_drop
+    }
+
     pub fn try_build_click_move(
         &self,
         model_index: usize,
         hit_point: Option<Vec3>,
     ) -> Option<Move> {
+        if let Some(piece_type) = self.selected_drop {
+            if model_index != self.board_model_index {
+                return None;
+            }
+            let to = self.world_to_square(hit_point?)?;
+            return Some(Move::drop(piece_type, to));
+        }
+        let from = self.selected_square?;
+        if let Some(square) = self.square_by_model.get(&model_index).copied() {
+            return self
+                .is_castling_target(square)
+                .then(|| Move::new(from, square));
+        }
         if model_index != self.board_model_index {
             return None;
         }
 
-        let from = self.selected_square?;
         let point = hit_point?;
         let to = self.world_to_square(point)?;
-        Some(Move { from, to })
+        Some(Move::new(from, to))
     }
 
-    pub fn apply_mapping_after_move(
-        &mut self,
-        from: Square,
-        to: Square,
-    ) -> Option<ModelMoveUpdate> {
-        let moving_model_index = self.model_by_square.remove(&from)?;
-        self.square_by_model.remove(&moving_model_index);
+    /// Chess960 castling is played by moving the king onto its own rook, so
+    /// clicking that rook must not select it instead.
+    fn is_castling_target(&self, square: Square) -> bool {
+        let own_piece = self
+            .game_state
+            .piece_at(square)
+            .is_some_and(|piece| piece.color == self.game_state.side_to_move());
+        self.selected_square.is_some_and(|from| {
+            own_piece
+                && self.game_state.is_chess960()
+                && self.game_state.legal_moves_from(from).contains(&square)
+        })
+    }
 
-        let mut captured_model_index = self.model_by_square.remove(&to);
+    /// Rebinds the models for `mv`, which must be the last move played on
+    /// `game_state`, and notes the models it captured for `captured_model`.
+    fn apply_mapping_after_move(&mut self, mv: Move) -> Option<()> {
+        let record = self
+            .game_state
+            .move_history()
+            .last()
+            .copied()
+            .filter(|record| record.mv == mv)?;
+        let to = record.destination();
+        self.captured_models.clear();
+        if mv.drop.is_some() {
+            let model_index = self.pop_pocket_model(record.piece)?;
+            self.bind_model(to, model_index);
+            self.captured_model_history.push(RemovedModels::default());
+            return Some(());
+        }
+        let moving_model_index = self.model_by_square.remove(&mv.from)?;
+        self.square_by_model.remove(&moving_model_index);
 
-        if captured_model_index.is_none()
-            && from.file() != to.file()
-            && self
-                .game_state
-                .piece_at(to)
-                .is_some_and(|piece| piece.piece_type == PieceType::Pawn)
-            && let Some(en_passant_captured_square) = Square::new(to.file(), from.rank())
-        {
-            captured_model_index = self.model_by_square.remove(&en_passant_captured_square);
+        let captured_model_index = record
+            .captured
+            .and_then(|(square, _)| self.unbind_square(square));
+        let mut removed = RemovedModels::default();
+        match (captured_model_index, self.pocketed_piece(&record)) {
+            (Some(model_index), Some(piece)) => {
+                let pocket_position = self.push_pocket_model(model_index, piece);
+                if let Some((square, captured)) = record.captured {
+                    self.captured_models.push(CapturedModel {
+                        square,
+                        piece: captured,
+                        model_index,
+                        pocketed: Some((piece, pocket_position)),
+                    });
+                }
+            }
+            (captured, _) => removed.captured = captured,
         }
+        if let (Some((square, piece)), Some(model_index)) = (record.captured, removed.captured) {
+            self.captured_models.push(CapturedModel {
+                square,
+                piece,
+                model_index,
+                pocketed: None,
+            });
+        }
+        for (square, piece) in record.exploded_pieces() {
+            if let Some(model_index) = self.unbind_square(square) {
+                removed.exploded.push((square, model_index));
+                self.captured_models.push(CapturedModel {
+                    square,
+                    piece,
+                    model_index,
+                    pocketed: None,
+                });
+            }
+        }
+
+        let rook_model = record
+            .castling_rook_squares()
+            .and_then(|(rook_from, rook_to)| {
+                let rook_model_index = self.model_by_square.remove(&rook_from)?;
+                Some((rook_model_index, rook_to))
+            });
 
-        if let Some(captured) = captured_model_index {
-            self.square_by_model.remove(&captured);
+        if record.explosion.is_some() {
+            removed.capturer = Some(moving_model_index);
+            self.captured_models.push(CapturedModel {
+                square: to,
+                piece: record.piece,
+                model_index: moving_model_index,
+                pocketed: None,
+            });
+        } else {
+            self.bind_model(to, moving_model_index);
         }
 
-        let mut moved_models = vec![PieceMotionUpdate {
-            model_index: moving_model_index,
-            destination_world_position: self.square_to_world(to),
-        }];
+        if let Some((rook_model_index, rook_to)) = rook_model {
+            self.bind_model(rook_to, rook_model_index);
+        }
+        self.captured_model_history.push(removed);
+        Some(())
+    }
 
-        self.model_by_square.insert(to, moving_model_index);
-        self.square_by_model.insert(moving_model_index, to);
+    /// The model of `piece`, captured on `square` by the last move played.
+    pub fn captured_model(&self, square: Square, piece: Piece) -> Option<CapturedModel> {
+        self.captured_models
+            .iter()
+            .find(|captured| captured.square == square && captured.piece == piece)
+            .copied()
+    }
 
-        let is_castling_move = self
-            .game_state
-            .piece_at(to)
-            .is_some_and(|piece| piece.piece_type == PieceType::King)
-            && (to.file() as i8 - from.file() as i8).unsigned_abs() == 2;
-
-        if is_castling_move {
-            let rank = from.rank();
-            let (rook_from, rook_to) = if to.file() > from.file() {
-                (
-                    Square::new(7, rank).expect("valid castling rook source"),
-                    Square::new(5, rank).expect("valid castling rook destination"),
-                )
-            } else {
-                (
-                    Square::new(0, rank).expect("valid castling rook source"),
-                    Square::new(3, rank).expect("valid castling rook destination"),
-                )
-            };
-
-            if let Some(rook_model_index) = self.model_by_square.remove(&rook_from) {
-                self.square_by_model.remove(&rook_model_index);
-                self.model_by_square.insert(rook_to, rook_model_index);
-                self.square_by_model.insert(rook_model_index, rook_to);
-                moved_models.push(PieceMotionUpdate {
-                    model_index: rook_model_index,
-                    destination_world_position: self.square_to_world(rook_to),
-                });
-            }
+    /// The piece a Crazyhouse capture in `record` put into the mover's
+    /// pocket, already in the mover's color.
+    fn pocketed_piece(&self, record: &MoveRecord) -> Option<Piece> {
+        if self.game_state.variant() != Variant::Crazyhouse {
+            return None;
         }
+        record.pocketed_piece()
+    }
 
-        Some(ModelMoveUpdate {
-            moved_models,
-            captured_model_index,
-        })
+    /// Adds `model_index` to the pocket as `piece` and returns where it sits.
+    pub fn push_pocket_model(&mut self, model_index: usize, piece: Piece) -> Vec3 {
+        let slot = self
+            .pocket_models
+            .iter()
+            .filter(|pocket_model| pocket_model.piece == piece)
+            .count();
+        self.pocket_models.push(PocketModel { model_index, piece });
+        self.pocket_world(piece, slot)
+    }
+
+    /// Takes the most recently pocketed model of `piece` out of the pocket.
+    fn pop_pocket_model(&mut self, piece: Piece) -> Option<usize> {
+        let position = self
+            .pocket_models
+            .iter()
+            .rposition(|pocket_model| pocket_model.piece == piece)?;
+        Some(self.pocket_models.remove(position).model_index)
+    }
+
+    /// World position of the `slot`-th pocketed `piece`. White's pocket runs
+    /// alongside the h-file and Black's alongside the a-file, one row per
+    /// piece type counted from the owner's side, with extra pieces of a
+    /// type stacked outward.
+    pub fn pocket_world(&self, piece: Piece, slot: usize) -> Vec3 {
+        let square_width = (self.board_max.x - self.board_min.x) / 8.0;
+        let square_depth = (self.board_max.z - self.board_min.z) / 8.0;
+        let offset = (1.0 + slot as f32 * 0.5) * square_width;
+        let row = piece_index(piece.piece_type) as f32;
+        let (x, rank) = match piece.color {
+            Color::White => (self.board_max.x + offset, row),
+            Color::Black => (self.board_min.x - offset, 7.0 - row),
+        };
+        let z = self.board_min.z + (rank + 0.5) * square_depth;
+        Vec3::new(x, self.board_max.y + 0.01, z)
+    }
+
+    fn unbind_square(&mut self, square: Square) -> Option<usize> {
+        let model_index = self.model_by_square.remove(&square)?;
+        self.square_by_model.remove(&model_index);
+        Some(model_index)
     }
 
     pub fn square_to_world(&self, square: Square) -> Vec3 {
@@ -190,3 +976,74 @@ pub fn parse_piece_template_name(name: &str) -> Option<(PieceType, Color)> {
 
     Some((piece_type, color))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// A scene with one model per piece, numbered in board order.
+    fn scene(fen: &str) -> ChessSceneState {
+        let game_state = GameState::from_fen(fen).expect("valid FEN");
+        let mut model_by_square = HashMap::new();
+        let mut square_by_model = HashMap::new();
+        for (model_index, (square, _)) in game_state.iter_pieces().enumerate() {
+            model_by_square.insert(square, model_index + 1);
+            square_by_model.insert(model_index + 1, square);
+        }
+        ChessSceneState::new(
+            game_state,
+            0,
+            Vec3::ZERO,
+            Vec3::new(8.0, 0.0, 8.0),
+            model_by_square,
+            square_by_model,
+        )
+    }
+
+    fn square(name: &str) -> Square {
+        name.parse().expect("square")
+    }
+
+    fn assert_castle_round_trip(fen: &str, castle: &str, rook_from: &str, rook_to: &str) {
+        let mut scene = scene(fen);
+        let models_before = scene.model_by_square.clone();
+        let mv = scene.game_state.parse_move(castle).expect("legal castle");
+        let king_to = if mv.to.file() > mv.from.file() {
+            "g1"
+        } else {
+            "c1"
+        };
+        let king_model = models_before[&mv.from];
+        let rook_model = models_before[&square(rook_from)];
+
+        scene.play_move(mv, Instant::now()).expect("legal castle");
+        assert_eq!(scene.model_by_square[&square(king_to)], king_model);
+        assert_eq!(scene.model_by_square[&square(rook_to)], rook_model);
+
+        let update = scene.undo_move().expect("a move to undo");
+        assert_eq!(scene.model_by_square, models_before);
+        for (square, model_index) in &models_before {
+            assert_eq!(scene.square_by_model[model_index], *square);
+        }
+        assert!(
+            update
+                .moved_models
+                .iter()
+                .any(|moved| moved.model_index == rook_model
+                    && moved.destination_world_position
+                        == scene.square_to_world(square(rook_from)))
+        );
+    }
+
+    #[test]
+    fn undoing_a_castle_onto_the_king_square_keeps_both_models() {
+        assert_castle_round_trip("4k3/8/8/8/8/8/8/5K1R w H - 0 1", "O-O", "h1", "f1");
+        assert_castle_round_trip("4k3/8/8/8/8/8/8/R2K4 w A - 0 1", "O-O-O", "a1", "d1");
+    }
+
+    #[test]
+    fn undoing_a_standard_castle_restores_the_models() {
+        assert_castle_round_trip("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "O-O", "h1", "f1");
+    }
+}
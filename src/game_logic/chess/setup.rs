@@ -0,0 +1,318 @@
+use crate::game_logic::chess::bitboard::{color_index, squares};
+use crate::game_logic::chess::crazyhouse::Pockets;
+use crate::game_logic::chess::fen::checks_given_field;
+use crate::game_logic::chess::state::home_rank;
+use crate::game_logic::chess::{Color, FenError, GameState, Piece, PieceType, Square, Variant};
+
+/// Why an edited position cannot be played.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SetupError {
+    MissingKing(Color),
+    ExtraKings(Color),
+    PawnOnBackRank(Square),
+    SideNotToMoveInCheck(Color),
+    /// A castling right without its king and rook on the home rank.
+    CastlingMismatch {
+        color: Color,
+        kingside: bool,
+    },
+    /// An en passant square with no pawn that could just have double-pushed
+    /// past it.
+    InvalidEnPassant(Square),
+    Fen(FenError),
+}
+
+/// A position being put together square by square in setup mode. Castling
+/// rights are plain flags; like a bare `K`/`Q` in X-FEN, each one goes to
+/// the outermost rook on that side of the king.
+#[derive(Debug, Clone)]
+pub struct BoardSetup {
+    board: [Option<Piece>; 64],
+    pub side_to_move: Color,
+    castling: [[bool; 2]; 2],
+    /// File of the en passant square; its rank follows from the side to
+    /// move.
+    pub en_passant_file: Option<u8>,
+    pub variant: Variant,
+    /// Carried over from the position setup started from; the board editor
+    /// leaves them alone.
+    pockets: Pockets,
+    checks_given: [u8; 2],
+}
+
+impl BoardSetup {
+    /// Starts editing from the pieces, side to move, castling rights and en
+    /// passant square of `state`.
+    pub fn from_state(state: &GameState) -> Self {
+        let mut board = [None; 64];
+        for (square, piece) in state.iter_pieces() {
+            board[square.to_index()] = Some(piece);
+        }
+        let castling = [Color::White, Color::Black].map(|color| {
+            [false, true].map(|kingside| state.castling_rights.rook_file(color, kingside).is_some())
+        });
+        Self {
+            board,
+            side_to_move: state.side_to_move(),
+            castling,
+            en_passant_file: state.en_passant_target.map(Square::file),
+            variant: state.variant(),
+            pockets: state.pockets,
+            checks_given: state.checks_given,
+        }
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.to_index()]
+    }
+
+    pub fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.board[square.to_index()] = piece;
+    }
+
+    pub fn iter_pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.board
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, piece)| Some((Square::from_index(idx)?, (*piece)?)))
+    }
+
+    /// Empties the board and drops every castling right and the en passant
+    /// square.
+    pub fn clear(&mut self) {
+        self.board = [None; 64];
+        self.castling = [[false; 2]; 2];
+        self.en_passant_file = None;
+    }
+
+    /// Whether `color` keeps its castling right on one side, for editing.
+    pub fn castling_mut(&mut self, color: Color, kingside: bool) -> &mut bool {
+        &mut self.castling[color_index(color)][usize::from(kingside)]
+    }
+
+    pub fn en_passant_target(&self) -> Option<Square> {
+        let rank = match self.side_to_move {
+            Color::White => 5,
+            Color::Black => 2,
+        };
+        Square::new(self.en_passant_file?, rank)
+    }
+
+    /// The edited position as FEN, with the pocket in Crazyhouse and the
+    /// check counts in Three-check.
+    pub fn to_fen(&self) -> String {
+        let mut position = GameState::empty();
+        position.variant = self.variant;
+        position.pockets = self.pockets;
+        for (square, piece) in self.iter_pieces() {
+            position.set_piece(square, Some(piece));
+        }
+        let placement = position.to_fen();
+        let placement = placement.split_whitespace().next().unwrap_or_default();
+
+        let side_to_move = match self.side_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+        let mut castling = String::new();
+        for (color, symbols) in [(Color::White, ['Q', 'K']), (Color::Black, ['q', 'k'])] {
+            for kingside in [true, false] {
+                if self.castling[color_index(color)][usize::from(kingside)] {
+                    castling.push(symbols[usize::from(kingside)]);
+                }
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        let en_passant = self
+            .en_passant_target()
+            .map_or_else(|| "-".to_owned(), |square| square.to_string());
+
+        let mut fen = format!("{placement} {side_to_move} {castling} {en_passant} 0 1");
+        if self.variant == Variant::ThreeCheck {
+            fen.push_str(&checks_given_field(self.checks_given));
+        }
+        fen
+    }
+
+    /// Builds the edited position and checks that it can be played.
+    pub fn build(&self) -> Result<GameState, SetupError> {
+        let state = GameState::from_fen(&self.to_fen())
+            .map_err(SetupError::Fen)?
+            .with_variant(self.variant);
+        state.validate_position()?;
+        Ok(state)
+    }
+}
+
+impl GameState {
+    /// Rejects positions no game could reach: a side without exactly one
+    /// king, a pawn on the first or last rank, the side that just moved left
+    /// in check, castling rights without their king and rook, or an en
+    /// passant square no pawn just skipped.
+    pub fn validate_position(&self) -> Result<(), SetupError> {
+        for color in [Color::White, Color::Black] {
+            match squares(self.pieces(color, PieceType::King)).count() {
+                0 => return Err(SetupError::MissingKing(color)),
+                1 => {}
+                _ => return Err(SetupError::ExtraKings(color)),
+            }
+        }
+
+        if let Some((square, _)) = self.iter_pieces().find(|(square, piece)| {
+            piece.piece_type == PieceType::Pawn && (square.rank() == 0 || square.rank() == 7)
+        }) {
+            return Err(SetupError::PawnOnBackRank(square));
+        }
+
+        let side_not_to_move = self.side_to_move.opposite();
+        if self.is_in_check(side_not_to_move) {
+            return Err(SetupError::SideNotToMoveInCheck(side_not_to_move));
+        }
+
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                if let Some(rook_file) = self.castling_rights.rook_file(color, kingside)
+                    && !self.castling_matches_placement(color, kingside, rook_file)
+                {
+                    return Err(SetupError::CastlingMismatch { color, kingside });
+                }
+            }
+        }
+
+        if let Some(target) = self.en_passant_target
+            && !self.en_passant_matches_placement(target)
+        {
+            return Err(SetupError::InvalidEnPassant(target));
+        }
+
+        Ok(())
+    }
+
+    fn castling_matches_placement(&self, color: Color, kingside: bool, rook_file: u8) -> bool {
+        let rank = home_rank(color);
+        let piece_on = |file: u8| Square::new(file, rank).and_then(|square| self.piece_at(square));
+        let Some(king_file) = (0..8).find(|file| {
+            piece_on(*file)
+                == Some(Piece {
+                    piece_type: PieceType::King,
+                    color,
+                })
+        }) else {
+            return false;
+        };
+
+        piece_on(rook_file)
+            == Some(Piece {
+                piece_type: PieceType::Rook,
+                color,
+            })
+            && (rook_file > king_file) == kingside
+    }
+
+    /// The pawn that double-pushed past `target` must stand in front of it,
+    /// with `target` and the pawn's start square empty.
+    fn en_passant_matches_placement(&self, target: Square) -> bool {
+        let mover = self.side_to_move.opposite();
+        let (pawn_rank, start_rank) = match mover {
+            Color::White => (3, 1),
+            Color::Black => (4, 6),
+        };
+        let pawn = Square::new(target.file(), pawn_rank).and_then(|square| self.piece_at(square));
+        let start = Square::new(target.file(), start_rank).and_then(|square| self.piece_at(square));
+        pawn == Some(Piece {
+            piece_type: PieceType::Pawn,
+            color: mover,
+        }) && start.is_none()
+            && self.piece_at(target).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_logic::chess::{
+        BoardSetup, Color, GameState, PieceType, SetupError, Square, Variant,
+    };
+
+    fn validate(fen: &str) -> Result<(), SetupError> {
+        GameState::from_fen(fen)
+            .expect("valid FEN")
+            .validate_position()
+    }
+
+    fn square(name: &str) -> Square {
+        name.parse().expect("square")
+    }
+
+    #[test]
+    fn accepts_reachable_positions() {
+        assert_eq!(
+            validate("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_each_unreachable_position() {
+        let cases = [
+            (
+                "8/8/8/8/8/8/8/4K3 w - - 0 1",
+                SetupError::MissingKing(Color::Black),
+            ),
+            (
+                "3kk3/8/8/8/8/8/8/4K3 w - - 0 1",
+                SetupError::ExtraKings(Color::Black),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/P3K3 w - - 0 1",
+                SetupError::PawnOnBackRank(square("a1")),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4R1K1 w - - 0 1",
+                SetupError::SideNotToMoveInCheck(Color::Black),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2R w KQ - 0 1",
+                SetupError::CastlingMismatch {
+                    color: Color::White,
+                    kingside: false,
+                },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - e6 0 1",
+                SetupError::InvalidEnPassant(square("e6")),
+            ),
+        ];
+        for (fen, expected) in cases {
+            assert_eq!(validate(fen), Err(expected), "{fen}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_crazyhouse_pocket() {
+        let state = GameState::from_fen("4k3/8/8/8/8/8/8/4K3[QNp] w - - 0 1")
+            .expect("valid FEN")
+            .with_variant(Variant::Crazyhouse);
+        let setup = BoardSetup::from_state(&state);
+
+        assert_eq!(setup.to_fen(), "4k3/8/8/8/8/8/8/4K3[NQp] w - - 0 1");
+        let built = setup.build().expect("playable");
+        assert_eq!(built.pocket_count(Color::White, PieceType::Queen), 1);
+        assert_eq!(built.pocket_count(Color::Black, PieceType::Pawn), 1);
+    }
+
+    #[test]
+    fn keeps_the_three_check_counts() {
+        let state = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1")
+            .expect("valid FEN")
+            .with_variant(Variant::ThreeCheck);
+        let setup = BoardSetup::from_state(&state);
+
+        assert_eq!(setup.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1");
+        let built = setup.build().expect("playable");
+        assert_eq!(built.checks_remaining(Color::White), 1);
+        assert_eq!(built.checks_remaining(Color::Black), 2);
+        assert_eq!(built.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1");
+    }
+}
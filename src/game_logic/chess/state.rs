@@ -1,19 +1,97 @@
-use crate::game_logic::chess::{Color, Move, MoveError, Piece, PieceType, Square};
+use crate::game_logic::chess::bitboard::{
+    Bitboard, between, bishop_attacks, color_index, first_square, king_attacks, knight_attacks,
+    pawn_attacks, piece_index, rook_attacks, square_bit, squares,
+};
+use crate::game_logic::chess::crazyhouse::Pockets;
+use crate::game_logic::chess::zobrist::{
+    castling_key, checks_key, piece_key, pockets_key, side_key,
+};
+use crate::game_logic::chess::{
+    Color, GameOutcome, Move, MoveError, MoveRecord, Piece, PieceType, Square, Variant,
+};
+
+const STANDARD_BACK_RANK: [PieceType; 8] = [
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Queen,
+    PieceType::King,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Rook,
+];
 
 #[derive(Clone)]
 pub struct GameState {
-    board: [Option<Piece>; 64],
-    side_to_move: Color,
-    en_passant_target: Option<Square>,
-    castling_rights: CastlingRights,
+    pub(super) board: [Option<Piece>; 64],
+    pub(super) color_bitboards: [Bitboard; 2],
+    pub(super) piece_bitboards: [Bitboard; 6],
+    pub(super) side_to_move: Color,
+    pub(super) en_passant_target: Option<Square>,
+    pub(super) castling_rights: CastlingRights,
+    /// Castling is written as the king taking its own rook, since the king
+    /// may start next to (or on) its destination.
+    pub(super) chess960: bool,
+    pub(super) variant: Variant,
+    /// Checks given by White and Black, counted in Three-check only.
+    pub(super) checks_given: [u8; 2],
+    /// Captured pieces each side may drop, used in Crazyhouse only.
+    pub(super) pockets: Pockets,
+    /// Squares holding a promoted piece, which is pocketed as a pawn.
+    pub(super) promoted: Bitboard,
+    pub(super) halfmove_clock: u32,
+    pub(super) fullmove_number: u32,
+    pub(super) zobrist_key: u64,
+    pub(super) position_history: Vec<u64>,
+    pub(super) move_history: Vec<MoveRecord>,
+    pub(super) redo_moves: Vec<Move>,
 }
 
-#[derive(Debug, Copy, Clone)]
-struct CastlingRights {
-    white_kingside: bool,
-    white_queenside: bool,
-    black_kingside: bool,
-    black_queenside: bool,
+/// Start file of the rook for each castling right still held.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub(super) struct CastlingRights {
+    pub(super) white_kingside: Option<u8>,
+    pub(super) white_queenside: Option<u8>,
+    pub(super) black_kingside: Option<u8>,
+    pub(super) black_queenside: Option<u8>,
+}
+
+impl CastlingRights {
+    pub(super) fn rook_file(&self, color: Color, kingside: bool) -> Option<u8> {
+        match (color, kingside) {
+            (Color::White, true) => self.white_kingside,
+            (Color::White, false) => self.white_queenside,
+            (Color::Black, true) => self.black_kingside,
+            (Color::Black, false) => self.black_queenside,
+        }
+    }
+
+    pub(super) fn rook_file_mut(&mut self, color: Color, kingside: bool) -> &mut Option<u8> {
+        match (color, kingside) {
+            (Color::White, true) => &mut self.white_kingside,
+            (Color::White, false) => &mut self.white_queenside,
+            (Color::Black, true) => &mut self.black_kingside,
+            (Color::Black, false) => &mut self.black_queenside,
+        }
+    }
+}
+
+/// Where the king and rook land for one castling move. The king's square
+/// is not always `mv.to`: Chess960 castling targets the rook.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(super) struct CastlingMove {
+    pub(super) kingside: bool,
+    pub(super) king_to: Square,
+    pub(super) rook_from: Square,
+    pub(super) rook_to: Square,
+}
+
+/// Restrictions on the side to move's pieces: a non-king move must land in
+/// `check_mask` and stay on its square's pin line.
+pub(super) struct MoveConstraints {
+    king_square: Option<Square>,
+    pub(super) check_mask: Bitboard,
+    pin_masks: [Bitboard; 64],
 }
 
 impl Default for GameState {
@@ -23,39 +101,61 @@ impl Default for GameState {
 }
 
 impl GameState {
-    pub fn new_start_position() -> Self {
-        let mut game = Self {
+    pub(super) fn empty() -> Self {
+        Self {
             board: [None; 64],
+            color_bitboards: [0; 2],
+            piece_bitboards: [0; 6],
             side_to_move: Color::White,
             en_passant_target: None,
-            castling_rights: CastlingRights {
-                white_kingside: true,
-                white_queenside: true,
-                black_kingside: true,
-                black_queenside: true,
-            },
+            castling_rights: CastlingRights::default(),
+            chess960: false,
+            variant: Variant::Standard,
+            checks_given: [0; 2],
+            pockets: [[0; 5]; 2],
+            promoted: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            zobrist_key: 0,
+            position_history: Vec::new(),
+            move_history: Vec::new(),
+            redo_moves: Vec::new(),
+        }
+    }
+
+    pub fn new_start_position() -> Self {
+        Self::with_back_rank(STANDARD_BACK_RANK)
+    }
+
+    /// Pieces on both back ranks as in `setup`, pawns in front, all four
+    /// castling rights held by the outer rooks.
+    pub(super) fn with_back_rank(setup: [PieceType; 8]) -> Self {
+        let mut game = Self::empty();
+        let rook_files = setup
+            .iter()
+            .enumerate()
+            .filter(|(_, piece_type)| **piece_type == PieceType::Rook)
+            .map(|(file, _)| file as u8)
+            .collect::<Vec<_>>();
+        let (queenside, kingside) = (rook_files.first().copied(), rook_files.last().copied());
+        game.castling_rights = CastlingRights {
+            white_kingside: kingside,
+            white_queenside: queenside,
+            black_kingside: kingside,
+            black_queenside: queenside,
         };
 
-        game.place_back_rank(Color::White, 0);
+        game.place_back_rank(Color::White, 0, setup);
         game.place_pawns(Color::White, 1);
-        game.place_back_rank(Color::Black, 7);
+        game.place_back_rank(Color::Black, 7, setup);
         game.place_pawns(Color::Black, 6);
+        game.zobrist_key = game.compute_zobrist_key();
+        game.record_position();
 
         game
     }
 
-    fn place_back_rank(&mut self, color: Color, rank: u8) {
-        let setup = [
-            PieceType::Rook,
-            PieceType::Knight,
-            PieceType::Bishop,
-            PieceType::Queen,
-            PieceType::King,
-            PieceType::Bishop,
-            PieceType::Knight,
-            PieceType::Rook,
-        ];
-
+    fn place_back_rank(&mut self, color: Color, rank: u8, setup: [PieceType; 8]) {
         for (file, piece_type) in setup.into_iter().enumerate() {
             let square = Square::new(file as u8, rank).expect("valid back rank square");
             self.set_piece(square, Some(Piece { piece_type, color }));
@@ -79,10 +179,33 @@ impl GameState {
         self.side_to_move
     }
 
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
     pub fn piece_at(&self, square: Square) -> Option<Piece> {
         self.board[square.to_index()]
     }
 
+    /// The piece `mv` moves, taken from the pocket for a drop.
+    pub(super) fn moving_piece(&self, mv: Move) -> Option<Piece> {
+        match mv.drop {
+            Some(piece_type) => Some(Piece {
+                piece_type,
+                color: self.side_to_move,
+            }),
+            None => self.piece_at(mv.from),
+        }
+    }
+
     pub fn iter_pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
         self.board
             .iter()
@@ -92,28 +215,71 @@ impl GameState {
     }
 
     pub fn apply_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        if let Some(piece_type) = mv.drop {
+            return self.apply_drop(mv, piece_type);
+        }
         let piece = self.piece_at(mv.from).ok_or(MoveError::NoPieceAtSource)?;
 
         if piece.color != self.side_to_move {
             return Err(MoveError::WrongTurn);
         }
 
-        if let Some(target) = self.piece_at(mv.to)
+        let castling = self.castling_move(mv, piece);
+        if castling.is_none()
+            && let Some(target) = self.piece_at(mv.to)
             && target.color == piece.color
         {
             return Err(MoveError::DestinationOccupiedByOwnPiece);
         }
 
-        if !self.is_legal_piece_move(piece, mv.from, mv.to) {
+        if self.variant == Variant::Atomic
+            && piece.piece_type == PieceType::King
+            && self.captured_by(mv, piece).is_some()
+        {
+            return Err(MoveError::KingCannotCapture);
+        }
+
+        let destination = square_bit(mv.to);
+        if self.pseudo_legal_targets(mv.from, piece) & destination == 0 {
             return Err(MoveError::IllegalPieceMovement);
         }
 
-        if self.would_leave_king_in_check(mv, piece.color) {
+        match (self.is_promotion_move(mv.from, mv.to), mv.promotion) {
+            (true, None) => return Err(MoveError::PromotionRequired),
+            (true, Some(promotion)) if !promotion.is_promotion_choice() => {
+                return Err(MoveError::InvalidPromotion);
+            }
+            (false, Some(_)) => return Err(MoveError::InvalidPromotion),
+            _ => {}
+        }
+
+        let constraints = self.move_constraints(piece.color);
+        let explosion = self.explosion_for(mv, piece);
+        if self.legal_targets(mv.from, piece, &constraints) & destination == 0 {
+            let own_king = self.pieces(piece.color, PieceType::King);
+            if explosion.is_some() && self.blast_radius(mv.to) & own_king != 0 {
+                return Err(MoveError::ExplodesOwnKing);
+            }
             return Err(MoveError::KingWouldBeInCheck);
         }
 
+        let record = MoveRecord {
+            mv,
+            piece,
+            captured: self.captured_by(mv, piece),
+            castling,
+            explosion,
+            previous_castling_rights: self.castling_rights,
+            previous_checks_given: self.checks_given,
+            previous_pockets: self.pockets,
+            previous_promoted: self.promoted,
+            previous_en_passant_target: self.en_passant_target,
+            previous_halfmove_clock: self.halfmove_clock,
+        };
         self.apply_move_unchecked(mv, piece);
-        self.side_to_move = self.side_to_move.opposite();
+        self.move_history.push(record);
+        self.redo_moves.clear();
+        self.record_position();
         Ok(())
     }
 
@@ -126,10 +292,55 @@ impl GameState {
             return Vec::new();
         }
 
-        self.legal_moves_for_piece(from, piece)
+        let constraints = self.move_constraints(piece.color);
+        squares(self.legal_targets(from, piece, &constraints)).collect()
+    }
+
+    /// Every legal move for the side to move, with one entry per promotion
+    /// choice and, in Crazyhouse, every drop.
+    pub(super) fn generate_legal_moves(&self) -> Vec<Move> {
+        let color = self.side_to_move;
+        let constraints = self.move_constraints(color);
+        let mut moves = Vec::new();
+
+        for from in squares(self.color_bitboard(color)) {
+            let Some(piece) = self.piece_at(from) else {
+                continue;
+            };
+
+            for to in squares(self.legal_targets(from, piece, &constraints)) {
+                if self.is_promotion_move(from, to) {
+                    moves.extend(
+                        PieceType::PROMOTION_CHOICES
+                            .iter()
+                            .map(|promotion| Move::with_promotion(from, to, *promotion)),
+                    );
+                } else {
+                    moves.push(Move::new(from, to));
+                }
+            }
+        }
+
+        moves.extend(self.legal_drops(&constraints));
+        moves
+    }
+
+    pub fn is_promotion_move(&self, from: Square, to: Square) -> bool {
+        let Some(piece) = self.piece_at(from) else {
+            return false;
+        };
+
+        let last_rank = match piece.color {
+            Color::White => 7,
+            Color::Black => 0,
+        };
+        piece.piece_type == PieceType::Pawn && to.rank() == last_rank
     }
 
     pub fn is_in_check(&self, color: Color) -> bool {
+        if self.variant == Variant::Atomic && self.kings_touch() {
+            return false;
+        }
         let Some(king_square) = self.find_king_square(color) else {
             return false;
         };
@@ -152,69 +363,321 @@ impl GameState {
         !self.has_any_legal_move(color)
     }
 
-    fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+    /// How the game ends in this position without anyone claiming or
+    /// agreeing to anything: a variant win, checkmate, stalemate or an
+    /// automatic draw.
+    pub fn game_outcome(&self) -> Option<GameOutcome> {
+        let side_to_move = self.side_to_move;
+        if let Some(outcome) = self.variant_outcome() {
+            Some(outcome)
+        } else if self.is_checkmate(side_to_move) {
+            Some(GameOutcome::Checkmate {
+                winner: side_to_move.opposite(),
+            })
+        } else if self.is_stalemate(side_to_move) {
+            Some(GameOutcome::Stalemate)
+        } else {
+            self.automatic_draw()
+        }
+    }
+
+    pub(super) fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        let bit = square_bit(square);
+        if let Some(previous) = self.board[square.to_index()] {
+            self.color_bitboards[color_index(previous.color)] &= !bit;
+            self.piece_bitboards[piece_index(previous.piece_type)] &= !bit;
+            self.zobrist_key ^= piece_key(square, previous);
+        }
+        if let Some(piece) = piece {
+            self.color_bitboards[color_index(piece.color)] |= bit;
+            self.piece_bitboards[piece_index(piece.piece_type)] |= bit;
+            self.zobrist_key ^= piece_key(square, piece);
+        }
         self.board[square.to_index()] = piece;
     }
 
+    pub(super) fn occupied(&self) -> Bitboard {
+        self.color_bitboards[0] | self.color_bitboards[1]
+    }
+
+    pub(super) fn color_bitboard(&self, color: Color) -> Bitboard {
+        self.color_bitboards[color_index(color)]
+    }
+
+    pub(super) fn pieces(&self, color: Color, piece_type: PieceType) -> Bitboard {
+        self.color_bitboard(color) & self.piece_bitboards[piece_index(piece_type)]
+    }
+
+    /// Pieces of `attacker_color` that attack `target` when the board holds
+    /// exactly the pieces in `occupied`.
+    pub(super) fn attackers_to(
+        &self,
+        target: Square,
+        attacker_color: Color,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        let queens = self.pieces(attacker_color, PieceType::Queen);
+        let diagonal_sliders = self.pieces(attacker_color, PieceType::Bishop) | queens;
+        let straight_sliders = self.pieces(attacker_color, PieceType::Rook) | queens;
+
+        (pawn_attacks(attacker_color.opposite(), target)
+            & self.pieces(attacker_color, PieceType::Pawn))
+            | (knight_attacks(target) & self.pieces(attacker_color, PieceType::Knight))
+            | (king_attacks(target) & self.pieces(attacker_color, PieceType::King))
+            | (bishop_attacks(target, occupied) & diagonal_sliders)
+            | (rook_attacks(target, occupied) & straight_sliders)
+    }
+
     fn has_any_legal_move(&self, color: Color) -> bool {
-        for (from, piece) in self.iter_pieces() {
-            if piece.color != color {
-                continue;
-            }
+        let constraints = self.move_constraints(color);
+        squares(self.color_bitboard(color)).any(|from| {
+            self.piece_at(from)
+                .is_some_and(|piece| self.legal_targets(from, piece, &constraints) != 0)
+        }) || (color == self.side_to_move && !self.legal_drops(&constraints).is_empty())
+    }
 
-            if self.legal_moves_for_piece(from, piece).is_empty() {
-                continue;
-            }
+    /// Checkers, the squares that resolve a check and the lines pinned
+    /// pieces may still move along, for the king of `color`.
+    pub(super) fn move_constraints(&self, color: Color) -> MoveConstraints {
+        let mut constraints = MoveConstraints {
+            king_square: self.find_king_square(color),
+            check_mask: !0,
+            pin_masks: [!0; 64],
+        };
+        let Some(king_square) = constraints.king_square else {
+            return constraints;
+        };
 
-            return true;
+        let opponent = color.opposite();
+        let occupied = self.occupied();
+        let checkers = self.attackers_to(king_square, opponent, occupied);
+        constraints.check_mask = match first_square(checkers) {
+            None => !0,
+            Some(checker) if checkers.count_ones() == 1 => checkers | between(king_square, checker),
+            Some(_) => 0,
+        };
+
+        for (pinned, _, line) in self.pin_lines(color) {
+            constraints.pin_masks[pinned.to_index()] = line;
         }
 
-        false
+        constraints
     }
 
-    fn legal_moves_for_piece(&self, from: Square, piece: Piece) -> Vec<Square> {
-        let mut legal_moves = Vec::new();
+    fn legal_targets(&self, from: Square, piece: Piece, constraints: &MoveConstraints) -> Bitboard {
+        if self.variant == Variant::Atomic {
+            return self.atomic_legal_targets(from, piece);
+        }
 
-        for to_index in 0..64 {
-            let Some(to) = Square::from_index(to_index) else {
-                continue;
-            };
+        let pseudo_legal = self.pseudo_legal_targets(from, piece);
 
-            if from == to {
-                continue;
-            }
+        if piece.piece_type == PieceType::King {
+            let opponent = piece.color.opposite();
+            let occupied = self.occupied() & !square_bit(from);
+            let castling = self.castling_targets(piece.color, from);
+            return squares(pseudo_legal & !castling)
+                .filter(|to| self.attackers_to(*to, opponent, occupied) == 0)
+                .fold(castling, |targets, to| targets | square_bit(to));
+        }
+
+        let en_passant = pseudo_legal & self.en_passant_capture(from, piece);
+        let mut targets = pseudo_legal
+            & !en_passant
+            & constraints.check_mask
+            & constraints.pin_masks[from.to_index()];
+        if let Some(to) = first_square(en_passant)
+            && self.is_legal_en_passant(from, to, piece.color, constraints.king_square)
+        {
+            targets |= en_passant;
+        }
+        targets
+    }
 
-            if self
-                .piece_at(to)
-                .is_some_and(|target| target.color == piece.color)
-            {
-                continue;
-            }
+    /// Destinations reachable under the movement rules of `piece`, ignoring
+    /// whether the move leaves its own king in check. Castling is included
+    /// only when it is fully legal, and in Chess960 its target is the
+    /// king's own rook.
+    pub(super) fn pseudo_legal_targets(&self, from: Square, piece: Piece) -> Bitboard {
+        let own = self.color_bitboard(piece.color);
+        let occupied = self.occupied();
 
-            if !self.is_legal_piece_move(piece, from, to) {
-                continue;
+        let targets = match piece.piece_type {
+            PieceType::Pawn => {
+                let empty = !occupied;
+                let (single_push, start_rank) = match piece.color {
+                    Color::White => ((square_bit(from) << 8) & empty, 1),
+                    Color::Black => ((square_bit(from) >> 8) & empty, 6),
+                };
+                let double_push = match piece.color {
+                    Color::White if from.rank() == start_rank => (single_push << 8) & empty,
+                    Color::Black if from.rank() == start_rank => (single_push >> 8) & empty,
+                    _ => 0,
+                };
+                let captures =
+                    pawn_attacks(piece.color, from) & self.color_bitboard(piece.color.opposite());
+                single_push | double_push | captures | self.en_passant_capture(from, piece)
             }
-
-            if !self.would_leave_king_in_check(Move { from, to }, piece.color) {
-                legal_moves.push(to);
+            PieceType::Knight => knight_attacks(from),
+            PieceType::Bishop => bishop_attacks(from, occupied),
+            PieceType::Rook => rook_attacks(from, occupied),
+            PieceType::Queen => bishop_attacks(from, occupied) | rook_attacks(from, occupied),
+            PieceType::King => {
+                let castling = self.castling_targets(piece.color, from);
+                return (king_attacks(from) & !own) | castling;
             }
+        };
+
+        targets & !own
+    }
+
+    fn en_passant_capture(&self, from: Square, piece: Piece) -> Bitboard {
+        let Some(target) = self.en_passant_target else {
+            return 0;
+        };
+        if piece.piece_type != PieceType::Pawn
+            || piece.color != self.side_to_move
+            || pawn_attacks(piece.color, from) & square_bit(target) == 0
+            || self.piece_at(target).is_some()
+        {
+            return 0;
         }
 
-        legal_moves
+        let captured_pawn = Square::new(target.file(), from.rank())
+            .and_then(|square| self.piece_at(square))
+            .is_some_and(|captured| {
+                captured.piece_type == PieceType::Pawn && captured.color != piece.color
+            });
+        if captured_pawn { square_bit(target) } else { 0 }
     }
 
-    fn would_leave_king_in_check(&self, mv: Move, color: Color) -> bool {
-        let mut next = self.clone();
-        let Some(piece) = next.piece_at(mv.from) else {
+    /// En passant removes two pieces from one line, so it is checked against
+    /// the board after the capture rather than against the pin masks.
+    fn is_legal_en_passant(
+        &self,
+        from: Square,
+        to: Square,
+        color: Color,
+        king_square: Option<Square>,
+    ) -> bool {
+        let Some(king_square) = king_square else {
             return true;
         };
+        let Some(captured_square) = Square::new(to.file(), from.rank()) else {
+            return false;
+        };
 
-        next.apply_move_unchecked(mv, piece);
-        next.is_in_check(color)
+        let captured = square_bit(captured_square);
+        let occupied = (self.occupied() & !square_bit(from) & !captured) | square_bit(to);
+        self.attackers_to(king_square, color.opposite(), occupied) & !captured == 0
     }
 
-    fn apply_move_unchecked(&mut self, mv: Move, piece: Piece) {
-        let captured_on_destination = self.piece_at(mv.to);
+    fn castling_targets(&self, color: Color, from: Square) -> Bitboard {
+        [true, false]
+            .into_iter()
+            .filter_map(|kingside| self.castling_for(color, kingside))
+            .filter(|castling| self.is_legal_castling(color, from, *castling))
+            .fold(0, |targets, castling| {
+                let target = if self.chess960 {
+                    castling.rook_from
+                } else {
+                    castling.king_to
+                };
+                targets | square_bit(target)
+            })
+    }
+
+    /// King and rook squares for castling on one side, if `color` still has
+    /// that right. The king and rook always end on the g/f or c/d files.
+    pub(super) fn castling_for(&self, color: Color, kingside: bool) -> Option<CastlingMove> {
+        let rank = home_rank(color);
+        let rook_file = self.castling_rights.rook_file(color, kingside)?;
+        let (king_file, rook_to_file) = if kingside { (6, 5) } else { (2, 3) };
+        Some(CastlingMove {
+            kingside,
+            king_to: Square::new(king_file, rank)?,
+            rook_from: Square::new(rook_file, rank)?,
+            rook_to: Square::new(rook_to_file, rank)?,
+        })
+    }
+
+    /// Reads `mv` as castling if it is written that way for this game: the
+    /// king taking its own castling rook in Chess960, or a two-file king
+    /// step otherwise. Legality is not checked.
+    pub(super) fn castling_move(&self, mv: Move, piece: Piece) -> Option<CastlingMove> {
+        if piece.piece_type != PieceType::King
+            || mv.from.rank() != home_rank(piece.color)
+            || mv.to.rank() != mv.from.rank()
+        {
+            return None;
+        }
+
+        let kingside = mv.to.file() > mv.from.file();
+        let castling = self.castling_for(piece.color, kingside)?;
+        let is_castling = if self.chess960 {
+            mv.to == castling.rook_from
+        } else {
+            mv.to == castling.king_to && mv.from.file().abs_diff(mv.to.file()) == 2
+        };
+        is_castling.then_some(castling)
+    }
+
+    /// Every square the king and rook cross must be empty apart from the two
+    /// of them, and the king may not start, pass or land on an attacked
+    /// square. Attacks are read with both pieces lifted, so a slider behind
+    /// the rook counts.
+    fn is_legal_castling(&self, color: Color, king_from: Square, castling: CastlingMove) -> bool {
+        let rook = Piece {
+            piece_type: PieceType::Rook,
+            color,
+        };
+        if king_from.rank() != home_rank(color)
+            || self.piece_at(castling.rook_from) != Some(rook)
+            || (castling.rook_from.file() > king_from.file()) != castling.kingside
+        {
+            return false;
+        }
+
+        let occupied = self.occupied() & !square_bit(king_from) & !square_bit(castling.rook_from);
+        let king_path = between(king_from, castling.king_to) | square_bit(castling.king_to);
+        let rook_path =
+            between(castling.rook_from, castling.rook_to) | square_bit(castling.rook_to);
+        if (king_path | rook_path) & occupied != 0 {
+            return false;
+        }
+
+        let attacker = color.opposite();
+        squares(king_path | square_bit(king_from))
+            .all(|square| self.attackers_to(square, attacker, occupied) == 0)
+    }
+
+    /// Plays `mv` and passes the turn, resolving the blast of an Atomic
+    /// capture. Pieces reach the Zobrist key through `set_piece`; castling
+    /// rights, en passant and side to move are updated here.
+    pub(super) fn apply_move_unchecked(&mut self, mv: Move, piece: Piece) {
+        let castling = self.castling_move(mv, piece);
+        let captured_on_destination = self
+            .piece_at(mv.to)
+            .filter(|target| target.color != piece.color);
+        let captured = self.captured_by(mv, piece);
+        let previous_castling_rights = self.castling_rights;
+        let previous_checks_given = self.checks_given;
+        let previous_pockets = self.pockets;
+        self.zobrist_key ^= self.en_passant_key();
+
+        if let Some((square, captured_piece)) = captured {
+            if self.variant == Variant::Crazyhouse {
+                let pocketed = if self.is_promoted_piece(square) {
+                    PieceType::Pawn
+                } else {
+                    captured_piece.piece_type
+                };
+                self.add_to_pocket(piece.color, pocketed);
+            }
+            self.promoted &= !square_bit(square);
+        }
+        if self.is_promoted_piece(mv.from) || mv.promotion.is_some() {
+            self.promoted = (self.promoted & !square_bit(mv.from)) | square_bit(mv.to);
+        }
 
         if piece.piece_type == PieceType::King {
             self.clear_castling_rights(piece.color);
@@ -235,9 +698,12 @@ impl GameState {
             && self.piece_at(mv.to).is_none()
             && mv.from.file() != mv.to.file();
 
-        let is_castling_move = piece.piece_type == PieceType::King
-            && mv.from.rank() == mv.to.rank()
-            && (mv.to.file() as i8 - mv.from.file() as i8).unsigned_abs() == 2;
+        self.halfmove_clock =
+            if piece.piece_type == PieceType::Pawn || captured_on_destination.is_some() {
+                0
+            } else {
+                self.halfmove_clock + 1
+            };
 
         if is_en_passant_capture {
             let captured_square =
@@ -245,26 +711,28 @@ impl GameState {
             self.set_piece(captured_square, None);
         }
 
-        self.set_piece(mv.from, None);
-        self.set_piece(mv.to, Some(piece));
-
-        if is_castling_move {
-            let rank = mv.from.rank();
-            let (rook_from, rook_to) = if mv.to.file() > mv.from.file() {
-                (
-                    Square::new(7, rank).expect("valid castling rook source"),
-                    Square::new(5, rank).expect("valid castling rook destination"),
-                )
-            } else {
-                (
-                    Square::new(0, rank).expect("valid castling rook source"),
-                    Square::new(3, rank).expect("valid castling rook destination"),
-                )
-            };
+        let placed_piece = match mv.promotion {
+            Some(piece_type) if piece.piece_type == PieceType::Pawn => Piece {
+                piece_type,
+                color: piece.color,
+            },
+            _ => piece,
+        };
 
-            let rook = self.piece_at(rook_from);
-            self.set_piece(rook_from, None);
-            self.set_piece(rook_to, rook);
+        self.set_piece(mv.from, None);
+        match castling {
+            Some(castling) => {
+                let rook = self.piece_at(castling.rook_from);
+                self.set_piece(castling.rook_from, None);
+                self.set_piece(castling.king_to, Some(piece));
+                self.set_piece(castling.rook_to, rook);
+            }
+            None => self.set_piece(mv.to, Some(placed_piece)),
+        }
+        if self.variant == Variant::Atomic
+            && (captured_on_destination.is_some() || is_en_passant_capture)
+        {
+            self.explode(mv.to);
         }
 
         self.en_passant_target = if piece.piece_type == PieceType::Pawn
@@ -275,265 +743,58 @@ impl GameState {
         } else {
             None
         };
-    }
-
-    fn clear_castling_rights(&mut self, color: Color) {
-        match color {
-            Color::White => {
-                self.castling_rights.white_kingside = false;
-                self.castling_rights.white_queenside = false;
-            }
-            Color::Black => {
-                self.castling_rights.black_kingside = false;
-                self.castling_rights.black_queenside = false;
-            }
-        }
-    }
-
-    fn clear_rook_castling_right(&mut self, color: Color, square: Square) {
-        let home_rank = match color {
-            Color::White => 0,
-            Color::Black => 7,
-        };
-
-        if square.rank() != home_rank {
-            return;
-        }
 
-        match square.file() {
-            0 => match color {
-                Color::White => self.castling_rights.white_queenside = false,
-                Color::Black => self.castling_rights.black_queenside = false,
-            },
-            7 => match color {
-                Color::White => self.castling_rights.white_kingside = false,
-                Color::Black => self.castling_rights.black_kingside = false,
-            },
-            _ => {}
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
         }
+        self.side_to_move = self.side_to_move.opposite();
+        self.count_check();
+
+        self.zobrist_key ^= castling_key(previous_castling_rights)
+            ^ castling_key(self.castling_rights)
+            ^ checks_key(previous_checks_given)
+            ^ checks_key(self.checks_given)
+            ^ pockets_key(previous_pockets)
+            ^ pockets_key(self.pockets)
+            ^ side_key(Color::Black)
+            ^ self.en_passant_key();
+        debug_assert_eq!(
+            self.zobrist_key,
+            self.compute_zobrist_key(),
+            "incremental Zobrist key diverged after {mv}"
+        );
     }
 
-    fn find_king_square(&self, color: Color) -> Option<Square> {
-        self.iter_pieces().find_map(|(square, piece)| {
-            (piece.color == color && piece.piece_type == PieceType::King).then_some(square)
-        })
+    pub(super) fn clear_castling_rights(&mut self, color: Color) {
+        *self.castling_rights.rook_file_mut(color, true) = None;
+        *self.castling_rights.rook_file_mut(color, false) = None;
     }
 
-    fn is_square_attacked_by(&self, target: Square, attacker_color: Color) -> bool {
-        self.iter_pieces().any(|(from, piece)| {
-            if piece.color != attacker_color {
-                return false;
-            }
-            self.can_piece_attack_square(piece, from, target)
-        })
-    }
-
-    fn can_piece_attack_square(&self, piece: Piece, from: Square, target: Square) -> bool {
-        if from == target {
-            return false;
-        }
-
-        let dx = target.file() as i8 - from.file() as i8;
-        let dy = target.rank() as i8 - from.rank() as i8;
-
-        match piece.piece_type {
-            PieceType::Pawn => {
-                let direction = match piece.color {
-                    Color::White => 1,
-                    Color::Black => -1,
-                };
-                dx.unsigned_abs() == 1 && dy == direction
-            }
-            PieceType::Knight => {
-                let adx = dx.unsigned_abs();
-                let ady = dy.unsigned_abs();
-                (adx == 1 && ady == 2) || (adx == 2 && ady == 1)
-            }
-            PieceType::Bishop => {
-                let adx = dx.unsigned_abs();
-                let ady = dy.unsigned_abs();
-                adx == ady && self.is_path_clear(from, target)
-            }
-            PieceType::Rook => (dx == 0 || dy == 0) && self.is_path_clear(from, target),
-            PieceType::Queen => {
-                let adx = dx.unsigned_abs();
-                let ady = dy.unsigned_abs();
-                ((adx == ady) || dx == 0 || dy == 0) && self.is_path_clear(from, target)
-            }
-            PieceType::King => dx.unsigned_abs() <= 1 && dy.unsigned_abs() <= 1,
-        }
-    }
-
-    fn is_legal_piece_move(&self, piece: Piece, from: Square, to: Square) -> bool {
-        if from == to {
-            return false;
+    pub(super) fn clear_rook_castling_right(&mut self, color: Color, square: Square) {
+        if square.rank() != home_rank(color) {
+            return;
         }
 
-        let dx = to.file() as i8 - from.file() as i8;
-        let dy = to.rank() as i8 - from.rank() as i8;
-
-        match piece.piece_type {
-            PieceType::Pawn => self.is_legal_pawn_move(piece.color, from, to, dx, dy),
-            PieceType::Knight => {
-                let adx = dx.unsigned_abs();
-                let ady = dy.unsigned_abs();
-                (adx == 1 && ady == 2) || (adx == 2 && ady == 1)
-            }
-            PieceType::Bishop => {
-                let adx = dx.unsigned_abs();
-                let ady = dy.unsigned_abs();
-                adx == ady && self.is_path_clear(from, to)
-            }
-            PieceType::Rook => (dx == 0 || dy == 0) && self.is_path_clear(from, to),
-            PieceType::Queen => {
-                let adx = dx.unsigned_abs();
-                let ady = dy.unsigned_abs();
-                ((adx == ady) || dx == 0 || dy == 0) && self.is_path_clear(from, to)
-            }
-            PieceType::King => {
-                if dx.unsigned_abs() <= 1 && dy.unsigned_abs() <= 1 {
-                    return true;
-                }
-
-                dy == 0
-                    && dx.unsigned_abs() == 2
-                    && self.is_legal_castling_move(piece.color, from, to)
+        for kingside in [true, false] {
+            let right = self.castling_rights.rook_file_mut(color, kingside);
+            if *right == Some(square.file()) {
+                *right = None;
             }
         }
     }
 
-    fn is_legal_castling_move(&self, color: Color, from: Square, to: Square) -> bool {
-        let home_rank = match color {
-            Color::White => 0,
-            Color::Black => 7,
-        };
-        let king_home = Square::new(4, home_rank).expect("valid king home square");
-        if from != king_home || to.rank() != home_rank {
-            return false;
-        }
-
-        let kingside_target = Square::new(6, home_rank).expect("valid kingside castling target");
-        let queenside_target = Square::new(2, home_rank).expect("valid queenside castling target");
-
-        let (is_kingside, rook_from, king_path_squares) = if to == kingside_target {
-            (
-                true,
-                Square::new(7, home_rank).expect("valid kingside rook square"),
-                [
-                    Square::new(5, home_rank).expect("valid kingside transit square"),
-                    Square::new(6, home_rank).expect("valid kingside target square"),
-                ],
-            )
-        } else if to == queenside_target {
-            (
-                false,
-                Square::new(0, home_rank).expect("valid queenside rook square"),
-                [
-                    Square::new(3, home_rank).expect("valid queenside transit square"),
-                    Square::new(2, home_rank).expect("valid queenside target square"),
-                ],
-            )
-        } else {
-            return false;
-        };
-
-        if !self.has_castling_right(color, is_kingside) {
-            return false;
-        }
-
-        if self.piece_at(rook_from)
-            != Some(Piece {
-                piece_type: PieceType::Rook,
-                color,
-            })
-        {
-            return false;
-        }
-
-        if !self.is_path_clear(from, rook_from) {
-            return false;
-        }
-
-        if self.is_in_check(color) {
-            return false;
-        }
-
-        let attacker = color.opposite();
-        !king_path_squares
-            .iter()
-            .copied()
-            .any(|square| self.is_square_attacked_by(square, attacker))
-    }
-
-    fn has_castling_right(&self, color: Color, kingside: bool) -> bool {
-        match (color, kingside) {
-            (Color::White, true) => self.castling_rights.white_kingside,
-            (Color::White, false) => self.castling_rights.white_queenside,
-            (Color::Black, true) => self.castling_rights.black_kingside,
-            (Color::Black, false) => self.castling_rights.black_queenside,
-        }
+    pub(super) fn find_king_square(&self, color: Color) -> Option<Square> {
+        first_square(self.pieces(color, PieceType::King))
     }
 
-    fn is_legal_pawn_move(&self, color: Color, from: Square, to: Square, dx: i8, dy: i8) -> bool {
-        let direction = match color {
-            Color::White => 1,
-            Color::Black => -1,
-        };
-        let start_rank = match color {
-            Color::White => 1,
-            Color::Black => 6,
-        };
-
-        let target_piece = self.piece_at(to);
-
-        if dx == 0 && dy == direction {
-            return target_piece.is_none();
-        }
-
-        if dx == 0 && dy == 2 * direction && from.rank() == start_rank {
-            let mid_rank = (from.rank() as i8 + direction) as u8;
-            let mid_square = Square::new(from.file(), mid_rank).expect("valid intermediate square");
-            return self.piece_at(mid_square).is_none() && target_piece.is_none();
-        }
-
-        if dx.unsigned_abs() == 1 && dy == direction {
-            if target_piece.is_some_and(|target| target.color != color) {
-                return true;
-            }
-
-            if self.en_passant_target.is_some_and(|target| target == to)
-                && self.piece_at(to).is_none()
-            {
-                let captured_square =
-                    Square::new(to.file(), from.rank()).expect("valid en passant capture square");
-                return self.piece_at(captured_square).is_some_and(|captured| {
-                    captured.piece_type == PieceType::Pawn && captured.color != color
-                });
-            }
-
-            return false;
-        }
-
-        false
+    fn is_square_attacked_by(&self, target: Square, attacker_color: Color) -> bool {
+        self.attackers_to(target, attacker_color, self.occupied()) != 0
     }
+}
 
-    fn is_path_clear(&self, from: Square, to: Square) -> bool {
-        let file_step = (to.file() as i8 - from.file() as i8).signum();
-        let rank_step = (to.rank() as i8 - from.rank() as i8).signum();
-
-        let mut file = from.file() as i8 + file_step;
-        let mut rank = from.rank() as i8 + rank_step;
-
-        while file != to.file() as i8 || rank != to.rank() as i8 {
-            let square =
-                Square::new(file as u8, rank as u8).expect("path square should be on board");
-            if self.piece_at(square).is_some() {
-                return false;
-            }
-            file += file_step;
-            rank += rank_step;
-        }
-
-        true
+pub(super) fn home_rank(color: Color) -> u8 {
+    match color {
+        Color::White => 0,
+        Color::Black => 7,
     }
 }
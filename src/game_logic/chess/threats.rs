@@ -0,0 +1,169 @@
+use crate::game_logic::chess::bitboard::{
+    Bitboard, between, bishop_attacks, rook_attacks, square_bit, squares,
+};
+use crate::game_logic::chess::{Color, GameState, PieceType, Square};
+
+/// A piece that cannot leave the line between its king and an enemy slider
+/// without exposing the king.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Pin {
+    pub pinned: Square,
+    pub pinner: Square,
+    /// Squares from the king (exclusive) to the pinner (inclusive), which
+    /// the pinned piece may still move along.
+    pub line: Vec<Square>,
+}
+
+impl GameState {
+    /// Squares of `color`'s pieces that attack `target` directly. Pieces
+    /// lined up behind another attacker are not counted.
+    pub fn attackers(&self, target: Square, color: Color) -> Vec<Square> {
+        squares(self.attackers_to(target, color, self.occupied())).collect()
+    }
+
+    /// Pieces protecting the piece on `target`: its own side's attackers of
+    /// the square. Empty when the square is empty.
+    pub fn defenders(&self, target: Square) -> Vec<Square> {
+        self.piece_at(target)
+            .map(|piece| self.attackers(target, piece.color))
+            .unwrap_or_default()
+    }
+
+    /// Pins against `color`'s king.
+    pub fn pins(&self, color: Color) -> Vec<Pin> {
+        self.pin_lines(color)
+            .map(|(pinned, pinner, line)| Pin {
+                pinned,
+                pinner,
+                line: squares(line).collect(),
+            })
+            .collect()
+    }
+
+    /// `color`'s pieces, other than the king, that the opponent attacks and
+    /// nothing defends.
+    pub fn hanging_pieces(&self, color: Color) -> Vec<Square> {
+        let occupied = self.occupied();
+        let pieces = self.color_bitboard(color) & !self.pieces(color, PieceType::King);
+        squares(pieces)
+            .filter(|square| {
+                self.attackers_to(*square, color.opposite(), occupied) != 0
+                    && self.attackers_to(*square, color, occupied) == 0
+            })
+            .collect()
+    }
+
+    /// How many of `color`'s pieces attack each square, indexed by
+    /// `Square::to_index`.
+    pub fn control_counts(&self, color: Color) -> [u8; 64] {
+        let occupied = self.occupied();
+        let mut counts = [0; 64];
+        for (index, count) in counts.iter_mut().enumerate() {
+            if let Some(square) = Square::from_index(index) {
+                *count = self.attackers_to(square, color, occupied).count_ones() as u8;
+            }
+        }
+        counts
+    }
+
+    /// Each pin against `color`'s king as the pinned square, the pinner's
+    /// square and the line between the king and the pinner, pinner
+    /// included.
+    pub(super) fn pin_lines(
+        &self,
+        color: Color,
+    ) -> impl Iterator<Item = (Square, Square, Bitboard)> + '_ {
+        let king_square = self.find_king_square(color);
+        let opponent = color.opposite();
+        let queens = self.pieces(opponent, PieceType::Queen);
+        let snipers = king_square.map_or(0, |king_square| {
+            (rook_attacks(king_square, 0) & (self.pieces(opponent, PieceType::Rook) | queens))
+                | (bishop_attacks(king_square, 0)
+                    & (self.pieces(opponent, PieceType::Bishop) | queens))
+        });
+
+        squares(snipers).filter_map(move |sniper| {
+            let line = between(king_square?, sniper);
+            let blockers = line & self.occupied();
+            if blockers.count_ones() != 1 || blockers & self.color_bitboard(color) == 0 {
+                return None;
+            }
+            let pinned = squares(blockers).next()?;
+            Some((pinned, sniper, line | square_bit(sniper)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The e8 rook pins the e2 knight to its king, and the b6 knight
+    /// attacks the c4 bishop.
+    const POSITION: &str = "k3r3/8/1n6/8/2B5/8/4N3/4K3 w - - 0 1";
+
+    fn state(fen: &str) -> GameState {
+        GameState::from_fen(fen).expect("valid FEN")
+    }
+
+    fn square(name: &str) -> Square {
+        name.parse().expect("square")
+    }
+
+    fn squares_named(names: &[&str]) -> Vec<Square> {
+        names.iter().map(|name| square(name)).collect()
+    }
+
+    #[test]
+    fn finds_an_absolute_pin_and_its_line() {
+        let pins = state(POSITION).pins(Color::White);
+        assert_eq!(
+            pins,
+            [Pin {
+                pinned: square("e2"),
+                pinner: square("e8"),
+                line: squares_named(&["e2", "e3", "e4", "e5", "e6", "e7", "e8"]),
+            }]
+        );
+        assert!(state(POSITION).pins(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn two_pieces_on_the_line_are_not_pinned() {
+        let game = state("k3r3/8/8/8/4B3/8/4N3/4K3 w - - 0 1");
+        assert!(game.pins(Color::White).is_empty());
+    }
+
+    #[test]
+    fn attacked_piece_without_defenders_is_hanging() {
+        let game = state(POSITION);
+        assert_eq!(game.attackers(square("c4"), Color::Black), [square("b6")]);
+        assert!(game.defenders(square("c4")).is_empty());
+        assert_eq!(game.hanging_pieces(Color::White), [square("c4")]);
+        assert!(game.hanging_pieces(Color::Black).is_empty());
+
+        let defended = state("k3r3/8/1n6/8/2B5/3P4/4N3/4K3 w - - 0 1");
+        assert_eq!(defended.defenders(square("c4")), [square("d3")]);
+        assert!(defended.hanging_pieces(Color::White).is_empty());
+        assert!(defended.defenders(square("c5")).is_empty());
+    }
+
+    #[test]
+    fn counts_attackers_of_each_square() {
+        let game = state(POSITION);
+        let white = game.control_counts(Color::White);
+        let black = game.control_counts(Color::Black);
+        let count = |counts: &[u8; 64], name: &str| counts[square(name).to_index()];
+
+        assert_eq!(count(&white, "d4"), 1);
+        assert_eq!(count(&white, "d3"), 1);
+        assert_eq!(count(&white, "a8"), 0);
+        // Rook and knight meet on c8; rook and king on b8.
+        assert_eq!(count(&black, "c8"), 2);
+        assert_eq!(count(&black, "b8"), 2);
+        // The rook sees down to the pinned knight, not past it.
+        assert_eq!(count(&black, "e2"), 1);
+        assert_eq!(count(&black, "e1"), 0);
+        assert_eq!(count(&black, "c4"), 1);
+    }
+}
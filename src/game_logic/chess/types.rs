@@ -23,7 +23,29 @@ pub enum PieceType {
     King,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+impl PieceType {
+    pub const ALL: [PieceType; 6] = [
+        PieceType::King,
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Pawn,
+    ];
+
+    pub const PROMOTION_CHOICES: [PieceType; 4] = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ];
+
+    pub fn is_promotion_choice(self) -> bool {
+        Self::PROMOTION_CHOICES.contains(&self)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Piece {
     pub piece_type: PieceType,
     pub color: Color,
@@ -67,10 +89,43 @@ impl Square {
     }
 }
 
+/// A board move, or in Crazyhouse a drop from the pocket, which has no
+/// source square: `from` is then the same as `to`.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Move {
     pub from: Square,
     pub to: Square,
+    pub promotion: Option<PieceType>,
+    pub drop: Option<PieceType>,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Self {
+        Self {
+            from,
+            to,
+            promotion: None,
+            drop: None,
+        }
+    }
+
+    pub fn with_promotion(from: Square, to: Square, promotion: PieceType) -> Self {
+        Self {
+            from,
+            to,
+            promotion: Some(promotion),
+            drop: None,
+        }
+    }
+
+    pub fn drop(piece_type: PieceType, to: Square) -> Self {
+        Self {
+            from: to,
+            to,
+            promotion: None,
+            drop: Some(piece_type),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -80,10 +135,48 @@ pub enum MoveError {
     DestinationOccupiedByOwnPiece,
     IllegalPieceMovement,
     KingWouldBeInCheck,
+    PromotionRequired,
+    InvalidPromotion,
+    KingCannotCapture,
+    ExplodesOwnKing,
+    NotInPocket,
+    DropOnOccupiedSquare,
+    PawnDropOnBackRank,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum GameOutcome {
-    Checkmate { winner: Color },
+    Checkmate {
+        winner: Color,
+    },
     Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    InsufficientMaterial,
+    KingOfTheHill {
+        winner: Color,
+    },
+    ThreeCheck {
+        winner: Color,
+    },
+    KingExploded {
+        winner: Color,
+    },
+    /// The loser's flag fell.
+    Timeout {
+        winner: Color,
+    },
+    /// A flag fell, but the opponent had nothing left to mate with.
+    TimeoutVsInsufficientMaterial,
+    Resignation {
+        winner: Color,
+    },
+    /// The loser broke the rules of play, e.g. an engine answering with an
+    /// illegal move or none at all.
+    Forfeit {
+        winner: Color,
+    },
+    DrawByAgreement,
 }
@@ -0,0 +1,219 @@
+use std::time::Instant;
+
+use crate::game_logic::chess::uci::client::{UciEngineConfig, UciEngineProcess, UciError};
+use crate::game_logic::chess::{Color, GameOutcome, GameResult, GameState, PgnGame, SearchLimits};
+
+/// Two engines playing `games` games from `openings`. Each opening is
+/// played twice in a row with colors swapped, so odd game counts give the
+/// first engine White once more.
+#[derive(Clone)]
+pub struct MatchConfig {
+    pub engines: [UciEngineConfig; 2],
+    /// Names written to the PGN `White`/`Black` tags.
+    pub names: [String; 2],
+    pub openings: Vec<GameState>,
+    pub games: usize,
+    pub limits: SearchLimits,
+}
+
+/// Results from the first engine's point of view.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, a draw counting half.
+    pub fn score_fraction(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (f64::from(self.wins) + f64::from(self.draws) / 2.0) / f64::from(self.games())
+    }
+
+    /// The rating difference that makes `score_fraction` the expected
+    /// score under the logistic Elo model. `None` after a clean sweep
+    /// either way, where the estimate is unbounded.
+    pub fn elo_difference(&self) -> Option<f64> {
+        let score = self.score_fraction();
+        if score <= 0.0 || score >= 1.0 {
+            return None;
+        }
+        Some(400.0 * (score / (1.0 - score)).log10())
+    }
+
+    fn record(&mut self, result: GameResult, first_engine_color: Color) {
+        match (result, first_engine_color) {
+            (GameResult::WhiteWins, Color::White) | (GameResult::BlackWins, Color::Black) => {
+                self.wins += 1;
+            }
+            (GameResult::WhiteWins, Color::Black) | (GameResult::BlackWins, Color::White) => {
+                self.losses += 1;
+            }
+            (GameResult::Draw | GameResult::Ongoing, _) => self.draws += 1,
+        }
+    }
+}
+
+/// Plays the match, handing each finished game and the running score to
+/// `on_game`. Games end by the rules in `GameState::game_outcome`, or by a
+/// claimable draw, which the arbiter takes for both sides. An engine that
+/// answers with an illegal move, or none at all, loses the game, and so
+/// does one that has to be stopped at the connection's `move_deadline`:
+/// on time, or a draw when its opponent could never mate. One that does
+/// not answer even then ends the match with `UciError::Timeout`.
+pub fn play_match(
+    config: &MatchConfig,
+    mut on_game: impl FnMut(&PgnGame, MatchScore),
+) -> Result<MatchScore, UciError> {
+    let mut engines = [
+        UciEngineProcess::spawn(&config.engines[0])?,
+        UciEngineProcess::spawn(&config.engines[1])?,
+    ];
+    let mut score = MatchScore::default();
+
+    for game_index in 0..config.games {
+        let (white, opening_index) = pairing(game_index, config.openings.len());
+        let Some(opening) = config.openings.get(opening_index) else {
+            break;
+        };
+        let first_engine_color = if white == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        for engine in &mut engines {
+            engine.connection().new_game()?;
+        }
+        let mut game = play_game(&mut engines, white, opening.clone(), config.limits)?;
+        game.set_tag("Event", "Engine match");
+        game.set_tag("Round", &(game_index + 1).to_string());
+        game.set_tag("White", &config.names[white]);
+        game.set_tag("Black", &config.names[1 - white]);
+
+        score.record(game.result, first_engine_color);
+        on_game(&game, score);
+    }
+    Ok(score)
+}
+
+/// The engine playing White in game `game_index`, and the index of its
+/// opening. Engine 0 plays White in even games.
+fn pairing(game_index: usize, opening_count: usize) -> (usize, usize) {
+    (game_index % 2, (game_index / 2) % opening_count.max(1))
+}
+
+/// One game with `engines[white]` playing White.
+fn play_game(
+    engines: &mut [UciEngineProcess; 2],
+    white: usize,
+    opening: GameState,
+    limits: SearchLimits,
+) -> Result<PgnGame, UciError> {
+    let mut state = opening.clone();
+    let mut game = PgnGame::new(opening);
+
+    // `Termination` takes the PGN standard's values.
+    let (outcome, termination) = loop {
+        if let Some(outcome) = state.game_outcome() {
+            break (outcome, "normal");
+        }
+        if let Some(outcome) = state.claimable_draw() {
+            break (outcome, "adjudication");
+        }
+
+        let side_to_move = state.side_to_move();
+        let engine_index = match side_to_move {
+            Color::White => white,
+            Color::Black => 1 - white,
+        };
+        let forfeit = (
+            GameOutcome::Forfeit {
+                winner: side_to_move.opposite(),
+            },
+            "rules infraction",
+        );
+        let connection = engines[engine_index].connection();
+        let started = Instant::now();
+        let best_move = match connection.search(&state, limits) {
+            Ok(result) => result.best_move,
+            Err(UciError::IllegalBestMove(_)) => None,
+            Err(err) => return Err(err),
+        };
+        if started.elapsed() > connection.move_deadline(limits) {
+            let winner = side_to_move.opposite();
+            let outcome = if state.can_possibly_mate(winner) {
+                GameOutcome::Timeout { winner }
+            } else {
+                GameOutcome::TimeoutVsInsufficientMaterial
+            };
+            break (outcome, "time forfeit");
+        }
+        let Some(mv) = best_move else {
+            break forfeit;
+        };
+        if state.apply_move(mv).is_err() {
+            break forfeit;
+        }
+        game.moves.push(mv);
+    };
+
+    game.result = GameResult::from_outcome(Some(outcome));
+    game.set_tag("Termination", termination);
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(wins: u32, draws: u32, losses: u32) -> MatchScore {
+        MatchScore {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn elo_difference_follows_the_score() {
+        assert_eq!(score(3, 4, 3).elo_difference(), Some(0.0));
+        assert_eq!(score(0, 0, 0).elo_difference(), Some(0.0));
+        // 75% is a 191 point edge, and the mirror image a deficit.
+        let ahead = score(3, 0, 1).elo_difference().expect("finite estimate");
+        assert!((ahead - 190.85).abs() < 0.01, "{ahead}");
+        assert_eq!(score(1, 0, 3).elo_difference(), Some(-ahead));
+        // A clean sweep has no finite estimate.
+        assert_eq!(score(5, 0, 0).elo_difference(), None);
+        assert_eq!(score(0, 0, 5).elo_difference(), None);
+        assert!(score(5, 1, 0).elo_difference().is_some());
+    }
+
+    #[test]
+    fn colors_alternate_and_each_opening_is_played_twice() {
+        let games: Vec<_> = (0..7).map(|game_index| pairing(game_index, 3)).collect();
+        assert_eq!(
+            games,
+            [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2), (0, 0)]
+        );
+        assert_eq!(pairing(5, 0), (1, 0));
+    }
+
+    #[test]
+    fn results_are_scored_for_the_first_engine() {
+        let mut score = MatchScore::default();
+        score.record(GameResult::WhiteWins, Color::White);
+        score.record(GameResult::WhiteWins, Color::Black);
+        score.record(GameResult::BlackWins, Color::Black);
+        score.record(GameResult::Draw, Color::White);
+        assert_eq!(score, self::score(2, 1, 1));
+        assert_eq!(score.score_fraction(), 0.625);
+    }
+}
@@ -0,0 +1,673 @@
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::game_logic::chess::engine::MATE_SCORE;
+use crate::game_logic::chess::{GameState, SearchLimits, SearchResult, Variant};
+
+/// Used when a search request carries neither a depth nor a time limit.
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(1);
+/// How long an engine may take to answer `uci` or `isready`.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often a search checks whether it has been cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// Time an engine may go over its move time, for process and pipe delays,
+/// before it is told to stop.
+const MOVE_TIME_GRACE: Duration = Duration::from_secs(1);
+/// Move time allowed for a search limited only by depth.
+const DEPTH_SEARCH_TIME_LIMIT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UciError {
+    Spawn(String),
+    Io(String),
+    EngineExited,
+    /// No answer within the time allowed for it.
+    Timeout,
+    IllegalBestMove(String),
+    /// The engine does not offer the variant the game is played in.
+    UnsupportedVariant(String),
+}
+
+/// How to start an external engine and which `setoption` values to send
+/// after the handshake.
+#[derive(Debug, Clone, Default)]
+pub struct UciEngineConfig {
+    pub path: PathBuf,
+    pub args: Vec<String>,
+    pub options: Vec<(String, String)>,
+}
+
+impl UciEngineConfig {
+    /// Parses `Name=value` pairs separated by commas or semicolons, as typed
+    /// into the chess panel.
+    pub fn parse_options(text: &str) -> Vec<(String, String)> {
+        text.split([',', ';'])
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+            .filter(|(name, _)| !name.is_empty())
+            .collect()
+    }
+}
+
+/// The UCI conversation with an engine over any line-based reader and
+/// writer, so it can be driven by a child process or a scripted stand-in.
+/// Lines are read on a thread of their own so that every wait can give up.
+pub struct UciConnection<W> {
+    lines: Receiver<Result<String, String>>,
+    writer: W,
+    response_timeout: Duration,
+    move_time_grace: Duration,
+    /// Option names the engine listed in its handshake.
+    options: Vec<String>,
+    /// The `var` values of its `UCI_Variant` option.
+    variants: Vec<String>,
+    /// What `UCI_Chess960` and `UCI_Variant` were last set to.
+    chess960: bool,
+    variant: Variant,
+}
+
+impl<W: Write> UciConnection<W> {
+    pub fn new(reader: impl BufRead + Send + 'static, writer: W) -> Self {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || read_lines(reader, &sender));
+        Self {
+            lines,
+            writer,
+            response_timeout: RESPONSE_TIMEOUT,
+            move_time_grace: MOVE_TIME_GRACE,
+            options: Vec::new(),
+            variants: Vec::new(),
+            chess960: false,
+            variant: Variant::Standard,
+        }
+    }
+
+    /// Sends `uci` and waits for `uciok`, noting the options the engine
+    /// lists on the way.
+    pub fn handshake(&mut self) -> Result<(), UciError> {
+        self.send("uci")?;
+        loop {
+            let line = self.read_line()?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("uciok") => return Ok(()),
+                Some("option") => self.note_option(tokens),
+                _ => {}
+            }
+        }
+    }
+
+    /// Records `option name <name> type ... [var <value>]...`.
+    fn note_option<'a>(&mut self, tokens: impl Iterator<Item = &'a str>) {
+        let mut name = Vec::new();
+        let mut field = None;
+        for token in tokens {
+            match token {
+                "name" | "type" | "default" | "min" | "max" | "var" => field = Some(token),
+                _ if field == Some("name") => name.push(token),
+                _ if field == Some("var") && name == ["UCI_Variant"] => {
+                    self.variants.push(token.to_owned());
+                }
+                _ => {}
+            }
+        }
+        self.options.push(name.join(" "));
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), UciError> {
+        self.send(&format!("setoption name {name} value {value}"))
+    }
+
+    /// Sends `isready` and waits for `readyok`.
+    pub fn wait_ready(&mut self) -> Result<(), UciError> {
+        self.send("isready")?;
+        loop {
+            if self.read_line()?.trim() == "readyok" {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn new_game(&mut self) -> Result<(), UciError> {
+        self.send("ucinewgame")?;
+        self.wait_ready()
+    }
+
+    /// Sends the game so far and searches it. `info` lines fill in depth,
+    /// score, nodes and principal variation; `bestmove` is checked against
+    /// the position before it is returned.
+    pub fn search(
+        &mut self,
+        state: &GameState,
+        limits: SearchLimits,
+    ) -> Result<SearchResult, UciError> {
+        self.search_cancellable(state, limits, &AtomicBool::new(false))
+    }
+
+    /// How long a search under `limits` may run before the engine is told
+    /// to stop: its move time and a little grace. An engine that still has
+    /// not answered `response_timeout` after that has stopped responding.
+    pub fn move_deadline(&self, limits: SearchLimits) -> Duration {
+        move_time(limits).map_or(DEPTH_SEARCH_TIME_LIMIT, |move_time| {
+            move_time + self.move_time_grace
+        })
+    }
+
+    /// `search` that sends `stop` once `cancelled` is set or the
+    /// `move_deadline` passes, then still reads up to the engine's
+    /// `bestmove` so the next search starts clean.
+    pub fn search_cancellable(
+        &mut self,
+        state: &GameState,
+        limits: SearchLimits,
+        cancelled: &AtomicBool,
+    ) -> Result<SearchResult, UciError> {
+        self.configure_variant(state)?;
+        self.send(&position_command(state))?;
+        self.send(&go_command(limits))?;
+
+        let mut result = SearchResult::default();
+        let mut deadline = Instant::now() + self.move_deadline(limits);
+        let mut stopped = false;
+        loop {
+            let now = Instant::now();
+            if !stopped && (cancelled.load(Ordering::Relaxed) || now >= deadline) {
+                self.send("stop")?;
+                stopped = true;
+                deadline = now + self.response_timeout;
+            } else if stopped && now >= deadline {
+                return Err(UciError::Timeout);
+            }
+            let wait_until = deadline.min(now + CANCEL_POLL_INTERVAL);
+            let Some(line) = self.read_line_until(wait_until)? else {
+                continue;
+            };
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("info") => apply_info(&mut result, tokens),
+                Some("bestmove") => {
+                    result.best_move = match tokens.next() {
+                        None | Some("(none)") | Some("0000") => None,
+                        Some(text) => Some(
+                            state
+                                .parse_uci(text)
+                                .map_err(|_| UciError::IllegalBestMove(text.to_owned()))?,
+                        ),
+                    };
+                    return Ok(result);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Sets `UCI_Chess960` and `UCI_Variant` for the game in `state` when
+    /// they change. A Chess960 or variant game the engine did not list an
+    /// option for is refused rather than sent as a position it would read
+    /// differently.
+    fn configure_variant(&mut self, state: &GameState) -> Result<(), UciError> {
+        let chess960 = state.is_chess960();
+        if chess960 != self.chess960 {
+            if !self.options.iter().any(|name| name == "UCI_Chess960") {
+                return Err(UciError::UnsupportedVariant("Chess960".to_owned()));
+            }
+            self.set_option("UCI_Chess960", if chess960 { "true" } else { "false" })?;
+            self.chess960 = chess960;
+        }
+
+        let variant = state.variant();
+        if variant != self.variant {
+            let name = uci_variant_name(variant);
+            if !self.variants.iter().any(|offered| offered == name) {
+                return Err(UciError::UnsupportedVariant(variant.name().to_owned()));
+            }
+            self.set_option("UCI_Variant", name)?;
+            self.variant = variant;
+        }
+        Ok(())
+    }
+
+    pub fn quit(&mut self) -> Result<(), UciError> {
+        self.send("quit")
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), UciError> {
+        writeln!(self.writer, "{command}")
+            .and_then(|()| self.writer.flush())
+            .map_err(|err| UciError::Io(err.to_string()))
+    }
+
+    /// The next line, waiting at most `response_timeout` for it.
+    fn read_line(&mut self) -> Result<String, UciError> {
+        self.read_line_until(Instant::now() + self.response_timeout)?
+            .ok_or(UciError::Timeout)
+    }
+
+    /// The next line, or `None` if none arrives before `deadline`.
+    fn read_line_until(&mut self, deadline: Instant) -> Result<Option<String>, UciError> {
+        match self
+            .lines
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            Ok(line) => line.map(Some).map_err(UciError::Io),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(UciError::EngineExited),
+        }
+    }
+}
+
+/// Forwards lines from `reader` until it ends, fails, or the connection is
+/// gone.
+fn read_lines(mut reader: impl BufRead, sender: &Sender<Result<String, String>>) {
+    loop {
+        let mut line = String::new();
+        let line = match reader.read_line(&mut line) {
+            Ok(0) => return,
+            Ok(_) => Ok(line),
+            Err(err) => Err(err.to_string()),
+        };
+        let failed = line.is_err();
+        if sender.send(line).is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// `position fen <start> moves <uci>...` for the game in `state`, so the
+/// engine sees the whole move history for its repetition checks.
+pub fn position_command(state: &GameState) -> String {
+    let mut initial = state.clone();
+    while initial.undo_move().is_some() {}
+
+    let mut command = format!("position fen {}", initial.to_fen());
+    if !state.move_history().is_empty() {
+        command.push_str(" moves");
+        for record in state.move_history() {
+            command.push_str(&format!(" {}", record.mv));
+        }
+    }
+    command
+}
+
+/// The `UCI_Variant` value multi-variant engines such as Fairy-Stockfish
+/// use for `variant`.
+fn uci_variant_name(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Standard => "chess",
+        Variant::KingOfTheHill => "kingofthehill",
+        Variant::ThreeCheck => "3check",
+        Variant::Atomic => "atomic",
+        Variant::Crazyhouse => "crazyhouse",
+    }
+}
+
+pub fn go_command(limits: SearchLimits) -> String {
+    let mut command = "go".to_owned();
+    if let Some(depth) = limits.max_depth {
+        command.push_str(&format!(" depth {depth}"));
+    }
+    if let Some(move_time) = move_time(limits) {
+        command.push_str(&format!(" movetime {}", move_time.as_millis()));
+    }
+    command
+}
+
+/// The `movetime` sent for `limits`, `None` for a search limited only by
+/// depth.
+fn move_time(limits: SearchLimits) -> Option<Duration> {
+    match (limits.max_depth, limits.move_time) {
+        (_, Some(move_time)) => Some(move_time),
+        (None, None) => Some(DEFAULT_MOVE_TIME),
+        (Some(_), None) => None,
+    }
+}
+
+fn apply_info<'a>(result: &mut SearchResult, mut tokens: impl Iterator<Item = &'a str>) {
+    while let Some(token) = tokens.next() {
+        match token {
+            "depth" => {
+                if let Some(depth) = tokens.next().and_then(|value| value.parse().ok()) {
+                    result.depth = depth;
+                }
+            }
+            "nodes" => {
+                if let Some(nodes) = tokens.next().and_then(|value| value.parse().ok()) {
+                    result.nodes = nodes;
+                }
+            }
+            "time" => {
+                if let Some(millis) = tokens.next().and_then(|value| value.parse().ok()) {
+                    result.elapsed = Duration::from_millis(millis);
+                }
+            }
+            "score" => {
+                let kind = tokens.next();
+                let value = tokens.next().and_then(|value| value.parse::<i32>().ok());
+                match (kind, value) {
+                    (Some("cp"), Some(centipawns)) => result.score = centipawns,
+                    (Some("mate"), Some(moves)) if moves > 0 => {
+                        result.score = MATE_SCORE - (2 * moves - 1);
+                    }
+                    (Some("mate"), Some(moves)) => result.score = -MATE_SCORE - 2 * moves,
+                    _ => {}
+                }
+            }
+            "pv" => {
+                result.principal_variation =
+                    tokens.by_ref().filter_map(|mv| mv.parse().ok()).collect();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// An engine child process.
+pub struct UciEngineProcess {
+    child: Child,
+    connection: UciConnection<ChildStdin>,
+}
+
+impl UciEngineProcess {
+    /// Spawns the engine, completes the handshake and sends the configured
+    /// options.
+    pub fn spawn(config: &UciEngineConfig) -> Result<Self, UciError> {
+        let mut child = Command::new(&config.path)
+            .args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| UciError::Spawn(format!("{}: {err}", config.path.display())))?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(UciError::Spawn("engine pipes unavailable".to_owned()));
+        };
+
+        let mut process = Self {
+            child,
+            connection: UciConnection::new(BufReader::new(stdout), stdin),
+        };
+        process.connection.handshake()?;
+        for (name, value) in &config.options {
+            process.connection.set_option(name, value)?;
+        }
+        process.connection.new_game()?;
+        Ok(process)
+    }
+
+    pub fn connection(&mut self) -> &mut UciConnection<ChildStdin> {
+        &mut self.connection
+    }
+}
+
+impl Drop for UciEngineProcess {
+    fn drop(&mut self) {
+        let _ = self.connection.quit();
+        if !matches!(self.child.try_wait(), Ok(Some(_))) {
+            thread::sleep(Duration::from_millis(50));
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+pub(in crate::game_logic::chess) struct UciSearchRequest {
+    pub(in crate::game_logic::chess) state: GameState,
+    pub(in crate::game_logic::chess) limits: SearchLimits,
+    pub(in crate::game_logic::chess) reply: Sender<Result<SearchResult, UciError>>,
+    pub(in crate::game_logic::chess) cancelled: Arc<AtomicBool>,
+}
+
+/// An external engine owned by a worker thread, so spawning, the handshake
+/// and every search stay off the UI thread.
+pub struct UciEngine {
+    requests: Sender<UciSearchRequest>,
+    config: UciEngineConfig,
+}
+
+impl UciEngine {
+    pub fn start(config: UciEngineConfig) -> Self {
+        let (requests, receiver) = mpsc::channel::<UciSearchRequest>();
+        let worker_config = config.clone();
+        thread::spawn(move || run_worker(&worker_config, &receiver));
+        Self { requests, config }
+    }
+
+    pub fn config(&self) -> &UciEngineConfig {
+        &self.config
+    }
+
+    pub(in crate::game_logic::chess) fn submit(&self, request: UciSearchRequest) {
+        let reply = request.reply.clone();
+        if self.requests.send(request).is_err() {
+            let _ = reply.send(Err(UciError::EngineExited));
+        }
+    }
+}
+
+fn run_worker(config: &UciEngineConfig, requests: &Receiver<UciSearchRequest>) {
+    let mut process = UciEngineProcess::spawn(config);
+    for request in requests {
+        if request.cancelled.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        let result = match &mut process {
+            Ok(process) => process.connection().search_cancellable(
+                &request.state,
+                request.limits,
+                &request.cancelled,
+            ),
+            Err(err) => Err(err.clone()),
+        };
+        if matches!(
+            result,
+            Err(UciError::EngineExited | UciError::Io(_) | UciError::Timeout)
+        ) {
+            process = Err(UciError::EngineExited);
+        }
+        let _ = request.reply.send(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, PipeWriter};
+
+    use super::*;
+
+    fn scripted(output: &str) -> UciConnection<Vec<u8>> {
+        UciConnection::new(Cursor::new(output.to_owned().into_bytes()), Vec::new())
+    }
+
+    /// A connection to an engine that never says anything, and the pipe end
+    /// that keeps it from seeing end of file.
+    fn silent() -> (UciConnection<Vec<u8>>, PipeWriter) {
+        let (reader, writer) = std::io::pipe().expect("pipe");
+        let mut connection = UciConnection::new(BufReader::new(reader), Vec::new());
+        connection.response_timeout = Duration::from_millis(20);
+        connection.move_time_grace = Duration::from_millis(10);
+        (connection, writer)
+    }
+
+    fn sent(connection: &UciConnection<Vec<u8>>) -> Vec<&str> {
+        std::str::from_utf8(&connection.writer)
+            .expect("utf-8")
+            .lines()
+            .collect()
+    }
+
+    #[test]
+    fn handshake_waits_for_uciok_and_readyok() {
+        let mut connection = scripted(
+            "id name Scripted\noption name Hash type spin default 16 min 1 max 64\nuciok\nreadyok\n",
+        );
+        connection.handshake().expect("uciok");
+        connection.set_option("Hash", "32").expect("sent");
+        connection.wait_ready().expect("readyok");
+        assert_eq!(
+            sent(&connection),
+            ["uci", "setoption name Hash value 32", "isready"]
+        );
+    }
+
+    #[test]
+    fn chess960_game_sets_uci_chess960() {
+        let state = GameState::new_chess960(0);
+        let mut connection =
+            scripted("option name UCI_Chess960 type check default false\nuciok\nbestmove (none)\n");
+        connection.handshake().expect("uciok");
+        connection
+            .search(&state, SearchLimits::default())
+            .expect("bestmove");
+        assert_eq!(
+            sent(&connection)[1],
+            "setoption name UCI_Chess960 value true"
+        );
+    }
+
+    #[test]
+    fn chess960_game_is_refused_without_uci_chess960() {
+        let state = GameState::new_chess960(0);
+        let mut connection = scripted("uciok\n");
+        connection.handshake().expect("uciok");
+        assert_eq!(
+            connection.search(&state, SearchLimits::default()).err(),
+            Some(UciError::UnsupportedVariant("Chess960".to_owned()))
+        );
+        assert_eq!(sent(&connection), ["uci"]);
+    }
+
+    #[test]
+    fn variant_game_sets_uci_variant_the_engine_offers() {
+        let state = GameState::new_start_position().with_variant(Variant::Crazyhouse);
+        let mut connection = scripted(
+            "option name UCI_Variant type combo default chess var chess var crazyhouse\n\
+             uciok\nbestmove e2e4\n",
+        );
+        connection.handshake().expect("uciok");
+        connection
+            .search(&state, SearchLimits::default())
+            .expect("bestmove");
+        assert_eq!(
+            sent(&connection)[1],
+            "setoption name UCI_Variant value crazyhouse"
+        );
+
+        let atomic = GameState::new_start_position().with_variant(Variant::Atomic);
+        assert_eq!(
+            connection.search(&atomic, SearchLimits::default()).err(),
+            Some(UciError::UnsupportedVariant("Atomic".to_owned()))
+        );
+    }
+
+    #[test]
+    fn handshake_times_out_on_a_silent_engine() {
+        let (mut connection, _writer) = silent();
+        assert_eq!(connection.handshake(), Err(UciError::Timeout));
+    }
+
+    #[test]
+    fn search_reads_info_and_bestmove() {
+        let state = GameState::new_start_position();
+        let mut connection = scripted(
+            "info depth 1 score cp 12\n\
+             info depth 3 score cp 25 nodes 1200 time 15 pv e2e4 e7e5 g1f3\n\
+             bestmove e2e4 ponder e7e5\n",
+        );
+        let result = connection
+            .search(&state, SearchLimits::default())
+            .expect("bestmove");
+        assert_eq!(result.best_move, Some("e2e4".parse().expect("move")));
+        assert_eq!(result.depth, 3);
+        assert_eq!(result.score, 25);
+        assert_eq!(result.nodes, 1200);
+        assert_eq!(result.elapsed, Duration::from_millis(15));
+        assert_eq!(result.principal_variation.len(), 3);
+        assert_eq!(
+            sent(&connection),
+            [
+                "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "go movetime 1000",
+            ]
+        );
+    }
+
+    #[test]
+    fn mate_scores_count_plies_to_mate() {
+        let state = GameState::new_start_position();
+        let mut connection = scripted("info score mate 2\nbestmove e2e4\n");
+        let result = connection
+            .search(&state, SearchLimits::default())
+            .expect("bestmove");
+        assert_eq!(result.score, MATE_SCORE - 3);
+
+        let mut connection = scripted("info score mate -1\nbestmove e2e4\n");
+        let result = connection
+            .search(&state, SearchLimits::default())
+            .expect("bestmove");
+        assert_eq!(result.score, -MATE_SCORE + 2);
+    }
+
+    #[test]
+    fn illegal_bestmove_is_an_error() {
+        let state = GameState::new_start_position();
+        let mut connection = scripted("bestmove e2e5\n");
+        assert_eq!(
+            connection.search(&state, SearchLimits::default()).err(),
+            Some(UciError::IllegalBestMove("e2e5".to_owned()))
+        );
+    }
+
+    #[test]
+    fn no_bestmove_means_no_move() {
+        let state = GameState::new_start_position();
+        let mut connection = scripted("bestmove (none)\n");
+        let result = connection
+            .search(&state, SearchLimits::default())
+            .expect("bestmove");
+        assert_eq!(result.best_move, None);
+    }
+
+    #[test]
+    fn engine_exiting_mid_search_is_an_error() {
+        let state = GameState::new_start_position();
+        let mut connection = scripted("info depth 1 score cp 10\n");
+        assert_eq!(
+            connection.search(&state, SearchLimits::default()).err(),
+            Some(UciError::EngineExited)
+        );
+    }
+
+    #[test]
+    fn cancelled_search_sends_stop() {
+        let state = GameState::new_start_position();
+        let mut connection = scripted("bestmove e2e4\n");
+        let result = connection
+            .search_cancellable(&state, SearchLimits::default(), &AtomicBool::new(true))
+            .expect("bestmove");
+        assert_eq!(result.best_move, Some("e2e4".parse().expect("move")));
+        assert_eq!(sent(&connection).last(), Some(&"stop"));
+    }
+
+    #[test]
+    fn search_past_its_deadline_is_stopped_then_abandoned() {
+        let state = GameState::new_start_position();
+        let (mut connection, _writer) = silent();
+        let limits = SearchLimits::move_time(Duration::from_millis(10));
+        assert_eq!(connection.move_deadline(limits), Duration::from_millis(20));
+
+        assert_eq!(
+            connection.search(&state, limits).err(),
+            Some(UciError::Timeout)
+        );
+        assert_eq!(sent(&connection).last(), Some(&"stop"));
+    }
+}
@@ -0,0 +1,8 @@
+mod arena;
+mod client;
+mod server;
+
+pub use arena::{MatchConfig, MatchScore, play_match};
+pub(super) use client::UciSearchRequest;
+pub use client::{UciEngine, UciEngineConfig, UciError};
+pub use server::run_uci_engine;
@@ -0,0 +1,336 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::game_logic::chess::engine::search;
+use crate::game_logic::chess::{
+    Color, GameState, SearchLimits, SearchResult, fen_error_message, notation_error_message,
+};
+
+const ENGINE_NAME: &str = concat!("rendering ", env!("CARGO_PKG_VERSION"));
+const ENGINE_AUTHOR: &str = "rendering contributors";
+
+/// Share of the remaining clock spent on one move when `go` only gives
+/// `wtime`/`btime`.
+const MOVES_TO_GO_GUESS: u32 = 30;
+const CLOCK_SAFETY_MARGIN: Duration = Duration::from_millis(50);
+
+/// Speaks UCI on `input`/`output` until `quit` or end of input, answering
+/// `go` with the built-in search on a background thread so `stop` is
+/// handled while it runs. At end of input a bounded search still finishes,
+/// so piped scripts get their `bestmove`.
+pub fn run_uci_engine<W: Write + Send + 'static>(input: impl BufRead, output: W) {
+    let mut server = UciServer {
+        output: Arc::new(Mutex::new(output)),
+        state: GameState::new_start_position(),
+        search: None,
+    };
+
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !server.handle_command(line.trim()) {
+            server.stop_search();
+            return;
+        }
+    }
+    server.finish_search();
+}
+
+struct RunningSearch {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+    bounded: bool,
+}
+
+struct UciServer<W> {
+    output: Arc<Mutex<W>>,
+    state: GameState,
+    search: Option<RunningSearch>,
+}
+
+impl<W: Write + Send + 'static> UciServer<W> {
+    /// Returns `false` once the engine should exit.
+    fn handle_command(&mut self, line: &str) -> bool {
+        let (command, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "uci" => {
+                self.send(&format!("id name {ENGINE_NAME}"));
+                self.send(&format!("id author {ENGINE_AUTHOR}"));
+                self.send("uciok");
+            }
+            "isready" => self.send("readyok"),
+            "ucinewgame" => {
+                self.stop_search();
+                self.state = GameState::new_start_position();
+            }
+            "position" => {
+                self.stop_search();
+                match parse_position(arguments) {
+                    Ok(state) => self.state = state,
+                    Err(err) => self.send(&format!("info string {err}")),
+                }
+            }
+            "go" => {
+                self.stop_search();
+                let limits = parse_go(arguments, self.state.side_to_move());
+                self.start_search(limits);
+            }
+            "stop" => self.stop_search(),
+            "quit" => return false,
+            _ => {}
+        }
+        true
+    }
+
+    fn start_search(&mut self, limits: SearchLimits) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let output = Arc::clone(&self.output);
+        let state = self.state.clone();
+        let bounded = limits.max_depth.is_some() || limits.move_time.is_some();
+
+        let thread = thread::spawn(move || {
+            let result = search(&state, limits, &thread_stop, |result| {
+                write_line(&output, &info_line(result));
+            });
+            let best_move = result
+                .best_move
+                .map_or_else(|| "0000".to_owned(), |mv| mv.to_string());
+            write_line(&output, &format!("bestmove {best_move}"));
+        });
+
+        self.search = Some(RunningSearch {
+            stop,
+            thread,
+            bounded,
+        });
+    }
+
+    /// Stops a running search and waits for its `bestmove`.
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            let _ = search.thread.join();
+        }
+    }
+
+    /// Lets a depth- or time-limited search run to completion; an infinite
+    /// one is stopped.
+    fn finish_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            if !search.bounded {
+                search.stop.store(true, Ordering::Relaxed);
+            }
+            let _ = search.thread.join();
+        }
+    }
+
+    fn send(&self, line: &str) {
+        write_line(&self.output, line);
+    }
+}
+
+fn write_line<W: Write>(output: &Mutex<W>, line: &str) {
+    if let Ok(mut output) = output.lock() {
+        let _ = writeln!(output, "{line}");
+        let _ = output.flush();
+    }
+}
+
+/// `startpos [moves ...]` or `fen <fen> [moves ...]`.
+fn parse_position(arguments: &str) -> Result<GameState, String> {
+    let (setup, moves) = match arguments.split_once(" moves") {
+        Some((setup, moves)) => (setup.trim(), moves),
+        None => (arguments.trim(), ""),
+    };
+
+    let mut state = if setup == "startpos" {
+        GameState::new_start_position()
+    } else if let Some(fen) = setup.strip_prefix("fen") {
+        GameState::from_fen(fen.trim()).map_err(|err| fen_error_message(&err))?
+    } else {
+        return Err(format!("Unknown position '{setup}'"));
+    };
+
+    for text in moves.split_whitespace() {
+        let mv = state
+            .parse_uci(text)
+            .map_err(|err| notation_error_message(&err))?;
+        state
+            .apply_move(mv)
+            .map_err(|_| format!("Illegal move '{text}'"))?;
+    }
+    Ok(state)
+}
+
+/// Reads `depth`, `movetime`, `infinite` and the clock fields. Without a
+/// depth or time the search runs until `stop`.
+fn parse_go(arguments: &str, side_to_move: Color) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut time_left = None;
+    let mut increment = Duration::ZERO;
+    let mut moves_to_go = MOVES_TO_GO_GUESS;
+
+    let mut tokens = arguments.split_whitespace();
+    while let Some(token) = tokens.next() {
+        let mut value = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+        match (token, side_to_move) {
+            ("depth", _) => limits.max_depth = value().map(|depth| depth as u32),
+            ("movetime", _) => limits.move_time = value().map(Duration::from_millis),
+            ("wtime", Color::White) | ("btime", Color::Black) => {
+                time_left = value().map(Duration::from_millis);
+            }
+            ("winc", Color::White) | ("binc", Color::Black) => {
+                increment = value().map_or(Duration::ZERO, Duration::from_millis);
+            }
+            ("movestogo", _) => {
+                moves_to_go = value().map_or(MOVES_TO_GO_GUESS, |moves| moves.max(1) as u32);
+            }
+            ("infinite", _) => return SearchLimits::default(),
+            _ => {}
+        }
+    }
+
+    if limits.move_time.is_none()
+        && let Some(time_left) = time_left
+    {
+        let budget = time_left / moves_to_go + increment / 2;
+        let available = time_left.saturating_sub(CLOCK_SAFETY_MARGIN);
+        limits.move_time = Some(budget.min(available).max(Duration::from_millis(1)));
+    }
+    limits
+}
+
+fn info_line(result: &SearchResult) -> String {
+    let score = match result.mate_in() {
+        Some(moves) => format!("mate {moves}"),
+        None => format!("cp {}", result.score),
+    };
+    let millis = result.elapsed.as_millis();
+    let nodes_per_second = result.nodes as u128 * 1000 / millis.max(1);
+    let line = result
+        .principal_variation
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "info depth {} score {score} nodes {} nps {nodes_per_second} time {millis} pv {line}",
+        result.depth, result.nodes
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Collects engine output where the test can still read it after the
+    /// server has taken ownership of the writer.
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("output lock").write(bytes)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(script: &str) -> Vec<String> {
+        let output = SharedOutput::default();
+        run_uci_engine(Cursor::new(script.to_owned()), output.clone());
+        let bytes = output.0.lock().expect("output lock").clone();
+        String::from_utf8(bytes)
+            .expect("UTF-8 output")
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn parses_startpos_with_moves() {
+        let state = parse_position("startpos moves e2e4 e7e5 g1f3").expect("valid position");
+        assert_eq!(
+            state.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        assert_eq!(
+            parse_position("startpos").expect("valid position").to_fen(),
+            GameState::new_start_position().to_fen()
+        );
+    }
+
+    #[test]
+    fn parses_fen_with_moves() {
+        let state = parse_position("fen 4k3/P7/8/8/8/8/8/4K3 w - - 0 1 moves a7a8q e8d7")
+            .expect("valid position");
+        assert_eq!(state.to_fen(), "Q7/3k4/8/8/8/8/8/4K3 w - - 1 2");
+        assert!(parse_position("fen 4k3/8/8/8/8/8/8/4K3 w - - 0 1 moves e1e3").is_err());
+        assert!(parse_position("fen not a fen").is_err());
+        assert!(parse_position("somewhere").is_err());
+    }
+
+    #[test]
+    fn parses_go_limits() {
+        let limits = parse_go("depth 7", Color::White);
+        assert_eq!(limits.max_depth, Some(7));
+        assert_eq!(limits.move_time, None);
+
+        let limits = parse_go("movetime 250", Color::Black);
+        assert_eq!(limits.move_time, Some(Duration::from_millis(250)));
+
+        // A thirtieth of the side's own clock plus half its increment.
+        let clock = "wtime 60000 btime 3000 winc 1000 binc 0";
+        assert_eq!(
+            parse_go(clock, Color::White).move_time,
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(
+            parse_go(clock, Color::Black).move_time,
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            parse_go("wtime 10000 movestogo 4", Color::White).move_time,
+            Some(Duration::from_millis(2500))
+        );
+        // Never more than the clock minus the safety margin.
+        assert_eq!(
+            parse_go("wtime 60 movestogo 1", Color::White).move_time,
+            Some(Duration::from_millis(10))
+        );
+        assert_eq!(
+            parse_go("depth 3 infinite", Color::White),
+            SearchLimits::default()
+        );
+    }
+
+    #[test]
+    fn answers_go_with_a_legal_bestmove() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let output = run(&format!(
+            "uci\nisready\nposition fen {fen} moves f1c4\ngo depth 3\n"
+        ));
+        assert!(output.iter().any(|line| line == "uciok"));
+        assert!(output.iter().any(|line| line == "readyok"));
+        assert!(output.iter().any(|line| line.starts_with("info depth 3 ")));
+
+        let best_move = output
+            .iter()
+            .find_map(|line| line.strip_prefix("bestmove "))
+            .expect("bestmove line");
+        let mut state = GameState::from_fen(fen).expect("valid FEN");
+        let mv = state.parse_uci("f1c4").expect("legal move");
+        state.apply_move(mv).expect("legal move");
+        let mv = state.parse_uci(best_move).expect("legal UCI move");
+        assert!(state.generate_legal_moves().contains(&mv));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::game_logic::chess::bitboard::{Bitboard, color_index, square_bit};
+use crate::game_logic::chess::{Color, GameOutcome, GameState, PieceType, Square};
+
+/// Checks a side must give to win a Three-check game.
+const CHECKS_TO_WIN: u8 = 3;
+
+/// Rule set a game is played under. King of the Hill and Three-check only
+/// change how a game is won; Atomic changes what a capture does and
+/// Crazyhouse adds drops.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// A king reaching d4, e4, d5 or e5 wins.
+    KingOfTheHill,
+    /// The third check given wins.
+    ThreeCheck,
+    /// Captures explode, and blowing up the enemy king wins.
+    Atomic,
+    /// Captured pieces change sides and may be dropped back on the board.
+    Crazyhouse,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 5] = [
+        Variant::Standard,
+        Variant::KingOfTheHill,
+        Variant::ThreeCheck,
+        Variant::Atomic,
+        Variant::Crazyhouse,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Standard => "Standard",
+            Self::KingOfTheHill => "King of the Hill",
+            Self::ThreeCheck => "Three-check",
+            Self::Atomic => "Atomic",
+            Self::Crazyhouse => "Crazyhouse",
+        }
+    }
+
+    /// Reads a PGN `Variant` tag, ignoring case, spaces and dashes.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized = name
+            .chars()
+            .filter(|symbol| !matches!(symbol, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match normalized.as_str() {
+            "standard" | "chess960" => Some(Self::Standard),
+            "kingofthehill" | "koth" => Some(Self::KingOfTheHill),
+            "threecheck" | "3check" => Some(Self::ThreeCheck),
+            "atomic" => Some(Self::Atomic),
+            "crazyhouse" | "zh" => Some(Self::Crazyhouse),
+            _ => None,
+        }
+    }
+}
+
+fn hill() -> Bitboard {
+    [(3, 3), (4, 3), (3, 4), (4, 4)]
+        .into_iter()
+        .filter_map(|(file, rank)| Square::new(file, rank))
+        .fold(0, |hill, square| hill | square_bit(square))
+}
+
+impl GameState {
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Plays this position under `variant`. Meant for setting up a game
+    /// before any move; check counts read from a FEN are kept only for
+    /// Three-check, and pockets only for Crazyhouse.
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        if variant != Variant::ThreeCheck {
+            self.checks_given = [0; 2];
+        }
+        if variant != Variant::Crazyhouse {
+            self.pockets = [[0; 5]; 2];
+        }
+        self.zobrist_key = self.compute_zobrist_key();
+        self.position_history.clear();
+        self.record_position();
+        self
+    }
+
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.checks_given[color_index(color)]
+    }
+
+    /// Checks `color` still has to give to win a Three-check game.
+    pub fn checks_remaining(&self, color: Color) -> u8 {
+        CHECKS_TO_WIN.saturating_sub(self.checks_given(color))
+    }
+
+    /// A win under the variant's own rules. It is always the side that just
+    /// moved who wins, so the side to move has lost.
+    pub fn variant_outcome(&self) -> Option<GameOutcome> {
+        let colors = [Color::White, Color::Black];
+        match self.variant {
+            Variant::Standard | Variant::Crazyhouse => None,
+            Variant::KingOfTheHill => colors
+                .into_iter()
+                .find(|color| self.pieces(*color, PieceType::King) & hill() != 0)
+                .map(|winner| GameOutcome::KingOfTheHill { winner }),
+            Variant::ThreeCheck => colors
+                .into_iter()
+                .find(|color| self.checks_given(*color) >= CHECKS_TO_WIN)
+                .map(|winner| GameOutcome::ThreeCheck { winner }),
+            Variant::Atomic => colors
+                .into_iter()
+                .find(|color| self.pieces(color.opposite(), PieceType::King) == 0)
+                .map(|winner| GameOutcome::KingExploded { winner }),
+        }
+    }
+
+    /// Counts a check given by the move just played, in Three-check.
+    pub(super) fn count_check(&mut self) {
+        if self.variant == Variant::ThreeCheck && self.is_in_check(self.side_to_move) {
+            self.checks_given[color_index(self.side_to_move.opposite())] += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(fen: &str, variant: Variant) -> GameState {
+        GameState::from_fen(fen)
+            .expect("valid FEN")
+            .with_variant(variant)
+    }
+
+    fn play(state: &mut GameState, text: &str) {
+        let mv = state.parse_san(text).expect("legal move");
+        state.apply_move(mv).expect("legal move");
+    }
+
+    #[test]
+    fn king_on_the_hill_wins() {
+        let fen = "4k3/8/8/8/8/3K4/8/8 w - - 0 1";
+        let mut hill_game = state(fen, Variant::KingOfTheHill);
+        play(&mut hill_game, "Kc4");
+        assert_eq!(hill_game.game_outcome(), None);
+        play(&mut hill_game, "Kd8");
+        play(&mut hill_game, "Kd5");
+        assert_eq!(
+            hill_game.game_outcome(),
+            Some(GameOutcome::KingOfTheHill {
+                winner: Color::White
+            })
+        );
+
+        let mut standard = state(fen, Variant::Standard);
+        play(&mut standard, "Kd4");
+        assert_eq!(standard.variant_outcome(), None);
+    }
+
+    #[test]
+    fn third_check_wins_and_undo_takes_it_back() {
+        let mut game = state("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", Variant::ThreeCheck);
+        play(&mut game, "Ra8+");
+        assert_eq!(game.checks_given(Color::White), 1);
+        assert_eq!(game.checks_remaining(Color::White), 2);
+        assert_eq!(game.game_outcome(), None);
+
+        let mut game = state("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0", Variant::ThreeCheck);
+        play(&mut game, "Ra8+");
+        assert_eq!(game.checks_given(Color::White), 3);
+        assert_eq!(
+            game.game_outcome(),
+            Some(GameOutcome::ThreeCheck {
+                winner: Color::White
+            })
+        );
+
+        game.undo_move().expect("a move to undo");
+        assert_eq!(game.checks_given(Color::White), 2);
+        assert_eq!(game.game_outcome(), None);
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0");
+    }
+
+    #[test]
+    fn check_counts_only_matter_in_three_check() {
+        let mut game = state("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0", Variant::Standard);
+        assert_eq!(game.checks_given(Color::White), 0);
+        play(&mut game, "Ra8+");
+        assert_eq!(game.checks_given(Color::White), 0);
+        assert_eq!(game.game_outcome(), None);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::game_logic::chess::bitboard::{color_index, piece_index};
+use crate::game_logic::chess::crazyhouse::Pockets;
+use crate::game_logic::chess::state::CastlingRights;
+use crate::game_logic::chess::{Color, GameState, Piece, Square};
+
+const SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// Pocket counts beyond this share the last key. Sixteen covers every pawn.
+const MAX_POCKET_COUNT: usize = 16;
+
+/// Random keys XORed together for each feature of a position.
+struct ZobristKeys {
+    /// Indexed by `color_index * 6 + piece_index`, then square.
+    pieces: [[u64; 64]; 12],
+    castling: [u64; 4],
+    en_passant_file: [u64; 8],
+    black_to_move: u64,
+    /// Three-check counts, indexed by color and then checks given minus one.
+    checks: [[u64; 3]; 2],
+    /// Crazyhouse pocket counts, indexed by color, `piece_index` and then
+    /// count minus one.
+    pockets: [[[u64; MAX_POCKET_COUNT]; 5]; 2],
+}
+
+static KEYS: ZobristKeys = generate_keys();
+
+/// Fills the table from a fixed splitmix64 stream, so keys are the same in
+/// every build.
+const fn generate_keys() -> ZobristKeys {
+    let mut state = SEED;
+    let mut keys = ZobristKeys {
+        pieces: [[0; 64]; 12],
+        castling: [0; 4],
+        en_passant_file: [0; 8],
+        black_to_move: 0,
+        checks: [[0; 3]; 2],
+        pockets: [[[0; MAX_POCKET_COUNT]; 5]; 2],
+    };
+
+    let mut kind = 0;
+    while kind < 12 {
+        let mut square = 0;
+        while square < 64 {
+            keys.pieces[kind][square] = splitmix64(&mut state);
+            square += 1;
+        }
+        kind += 1;
+    }
+
+    let mut index = 0;
+    while index < 4 {
+        keys.castling[index] = splitmix64(&mut state);
+        index += 1;
+    }
+
+    let mut file = 0;
+    while file < 8 {
+        keys.en_passant_file[file] = splitmix64(&mut state);
+        file += 1;
+    }
+
+    keys.black_to_move = splitmix64(&mut state);
+
+    let mut color = 0;
+    while color < 2 {
+        let mut count = 0;
+        while count < 3 {
+            keys.checks[color][count] = splitmix64(&mut state);
+            count += 1;
+        }
+        color += 1;
+    }
+
+    let mut color = 0;
+    while color < 2 {
+        let mut kind = 0;
+        while kind < 5 {
+            let mut count = 0;
+            while count < MAX_POCKET_COUNT {
+                keys.pockets[color][kind][count] = splitmix64(&mut state);
+                count += 1;
+            }
+            kind += 1;
+        }
+        color += 1;
+    }
+    keys
+}
+
+pub(super) const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut value = *state;
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+pub(super) fn piece_key(square: Square, piece: Piece) -> u64 {
+    KEYS.pieces[color_index(piece.color) * 6 + piece_index(piece.piece_type)][square.to_index()]
+}
+
+pub(super) fn castling_key(rights: CastlingRights) -> u64 {
+    [
+        rights.white_kingside,
+        rights.white_queenside,
+        rights.black_kingside,
+        rights.black_queenside,
+    ]
+    .into_iter()
+    .zip(KEYS.castling)
+    .filter(|(rook_file, _)| rook_file.is_some())
+    .fold(0, |key, (_, right_key)| key ^ right_key)
+}
+
+/// Zero until a side has given a check, so standard games hash as before.
+pub(super) fn checks_key(checks_given: [u8; 2]) -> u64 {
+    checks_given
+        .into_iter()
+        .zip(KEYS.checks)
+        .filter_map(|(count, keys)| keys.get(usize::from(count).checked_sub(1)?).copied())
+        .fold(0, |key, count_key| key ^ count_key)
+}
+
+/// Zero while both pockets are empty, like `checks_key`.
+pub(super) fn pockets_key(pockets: Pockets) -> u64 {
+    pockets
+        .into_iter()
+        .zip(KEYS.pockets)
+        .flat_map(|(counts, keys)| counts.into_iter().zip(keys))
+        .filter_map(|(count, keys)| {
+            let index = usize::from(count).min(MAX_POCKET_COUNT).checked_sub(1)?;
+            Some(keys[index])
+        })
+        .fold(0, |key, count_key| key ^ count_key)
+}
+
+pub(super) fn side_key(side_to_move: Color) -> u64 {
+    match side_to_move {
+        Color::White => 0,
+        Color::Black => KEYS.black_to_move,
+    }
+}
+
+impl GameState {
+    /// Incrementally updated 64-bit hash of placement, side to move,
+    /// castling rights, Three-check counts, Crazyhouse pockets and the en
+    /// passant file.
+    /// Positions that are the same under the repetition rules share a key.
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_key
+    }
+
+    /// The en passant part of the key: the target's file, but only when a
+    /// pawn of the side to move could take there.
+    pub(super) fn en_passant_key(&self) -> u64 {
+        self.en_passant_target
+            .filter(|target| self.can_capture_en_passant(*target))
+            .map_or(0, |target| KEYS.en_passant_file[target.file() as usize])
+    }
+
+    /// The key built from scratch, for setting up positions and for checking
+    /// the incremental updates.
+    pub(super) fn compute_zobrist_key(&self) -> u64 {
+        let pieces = self
+            .iter_pieces()
+            .fold(0, |key, (square, piece)| key ^ piece_key(square, piece));
+        pieces
+            ^ castling_key(self.castling_rights)
+            ^ checks_key(self.checks_given)
+            ^ pockets_key(self.pockets)
+            ^ self.en_passant_key()
+            ^ side_key(self.side_to_move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_logic::chess::{GameState, Variant};
+
+    /// Plays every legal move, and every reply to it, checking the
+    /// incremental key against a recompute after each move and undo.
+    fn assert_incremental_keys(mut state: GameState) {
+        let key = state.zobrist_key();
+        for mv in state.generate_legal_moves() {
+            state.apply_move(mv).expect("legal move");
+            assert_eq!(
+                state.zobrist_key(),
+                state.compute_zobrist_key(),
+                "after {mv}"
+            );
+            for reply in state.generate_legal_moves() {
+                state.apply_move(reply).expect("legal move");
+                assert_eq!(
+                    state.zobrist_key(),
+                    state.compute_zobrist_key(),
+                    "after {mv} {reply}"
+                );
+                state.undo_move();
+            }
+            state.undo_move();
+            assert_eq!(state.zobrist_key(), key, "after undoing {mv}");
+        }
+    }
+
+    fn state(fen: &str) -> GameState {
+        GameState::from_fen(fen).expect("valid FEN")
+    }
+
+    #[test]
+    fn incremental_key_matches_recompute_through_castling_and_en_passant() {
+        assert_incremental_keys(state(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ));
+        assert_incremental_keys(state("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"));
+    }
+
+    #[test]
+    fn incremental_key_matches_recompute_through_promotions() {
+        assert_incremental_keys(state("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1"));
+    }
+
+    #[test]
+    fn incremental_key_matches_recompute_through_drops_and_checks() {
+        assert_incremental_keys(
+            state("r3k2r/ppp2ppp/8/3p4/4P3/8/PPP2PPP/R3K2R[NPbp] w KQkq - 0 1")
+                .with_variant(Variant::Crazyhouse),
+        );
+        assert_incremental_keys(
+            state("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1 +2+0").with_variant(Variant::ThreeCheck),
+        );
+    }
+
+    #[test]
+    fn transpositions_share_a_key() {
+        let play = |moves: &[&str]| {
+            let mut state = GameState::new_start_position();
+            for text in moves {
+                let mv = state.parse_san(text).expect("legal move");
+                state.apply_move(mv).expect("legal move");
+            }
+            state.zobrist_key()
+        };
+        assert_eq!(
+            play(&["Nf3", "Nf6", "Nc3", "Nc6"]),
+            play(&["Nc3", "Nc6", "Nf3", "Nf6"])
+        );
+        // Same placement, but only the first can take en passant on d6.
+        assert_ne!(
+            play(&["e4", "Nf6", "e5", "d5"]),
+            play(&["e4", "d5", "e5", "Nf6"])
+        );
+    }
+}
@@ -6,11 +6,17 @@ use eframe::wgpu::Features;
 use std::sync::Arc;
 
 mod app;
+mod cli;
 mod game_logic;
 mod render;
 
 fn main() -> eframe::Result {
     env_logger::init();
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(exit_code) = cli::run(&args) {
+        std::process::exit(exit_code);
+    }
+
     let options = eframe::NativeOptions {
         viewport: ViewportBuilder::default().with_inner_size([350.0, 380.0]),
         multisampling: *render::pipeline::SAMPLE_COUNT,